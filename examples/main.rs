@@ -1,6 +1,6 @@
 use raytracer::{
     camera::Camera, color::Color, light::Light, node::Node,
-    obj_file::parse_obj_file, point3d::Point3D, transform::Transform,
+    obj_file::parse_obj_file_with_mtl, point3d::Point3D, transform::Transform,
     vector3d::Vector3D, world::World, FLOAT,
 };
 
@@ -8,7 +8,8 @@ use std::{
     boxed::Box,
     env,
     fs::File,
-    io::{stdout, BufReader, BufWriter, Write},
+    io::{stdout, BufRead, BufReader, BufWriter},
+    path::Path,
 };
 
 use clap::{App, Arg};
@@ -25,27 +26,29 @@ fn main() {
             Arg::with_name("output")
                 .short("o")
                 .value_name("FILE")
-                .help("ppm filename to be output")
+                .help("ppm or png filename to be output")
                 .takes_value(true),
         )
         .get_matches();
 
-    let mut writer: Box<dyn Write> =
-        if let Some(filename) = matches.value_of("output") {
-            Box::new(BufWriter::new(
-                File::create(filename).expect("cannot create file"),
-            ))
-        } else {
-            Box::new(BufWriter::new(stdout()))
-        };
-
     let mut world = World::new();
 
-    let mut reader = BufReader::new(
-        File::open(matches.value_of("input").unwrap())
-            .expect("cannot open file"),
-    );
-    let parser = parse_obj_file(&mut reader);
+    let input = Path::new(matches.value_of("input").unwrap());
+    let mut reader =
+        BufReader::new(File::open(input).expect("cannot open file"));
+    // MTL ファイルは OBJ ファイルと同じディレクトリから探す
+    let mut open_mtl = |name: &str| {
+        let path = input.parent().unwrap_or(Path::new("")).join(name);
+        File::open(path)
+            .map(|f| Box::new(BufReader::new(f)) as Box<dyn BufRead>)
+    };
+    let parser = match parse_obj_file_with_mtl(&mut reader, &mut open_mtl) {
+        Ok(parser) => parser,
+        Err(e) => {
+            eprintln!("cannot parse file: {}", e);
+            std::process::exit(1);
+        }
+    };
     let mut group: Box<Node> = parser.into();
     group.set_transform(
         &Transform::translation(0.0, -10.0, 0.0)
@@ -69,5 +72,10 @@ fn main() {
 
     let canvas = camera.render(&world);
 
-    canvas.to_ppm(&mut writer).expect("write failed");
+    if let Some(filename) = matches.value_of("output") {
+        canvas.save_to_file(filename).expect("write failed");
+    } else {
+        let mut writer = BufWriter::new(stdout());
+        canvas.to_ppm(&mut writer).expect("write failed");
+    }
 }
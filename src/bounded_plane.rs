@@ -0,0 +1,151 @@
+use super::{
+    bounding_box::BoundingBox, intersection::Intersection, material::Material,
+    node::Node, point3d::Point3D, ray::Ray, shape::Shape, vector3d::Vector3D,
+    EPSILON, FLOAT,
+};
+
+/// xz 平面 (y = 0) のうち、x と z が指定した範囲に収まる長方形の部分。
+#[derive(Debug)]
+pub struct BoundedPlane {
+    /// マテリアル
+    material: Material,
+    /// x の最小値
+    x_min: FLOAT,
+    /// x の最大値
+    x_max: FLOAT,
+    /// z の最小値
+    z_min: FLOAT,
+    /// z の最大値
+    z_max: FLOAT,
+}
+
+impl BoundedPlane {
+    /// 新規に BoundedPlane を作成する
+    ///
+    /// # Argumets
+    /// * `x_min` - x の最小値
+    /// * `x_max` - x の最大値
+    /// * `z_min` - z の最小値
+    /// * `z_max` - z の最大値
+    pub fn new(x_min: FLOAT, x_max: FLOAT, z_min: FLOAT, z_max: FLOAT) -> Self {
+        BoundedPlane {
+            material: Material::new(),
+            x_min,
+            x_max,
+            z_min,
+            z_max,
+        }
+    }
+
+    pub fn x_min(&self) -> FLOAT {
+        self.x_min
+    }
+
+    pub fn x_max(&self) -> FLOAT {
+        self.x_max
+    }
+
+    pub fn z_min(&self) -> FLOAT {
+        self.z_min
+    }
+
+    pub fn z_max(&self) -> FLOAT {
+        self.z_max
+    }
+}
+
+impl Shape for BoundedPlane {
+    fn material(&self) -> Option<&Material> {
+        Some(&self.material)
+    }
+
+    fn material_mut(&mut self) -> Option<&mut Material> {
+        Some(&mut self.material)
+    }
+
+    fn local_intersect<'a>(
+        &self,
+        r: &Ray,
+        n: &'a Node,
+    ) -> Vec<Intersection<'a>> {
+        if r.direction().y.abs() < EPSILON {
+            return vec![];
+        }
+
+        let t = -r.origin().y / r.direction().y;
+        let x = r.origin().x + t * r.direction().x;
+        let z = r.origin().z + t * r.direction().z;
+        if x < self.x_min || self.x_max < x || z < self.z_min || self.z_max < z
+        {
+            return vec![];
+        }
+
+        vec![Intersection::new(t, n)]
+    }
+
+    fn local_normal_at(&self, _: &Point3D, _: &Intersection) -> Vector3D {
+        Vector3D::new(0.0, 1.0, 0.0)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point3D::new(self.x_min, 0.0, self.z_min),
+            Point3D::new(self.x_max, 0.0, self.z_max),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_normal_of_a_bounded_plane_is_constant_everywhere() {
+        let p = BoundedPlane::new(-1.0, 1.0, -1.0, 1.0);
+        let dummy_node =
+            Node::new(Box::new(BoundedPlane::new(-1.0, 1.0, -1.0, 1.0)));
+        let i = Intersection::new(0.0, &dummy_node);
+        let n1 = p.local_normal_at(&Point3D::new(0.0, 0.0, 0.0), &i);
+        let n2 = p.local_normal_at(&Point3D::new(0.5, 0.0, -0.5), &i);
+
+        assert_eq!(Vector3D::new(0.0, 1.0, 0.0), n1);
+        assert_eq!(Vector3D::new(0.0, 1.0, 0.0), n2);
+    }
+
+    #[test]
+    fn a_ray_inside_the_bounds_hits() {
+        let dummy_node =
+            Node::new(Box::new(BoundedPlane::new(-1.0, 1.0, -1.0, 1.0)));
+
+        let p = BoundedPlane::new(-1.0, 2.0, -0.5, 0.5);
+        let r = Ray::new(
+            Point3D::new(1.5, 1.0, 0.25),
+            Vector3D::new(0.0, -1.0, 0.0),
+        );
+
+        let xs = p.local_intersect(&r, &*dummy_node);
+        assert_eq!(1, xs.len());
+        assert_eq!(1.0, xs[0].t);
+        assert!(std::ptr::eq(xs[0].object, &*dummy_node));
+    }
+
+    #[test]
+    fn a_ray_outside_the_bounds_misses() {
+        let dummy_node =
+            Node::new(Box::new(BoundedPlane::new(-1.0, 1.0, -1.0, 1.0)));
+
+        let p = BoundedPlane::new(-1.0, 2.0, -0.5, 0.5);
+        let origins = [
+            Point3D::new(2.5, 1.0, 0.0),
+            Point3D::new(-1.5, 1.0, 0.0),
+            Point3D::new(0.0, 1.0, 0.75),
+            Point3D::new(0.0, 1.0, -0.75),
+        ];
+
+        for origin in origins.iter() {
+            let r = Ray::new(origin.clone(), Vector3D::new(0.0, -1.0, 0.0));
+            let xs = p.local_intersect(&r, &dummy_node);
+            assert_eq!(0, xs.len());
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use super::{
+    point3d::Point3D, ray::Ray, transform::Transform, EPSILON, FLOAT, INFINITY,
+};
+
+/// Axis Aligned な Bounding Box
+#[derive(Debug, Clone)]
+pub struct BoundingBox {
+    /// 各軸の最小値
+    min: Point3D,
+    /// 各軸の最大値
+    max: Point3D,
+}
+
+impl BoundingBox {
+    /// 新規に BoundingBox を作成する
+    ///
+    /// # Argumets
+    /// * `min` - 各軸の最小値
+    /// * `max` - 各軸の最大値
+    pub fn new(min: Point3D, max: Point3D) -> Self {
+        BoundingBox { min, max }
+    }
+
+    /// 何も含まない BoundingBox を作成する
+    pub fn empty() -> Self {
+        BoundingBox {
+            min: Point3D::new(INFINITY, INFINITY, INFINITY),
+            max: Point3D::new(-INFINITY, -INFINITY, -INFINITY),
+        }
+    }
+
+    /// 全空間を含む BoundingBox を作成する
+    pub fn infinite() -> Self {
+        BoundingBox {
+            min: Point3D::new(-INFINITY, -INFINITY, -INFINITY),
+            max: Point3D::new(INFINITY, INFINITY, INFINITY),
+        }
+    }
+
+    /// 各軸の最小値を取得する
+    pub fn min(&self) -> &Point3D {
+        &self.min
+    }
+
+    /// 各軸の最大値を取得する
+    pub fn max(&self) -> &Point3D {
+        &self.max
+    }
+
+    /// 全ての軸で有限な範囲を持つか
+    pub fn is_finite(&self) -> bool {
+        self.min.x.is_finite()
+            && self.min.y.is_finite()
+            && self.min.z.is_finite()
+            && self.max.x.is_finite()
+            && self.max.y.is_finite()
+            && self.max.z.is_finite()
+    }
+
+    /// p を含むように self を拡張する
+    ///
+    /// # Argumets
+    /// * `p` - 追加する点
+    pub fn add_point(&mut self, p: &Point3D) {
+        self.min.x = self.min.x.min(p.x);
+        self.min.y = self.min.y.min(p.y);
+        self.min.z = self.min.z.min(p.z);
+        self.max.x = self.max.x.max(p.x);
+        self.max.y = self.max.y.max(p.y);
+        self.max.z = self.max.z.max(p.z);
+    }
+
+    /// other を含むように self を拡張する
+    ///
+    /// # Argumets
+    /// * `other` - 追加する BoundingBox
+    pub fn add_box(&mut self, other: &BoundingBox) {
+        self.add_point(&other.min);
+        self.add_point(&other.max);
+    }
+
+    /// p が self に含まれるか
+    ///
+    /// # Argumets
+    /// * `p` - 判定対象の点
+    pub fn contains_point(&self, p: &Point3D) -> bool {
+        self.min.x <= p.x
+            && p.x <= self.max.x
+            && self.min.y <= p.y
+            && p.y <= self.max.y
+            && self.min.z <= p.z
+            && p.z <= self.max.z
+    }
+
+    /// other が self に完全に含まれるか
+    ///
+    /// # Argumets
+    /// * `other` - 判定対象の BoundingBox
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        self.contains_point(&other.min) && self.contains_point(&other.max)
+    }
+
+    /// Ray が self と交差するか
+    ///
+    /// # Argumets
+    /// * `r` - 判定対象の Ray
+    pub fn intersects(&self, r: &Ray) -> bool {
+        /// Ray の各軸の面との交点となる t を求める。
+        ///
+        /// # Argumets
+        /// * `origin` - Ray の開始点
+        /// * `direction` - Ray の方向
+        /// * `min` - 軸の最小値
+        /// * `max` - 軸の最大値
+        fn check_axis(
+            origin: FLOAT,
+            direction: FLOAT,
+            min: FLOAT,
+            max: FLOAT,
+        ) -> (FLOAT, FLOAT) {
+            let tmin_numerator = min - origin;
+            let tmax_numerator = max - origin;
+
+            let (tmin, tmax) = if direction.abs() >= EPSILON {
+                (tmin_numerator / direction, tmax_numerator / direction)
+            } else {
+                (tmin_numerator * INFINITY, tmax_numerator * INFINITY)
+            };
+
+            if tmin > tmax {
+                (tmax, tmin)
+            } else {
+                (tmin, tmax)
+            }
+        }
+
+        let o = r.origin();
+        let d = r.direction();
+        let (xtmin, xtmax) = check_axis(o.x, d.x, self.min.x, self.max.x);
+        let (ytmin, ytmax) = check_axis(o.y, d.y, self.min.y, self.max.y);
+        let (ztmin, ztmax) = check_axis(o.z, d.z, self.min.z, self.max.z);
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+
+        tmin <= tmax
+    }
+
+    /// 最も長い軸の中点で self を 2 つに分割する
+    pub fn split(&self) -> (BoundingBox, BoundingBox) {
+        let dx = self.max.x - self.min.x;
+        let dy = self.max.y - self.min.y;
+        let dz = self.max.z - self.min.z;
+        let greatest = dx.max(dy).max(dz);
+
+        let mut mid_min = self.min.clone();
+        let mut mid_max = self.max.clone();
+        if greatest == dx {
+            mid_min.x = self.min.x + dx / 2.0;
+            mid_max.x = mid_min.x;
+        } else if greatest == dy {
+            mid_min.y = self.min.y + dy / 2.0;
+            mid_max.y = mid_min.y;
+        } else {
+            mid_min.z = self.min.z + dz / 2.0;
+            mid_max.z = mid_min.z;
+        }
+
+        (
+            BoundingBox::new(self.min.clone(), mid_max),
+            BoundingBox::new(mid_min, self.max.clone()),
+        )
+    }
+
+    /// self に変換を適用した BoundingBox を返す。
+    /// 無限の範囲を持つ場合は変換後も全空間とする。
+    ///
+    /// # Argumets
+    /// * `transform` - 適用する変換
+    pub fn transform(&self, transform: &Transform) -> BoundingBox {
+        if !self.is_finite() {
+            return BoundingBox::infinite();
+        }
+
+        let corners = [
+            Point3D::new(self.min.x, self.min.y, self.min.z),
+            Point3D::new(self.min.x, self.min.y, self.max.z),
+            Point3D::new(self.min.x, self.max.y, self.min.z),
+            Point3D::new(self.min.x, self.max.y, self.max.z),
+            Point3D::new(self.max.x, self.min.y, self.min.z),
+            Point3D::new(self.max.x, self.min.y, self.max.z),
+            Point3D::new(self.max.x, self.max.y, self.min.z),
+            Point3D::new(self.max.x, self.max.y, self.max.z),
+        ];
+
+        let mut result = BoundingBox::empty();
+        for p in &corners {
+            result.add_point(&(transform * p));
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::vector3d::Vector3D, *};
+
+    #[test]
+    fn creating_an_empty_bounding_box() {
+        let b = BoundingBox::empty();
+
+        assert_eq!(INFINITY, b.min().x);
+        assert_eq!(-INFINITY, b.max().x);
+    }
+
+    #[test]
+    fn adding_points_to_an_empty_bounding_box() {
+        let mut b = BoundingBox::empty();
+        b.add_point(&Point3D::new(-5.0, 2.0, 0.0));
+        b.add_point(&Point3D::new(7.0, 0.0, -3.0));
+
+        assert_eq!(Point3D::new(-5.0, 0.0, -3.0), *b.min());
+        assert_eq!(Point3D::new(7.0, 2.0, 0.0), *b.max());
+    }
+
+    #[test]
+    fn adding_one_bounding_box_to_another() {
+        let mut b1 = BoundingBox::new(
+            Point3D::new(-5.0, -2.0, 0.0),
+            Point3D::new(7.0, 4.0, 4.0),
+        );
+        let b2 = BoundingBox::new(
+            Point3D::new(8.0, -7.0, -2.0),
+            Point3D::new(14.0, 2.0, 8.0),
+        );
+        b1.add_box(&b2);
+
+        assert_eq!(Point3D::new(-5.0, -7.0, -2.0), *b1.min());
+        assert_eq!(Point3D::new(14.0, 4.0, 8.0), *b1.max());
+    }
+
+    #[test]
+    fn checking_to_see_if_a_box_contains_a_given_point() {
+        let b = BoundingBox::new(
+            Point3D::new(5.0, -2.0, 0.0),
+            Point3D::new(11.0, 4.0, 7.0),
+        );
+
+        assert!(b.contains_point(&Point3D::new(5.0, -2.0, 0.0)));
+        assert!(b.contains_point(&Point3D::new(11.0, 4.0, 7.0)));
+        assert!(b.contains_point(&Point3D::new(8.0, 1.0, 3.0)));
+        assert!(!b.contains_point(&Point3D::new(3.0, 0.0, 3.0)));
+        assert!(!b.contains_point(&Point3D::new(8.0, -4.0, 3.0)));
+        assert!(!b.contains_point(&Point3D::new(8.0, 1.0, 8.0)));
+    }
+
+    #[test]
+    fn transforming_a_bounding_box() {
+        let b = BoundingBox::new(
+            Point3D::new(-1.0, -1.0, -1.0),
+            Point3D::new(1.0, 1.0, 1.0),
+        );
+        let t = &Transform::rotation_x(std::f64::consts::FRAC_PI_4 as FLOAT)
+            * &Transform::rotation_y(std::f64::consts::FRAC_PI_4 as FLOAT);
+        let b2 = b.transform(&t);
+
+        assert_eq!(Point3D::new(-1.41421, -1.70711, -1.70711), *b2.min());
+        assert_eq!(Point3D::new(1.41421, 1.70711, 1.70711), *b2.max());
+    }
+
+    #[test]
+    fn splitting_a_perfect_cube() {
+        let b = BoundingBox::new(
+            Point3D::new(-1.0, -4.0, -5.0),
+            Point3D::new(9.0, 6.0, 5.0),
+        );
+        let (left, right) = b.split();
+
+        assert_eq!(Point3D::new(-1.0, -4.0, -5.0), *left.min());
+        assert_eq!(Point3D::new(4.0, 6.0, 5.0), *left.max());
+        assert_eq!(Point3D::new(4.0, -4.0, -5.0), *right.min());
+        assert_eq!(Point3D::new(9.0, 6.0, 5.0), *right.max());
+    }
+
+    #[test]
+    fn splitting_a_y_wide_box() {
+        let b = BoundingBox::new(
+            Point3D::new(-1.0, -2.0, -3.0),
+            Point3D::new(5.0, 8.0, 3.0),
+        );
+        let (left, right) = b.split();
+
+        assert_eq!(Point3D::new(-1.0, -2.0, -3.0), *left.min());
+        assert_eq!(Point3D::new(5.0, 3.0, 3.0), *left.max());
+        assert_eq!(Point3D::new(-1.0, 3.0, -3.0), *right.min());
+        assert_eq!(Point3D::new(5.0, 8.0, 3.0), *right.max());
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_a_bounding_box() {
+        let b = BoundingBox::new(
+            Point3D::new(5.0, -2.0, 0.0),
+            Point3D::new(11.0, 4.0, 7.0),
+        );
+
+        let cases = [
+            (
+                Point3D::new(15.0, 1.0, 2.0),
+                Vector3D::new(-1.0, 0.0, 0.0),
+                true,
+            ),
+            (
+                Point3D::new(-5.0, -1.0, 4.0),
+                Vector3D::new(1.0, 0.0, 0.0),
+                true,
+            ),
+            (
+                Point3D::new(7.0, 6.0, 5.0),
+                Vector3D::new(0.0, -1.0, 0.0),
+                true,
+            ),
+            (
+                Point3D::new(9.0, -5.0, 6.0),
+                Vector3D::new(0.0, 1.0, 0.0),
+                true,
+            ),
+            (
+                Point3D::new(8.0, 2.0, 12.0),
+                Vector3D::new(0.0, 0.0, -1.0),
+                true,
+            ),
+            (
+                Point3D::new(6.0, 0.0, -5.0),
+                Vector3D::new(0.0, 0.0, 1.0),
+                true,
+            ),
+            (
+                Point3D::new(8.0, 1.0, 3.5),
+                Vector3D::new(0.0, 0.0, 1.0),
+                true,
+            ),
+            (
+                Point3D::new(9.0, -1.0, -8.0),
+                Vector3D::new(2.0, 4.0, 6.0),
+                false,
+            ),
+            (
+                Point3D::new(8.0, 3.0, -4.0),
+                Vector3D::new(6.0, 2.0, 4.0),
+                false,
+            ),
+            (
+                Point3D::new(9.0, -1.0, -2.0),
+                Vector3D::new(4.0, 6.0, 2.0),
+                false,
+            ),
+            (
+                Point3D::new(4.0, 0.0, 9.0),
+                Vector3D::new(0.0, 0.0, -1.0),
+                false,
+            ),
+            (
+                Point3D::new(8.0, 6.0, -1.0),
+                Vector3D::new(0.0, -1.0, 0.0),
+                false,
+            ),
+            (
+                Point3D::new(12.0, 5.0, 4.0),
+                Vector3D::new(-1.0, 0.0, 0.0),
+                false,
+            ),
+        ];
+        for (origin, direction, expected) in cases.iter() {
+            let mut direction = direction.clone();
+            direction.normalize();
+            let r = Ray::new(origin.clone(), direction);
+            assert_eq!(*expected, b.intersects(&r));
+        }
+    }
+}
@@ -1,6 +1,14 @@
 use super::{
-    canvas::Canvas, point3d::Point3D, ray::Ray, transform::Transform,
-    world::World, FLOAT,
+    canvas::Canvas,
+    color::Color,
+    intersection::{hit, Intersection},
+    node::Node,
+    point3d::Point3D,
+    random::Random,
+    ray::Ray,
+    transform::Transform,
+    world::World,
+    FLOAT, INFINITY,
 };
 
 #[derive(Debug)]
@@ -19,6 +27,12 @@ pub struct Camera {
     half_height: FLOAT,
     /// 1 pixel あたりのサイズ
     pixel_size: FLOAT,
+    /// カメラからピントが合う位置までの距離
+    focal_distance: FLOAT,
+    /// サンプリングに用いる乱数の種
+    seed: u64,
+    /// 反射・屈折を追跡する再帰の最大深さ
+    max_depth: usize,
 }
 
 impl Camera {
@@ -53,9 +67,61 @@ impl Camera {
             half_width,
             half_height,
             pixel_size,
+            focal_distance: 1.0,
+            seed: 0,
+            max_depth: 5,
         }
     }
 
+    /// 視野角を度数法で指定して Camera を作成する
+    ///
+    /// # Argumets
+    /// * `hsize` - 出力画像の水平方向サイズ
+    /// * `vsize` - 出力画像の垂直方向サイズ
+    /// * `degrees` - 視野角(度)
+    pub fn with_fov_degrees(
+        hsize: usize,
+        vsize: usize,
+        degrees: FLOAT,
+    ) -> Self {
+        Camera::new(hsize, vsize, degrees.to_radians())
+    }
+
+    /// 視野角(rad)を取得する
+    pub fn field_of_view(&self) -> FLOAT {
+        self.field_of_view
+    }
+
+    /// サンプリングに用いる乱数の種を取得する
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// サンプリングに用いる乱数の種を取得する
+    pub fn seed_mut(&mut self) -> &mut u64 {
+        &mut self.seed
+    }
+
+    /// 反射・屈折を追跡する再帰の最大深さを取得する
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// 反射・屈折を追跡する再帰の最大深さを取得する
+    pub fn max_depth_mut(&mut self) -> &mut usize {
+        &mut self.max_depth
+    }
+
+    /// 出力画像の水平方向サイズを取得する
+    pub fn hsize(&self) -> usize {
+        self.hsize
+    }
+
+    /// 出力画像の垂直方向サイズを取得する
+    pub fn vsize(&self) -> usize {
+        self.vsize
+    }
+
     /// カメラの変換行列(View-World transform)を取得する
     pub fn transform(&self) -> &Transform {
         &self.transform
@@ -66,14 +132,53 @@ impl Camera {
         &mut self.transform
     }
 
+    /// カメラからピントが合う位置までの距離を取得する
+    pub fn focal_distance(&self) -> FLOAT {
+        self.focal_distance
+    }
+
+    /// カメラからピントが合う位置までの距離を取得する
+    pub fn focal_distance_mut(&mut self) -> &mut FLOAT {
+        &mut self.focal_distance
+    }
+
+    /// 出力画像上の指定した pixel を通る Ray が最初に当たる位置に
+    /// ピントを合わせる。
+    /// 何にも当たらなかった場合は focal_distance を変更せずに false を返す。
+    ///
+    /// # Argumets
+    /// * `w` - 対象となる World
+    /// * `px` - 出力画像の x 座標
+    /// * `py` - 出力画像の y 座標
+    pub fn focus_on(&mut self, w: &World, px: usize, py: usize) -> bool {
+        let ray = self.ray_for_pixel(px, py);
+        let xs = w.intersect(&ray);
+        match hit(&xs) {
+            Some(nearest) => {
+                self.focal_distance = nearest.t;
+                true
+            }
+            None => false,
+        }
+    }
+
     /// 出力画像上の指定した pixel を通る Ray を生成する
     ///
     /// # Argumets
     /// * `px` - 出力画像の x 座標
     /// * `py` - 出力画像の y 座標
     fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
-        let xoffset = (px as FLOAT + 0.5) * self.pixel_size;
-        let yoffset = (py as FLOAT + 0.5) * self.pixel_size;
+        self.ray_for_point(px as FLOAT + 0.5, py as FLOAT + 0.5)
+    }
+
+    /// 出力画像上の指定した位置を通る Ray を生成する
+    ///
+    /// # Argumets
+    /// * `px` - 出力画像の x 座標。pixel の左端が整数となる。
+    /// * `py` - 出力画像の y 座標。pixel の上端が整数となる。
+    fn ray_for_point(&self, px: FLOAT, py: FLOAT) -> Ray {
+        let xoffset = px * self.pixel_size;
+        let yoffset = py * self.pixel_size;
 
         let world_x = self.half_width - xoffset;
         let world_y = self.half_height - yoffset;
@@ -93,22 +198,227 @@ impl Camera {
     /// * `w` - レンダリング対象
     pub fn render(&self, w: &World) -> Canvas {
         let mut image = Canvas::new(self.hsize, self.vsize);
+        // 交差情報のバッファは全 pixel で使い回す
+        let mut xs = vec![];
 
         for y in 0..self.vsize {
             for x in 0..self.hsize {
                 let ray = self.ray_for_pixel(x, y);
-                let color = w.color_at(&ray, 5);
+                let color = w.color_at_with(&ray, self.max_depth, &mut xs);
                 *image.color_at_mut(x, y) = color;
             }
         }
         image
     }
+
+    /// 画像全体のうち、左上が (x0, y0) で幅 w、高さ h の領域のみを
+    /// レンダリングする。結果は w x h の Canvas で、各 pixel は render の
+    /// 結果の対応する pixel と等しい。
+    /// 領域ごとに別々にレンダリングして、後で結合する用途を想定している。
+    ///
+    /// # Argumets
+    /// * `world` - レンダリング対象
+    /// * `x0` - 領域の左端
+    /// * `y0` - 領域の上端
+    /// * `w` - 領域の幅
+    /// * `h` - 領域の高さ
+    pub fn render_region(
+        &self,
+        world: &World,
+        x0: usize,
+        y0: usize,
+        w: usize,
+        h: usize,
+    ) -> Canvas {
+        assert!(x0 + w <= self.hsize && y0 + h <= self.vsize);
+
+        let mut image = Canvas::new(w, h);
+        let mut xs = vec![];
+        for y in 0..h {
+            for x in 0..w {
+                let ray = self.ray_for_pixel(x0 + x, y0 + y);
+                let color = world.color_at_with(&ray, self.max_depth, &mut xs);
+                *image.color_at_mut(x, y) = color;
+            }
+        }
+        image
+    }
+
+    /// 1 pixel あたり samples 本の Ray の平均を取って World をレンダリングする。
+    /// 各 Ray は pixel 内の位置と時刻 [0, 1) をずらして生成するため、
+    /// アンチエイリアスとモーションブラーの効果が得られる。
+    /// ずらす量は seed から生成する乱数で決めるため、seed が同じであれば
+    /// 結果も同じになる。
+    ///
+    /// # Argumets
+    /// * `w` - レンダリング対象
+    /// * `samples` - 1 pixel あたりの Ray の数
+    pub fn render_antialiased(&self, w: &World, samples: usize) -> Canvas {
+        let mut rng = Random::new(self.seed);
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        let mut xs = vec![];
+
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let mut color = Color::BLACK;
+                for i in 0..samples {
+                    let dx = rng.next_float();
+                    let dy = rng.next_float();
+                    // 時刻は層別にして偏りを抑える
+                    let time =
+                        (i as FLOAT + rng.next_float()) / samples as FLOAT;
+                    let ray = self
+                        .ray_for_point(x as FLOAT + dx, y as FLOAT + dy)
+                        .with_time(time);
+                    color = &color
+                        + &w.color_at_with(&ray, self.max_depth, &mut xs);
+                }
+                *image.color_at_mut(x, y) = &color * (1.0 / samples as FLOAT);
+            }
+        }
+        image
+    }
+
+    /// render_antialiased と同じく 1 pixel あたり samples 本の Ray を用いるが、
+    /// 各 Ray では最初に当たるオブジェクトを求めるのみとし、色の計算は
+    /// pixel 内で当たったオブジェクトごとに 1 度だけ行う (MSAA に相当)。
+    /// オブジェクトの色は、そのオブジェクトに当たった Ray のうち pixel の
+    /// 中心に最も近いもので計算し、当たった Ray の割合で重み付けして平均する。
+    /// 反射や屈折の計算を減らせるが、オブジェクト内での色の変化は平均されない。
+    ///
+    /// # Argumets
+    /// * `w` - レンダリング対象
+    /// * `samples` - 1 pixel あたりの Ray の数
+    pub fn render_multisampled(&self, w: &World, samples: usize) -> Canvas {
+        let mut rng = Random::new(self.seed);
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        // 反射、屈折した Ray の交差情報のバッファは全 pixel で使い回す
+        let mut buffer = vec![];
+
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let mut coverage: Vec<Coverage> = vec![];
+                for i in 0..samples {
+                    let dx = rng.next_float();
+                    let dy = rng.next_float();
+                    let time =
+                        (i as FLOAT + rng.next_float()) / samples as FLOAT;
+                    let ray = self
+                        .ray_for_point(x as FLOAT + dx, y as FLOAT + dy)
+                        .with_time(time);
+                    let xs = w.intersect(&ray);
+                    let object = hit(&xs).map(|h| h.object as *const Node);
+                    let distance = (dx - 0.5).powi(2) + (dy - 0.5).powi(2);
+
+                    match coverage.iter_mut().find(|c| c.object == object) {
+                        Some(c) => {
+                            c.count += 1;
+                            if distance < c.distance {
+                                c.ray = ray;
+                                c.xs = xs;
+                                c.distance = distance;
+                            }
+                        }
+                        None => coverage.push(Coverage {
+                            object,
+                            ray,
+                            xs,
+                            distance,
+                            count: 1,
+                        }),
+                    }
+                }
+
+                let mut color = Color::BLACK;
+                for c in coverage.iter() {
+                    let shaded = w.color_at_intersections(
+                        &c.ray,
+                        self.max_depth,
+                        &c.xs,
+                        &mut buffer,
+                    );
+                    color = &color + &(&shaded * c.count as FLOAT);
+                }
+                *image.color_at_mut(x, y) = &color * (1.0 / samples as FLOAT);
+            }
+        }
+        image
+    }
+
+    /// 各 pixel の色の計算で行った Ray と形状の交差判定の回数を画像にする。
+    /// 回数は最大値で正規化し、少ない方から 黒, 赤, 黄, 白 の順に割り当てる。
+    ///
+    /// # Argumets
+    /// * `w` - レンダリング対象
+    pub fn render_heatmap(&self, w: &World) -> Canvas {
+        let mut counts = Vec::with_capacity(self.hsize * self.vsize);
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let ray = self.ray_for_pixel(x, y);
+                let (_, count) = w.color_at_counted(&ray, self.max_depth);
+                counts.push(count);
+            }
+        }
+
+        let max = counts.iter().copied().max().unwrap_or(0).max(1);
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let heat = counts[self.hsize * y + x] as FLOAT / max as FLOAT;
+                *image.color_at_mut(x, y) = Color::new(
+                    (3.0 * heat).min(1.0),
+                    (3.0 * heat - 1.0).clamp(0.0, 1.0),
+                    (3.0 * heat - 2.0).clamp(0.0, 1.0),
+                );
+            }
+        }
+        image
+    }
+
+    /// World の深度バッファを作成する。
+    /// 各 pixel を通る Ray が最初に当たる位置までの距離を行優先で格納する。
+    /// 何にも当たらなかった pixel は無限大となる。
+    ///
+    /// # Argumets
+    /// * `w` - レンダリング対象
+    pub fn render_depth(&self, w: &World) -> Vec<FLOAT> {
+        let mut depth = vec![INFINITY; self.hsize * self.vsize];
+        let mut xs = vec![];
+
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let ray = self.ray_for_pixel(x, y);
+                w.intersect_into(&ray, &mut xs);
+                if let Some(nearest) = hit(&xs) {
+                    depth[self.hsize * y + x] = nearest.t;
+                }
+            }
+        }
+        depth
+    }
+}
+
+/// render_multisampled において、pixel 内で Ray が当たったオブジェクトの情報
+struct Coverage<'a> {
+    /// 当たったオブジェクト。None は背景
+    object: Option<*const Node>,
+    /// 色の計算に用いる Ray
+    ray: Ray,
+    /// ray の交点
+    xs: Vec<Intersection<'a>>,
+    /// ray の pixel の中心からの距離の 2 乗 (pixel 単位)
+    distance: FLOAT,
+    /// 当たった Ray の数
+    count: usize,
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        super::{approx_eq, vector3d::Vector3D},
+        super::{
+            approx_eq, cube::Cube, light::Light, node::intersection_tests,
+            plane::Plane, sphere::Sphere, vector3d::Vector3D, EPSILON,
+        },
         *,
     };
 
@@ -131,6 +441,16 @@ mod tests {
         assert!(approx_eq(0.01, c.pixel_size));
     }
 
+    #[test]
+    fn constructing_a_camera_with_the_field_of_view_in_degrees() {
+        let c = Camera::with_fov_degrees(200, 125, 90.0);
+        let expected =
+            Camera::new(200, 125, std::f64::consts::FRAC_PI_2 as FLOAT);
+
+        assert!(approx_eq(expected.field_of_view(), c.field_of_view()));
+        assert!(approx_eq(expected.pixel_size, c.pixel_size));
+    }
+
     #[test]
     fn the_pixel_size_for_a_vertical_canvas() {
         let c = Camera::new(125, 200, std::f32::consts::FRAC_PI_2 as FLOAT);
@@ -154,4 +474,138 @@ mod tests {
         assert_eq!(Point3D::new(0.0, 0.0, 0.0), *r.origin());
         assert_eq!(Vector3D::new(0.66519, 0.33259, -0.66851), *r.direction());
     }
+
+    #[test]
+    fn focusing_on_the_object_seen_through_a_pixel() {
+        let mut w = World::new();
+        let mut s = Node::new(Box::new(Sphere::new()));
+        s.set_transform(Transform::translation(0.0, 0.0, -5.0));
+        w.add_node(s);
+
+        let mut c = Camera::new(11, 11, std::f32::consts::FRAC_PI_2 as FLOAT);
+        assert!(c.focus_on(&w, 5, 5));
+        assert!((4.0 - c.focal_distance()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn focusing_on_a_pixel_that_misses_keeps_the_focal_distance() {
+        let w = World::new();
+        let mut c = Camera::new(11, 11, std::f32::consts::FRAC_PI_2 as FLOAT);
+        *c.focal_distance_mut() = 3.0;
+
+        assert!(!c.focus_on(&w, 5, 5));
+        assert_eq!(3.0, c.focal_distance());
+    }
+
+    #[test]
+    fn rendering_a_region_matches_the_full_render() {
+        let w = World::default_scene();
+        let mut c = Camera::new(11, 11, std::f32::consts::FRAC_PI_2 as FLOAT);
+        *c.transform_mut() = Transform::view_transform(
+            &Point3D::new(0.0, 0.0, -5.0),
+            &Point3D::new(0.0, 0.0, 0.0),
+            &Vector3D::new(0.0, 1.0, 0.0),
+        );
+        let full = c.render(&w);
+        let region = c.render_region(&w, 3, 4, 6, 5);
+
+        assert_eq!(6, region.width());
+        assert_eq!(5, region.height());
+        for y in 0..5 {
+            for x in 0..6 {
+                assert_eq!(full.color_at(3 + x, 4 + y), region.color_at(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn rendering_a_depth_buffer() {
+        let mut w = World::new();
+        let mut s = Node::new(Box::new(Sphere::new()));
+        s.set_transform(Transform::translation(0.0, 0.0, -5.0));
+        w.add_node(s);
+
+        let c = Camera::new(11, 11, std::f32::consts::FRAC_PI_2 as FLOAT);
+        let depth = c.render_depth(&w);
+
+        assert_eq!(11 * 11, depth.len());
+        assert!(approx_eq(4.0, depth[11 * 5 + 5]));
+        assert_eq!(INFINITY, depth[0]);
+    }
+
+    #[test]
+    fn rendering_a_heatmap_of_intersection_tests() {
+        let mut w = World::new();
+        w.add_light(Light::new(Point3D::new(-10.0, 10.0, 10.0), Color::WHITE));
+        let mut s = Node::new(Box::new(Sphere::new()));
+        s.set_transform(Transform::translation(0.0, 0.0, -5.0));
+        w.add_node(s);
+
+        let c = Camera::new(11, 11, std::f32::consts::FRAC_PI_2 as FLOAT);
+        // 1 pixel あたり、視線と影の Ray で高々 2 回
+        for y in 0..c.vsize() {
+            for x in 0..c.hsize() {
+                let (_, count) =
+                    w.color_at_counted(&c.ray_for_pixel(x, y), c.max_depth());
+                assert!(1 <= count && count <= 2);
+            }
+        }
+
+        let image = c.render_heatmap(&w);
+        assert_eq!(Color::WHITE, *image.color_at(5, 5));
+        assert_ne!(*image.color_at(0, 0), *image.color_at(5, 5));
+    }
+
+    #[test]
+    fn multisampling_shades_each_object_once_per_pixel() {
+        let mut w = World::new();
+        w.add_light(Light::new(Point3D::new(0.0, 0.0, 1000.0), Color::WHITE));
+        // 正面から見た立方体の面は一様な色になる
+        let mut cube = Node::new(Box::new(Cube::new()));
+        cube.set_transform(Transform::translation(0.0, 0.0, -6.5));
+        cube.material_mut().unwrap().specular = 0.0;
+        w.add_node(cube);
+        let c = Camera::new(11, 11, std::f32::consts::FRAC_PI_2 as FLOAT);
+
+        let before = intersection_tests();
+        let full = c.render_antialiased(&w, 4);
+        let full_tests = intersection_tests() - before;
+        let before = intersection_tests();
+        let msaa = c.render_multisampled(&w, 4);
+        let msaa_tests = intersection_tests() - before;
+
+        // 立方体の輪郭にかかる pixel
+        let (full_edge, msaa_edge) = (full.color_at(4, 5), msaa.color_at(4, 5));
+        assert!(0.0 < full_edge.red && full_edge.red < 1.0);
+        assert!((full_edge.red - msaa_edge.red).abs() < 0.01);
+        assert!((full_edge.green - msaa_edge.green).abs() < 0.01);
+        assert!((full_edge.blue - msaa_edge.blue).abs() < 0.01);
+        // 影の Ray が Ray ごとではなく、オブジェクトごとになる
+        assert!(msaa_tests < full_tests);
+    }
+
+    #[test]
+    fn rendering_follows_reflections_up_to_the_max_depth() {
+        let mut w = World::new();
+        *w.background_mut() = Color::WHITE;
+        let mut floor = Node::new(Box::new(Plane::new()));
+        floor.set_transform(Transform::translation(0.0, -1.0, 0.0));
+        let m = floor.material_mut().unwrap();
+        m.color = Color::BLACK;
+        m.ambient = 0.0;
+        m.diffuse = 0.0;
+        m.specular = 0.0;
+        m.reflective = 1.0;
+        w.add_node(floor);
+
+        let mut c = Camera::new(11, 11, std::f32::consts::FRAC_PI_2 as FLOAT);
+        assert_eq!(5, c.max_depth());
+        // 床に映った背景が見える
+        let image = c.render(&w);
+        assert_ne!(Color::BLACK, *image.color_at(5, 10));
+
+        *c.max_depth_mut() = 0;
+        let image = c.render(&w);
+        assert_eq!(Color::BLACK, *image.color_at(5, 10));
+    }
 }
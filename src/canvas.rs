@@ -1,8 +1,13 @@
-use super::color::Color;
-use std::io::{Result, Write};
+use super::{color::Color, FLOAT};
+use std::{
+    fs::File,
+    io::{BufWriter, Error, ErrorKind, Read, Result, Write},
+    path::Path,
+};
 
 /// 2 次元のイメージを表す。
 /// 左上が原点
+#[derive(Debug)]
 pub struct Canvas {
     /// 幅
     width: usize,
@@ -10,6 +15,8 @@ pub struct Canvas {
     height: usize,
     /// 色の配列
     colors: Vec<Color>,
+    /// 出力時に適用するガンマ値。1.0 の場合は補正しない。
+    gamma: FLOAT,
 }
 
 impl Canvas {
@@ -24,9 +31,20 @@ impl Canvas {
             width,
             height,
             colors: vec![Color::BLACK; width * height],
+            gamma: 1.0,
         }
     }
 
+    /// 出力時に適用するガンマ値を取得する
+    pub fn gamma(&self) -> FLOAT {
+        self.gamma
+    }
+
+    /// 出力時に適用するガンマ値を取得する
+    pub fn gamma_mut(&mut self) -> &mut FLOAT {
+        &mut self.gamma
+    }
+
     /// Canvas の幅
     pub fn width(&self) -> usize {
         self.width
@@ -77,17 +95,510 @@ impl Canvas {
 
         for i in 0..self.height {
             for j in 0..self.width {
-                let c = self.color_at(j, i);
-                let r = (c.red * 255.0).round().min(255.0).max(0.0) as u8;
-                let g = (c.green * 255.0).round().min(255.0).max(0.0) as u8;
-                let b = (c.blue * 255.0).round().min(255.0).max(0.0) as u8;
-
+                let (r, g, b) = to_rgb8(self.color_at(j, i), self.gamma);
                 result +=
                     dst.write(format!("{} {} {}\n", r, g, b).as_bytes())?;
             }
         }
         Ok(result)
     }
+
+    /// Canvas の内容を PPM 形式にして出力する。
+    /// to_ppm と異なり、1 行が 70 文字を超えない範囲で複数の値を詰めて
+    /// 出力する。各行の pixel データは新しい行から始める。
+    /// 出力に成功した場合、出力したバイト数を返す。
+    ///
+    /// # Argumets
+    /// * `dst` - 出力先
+    ///
+    /// # Failures
+    /// 出力に失敗
+    pub fn to_ppm_compact(&self, dst: &mut dyn Write) -> Result<usize> {
+        const MAX_LINE_LENGTH: usize = 70;
+
+        let mut result = 0;
+        result += dst.write(
+            format!("P3\n{} {}\n255\n", self.width, self.height).as_bytes(),
+        )?;
+
+        for i in 0..self.height {
+            let mut line = String::new();
+            for j in 0..self.width {
+                let (r, g, b) = to_rgb8(self.color_at(j, i), self.gamma);
+                for v in [r, g, b].iter() {
+                    let v = v.to_string();
+                    if !line.is_empty() {
+                        if MAX_LINE_LENGTH < line.len() + 1 + v.len() {
+                            line.push('\n');
+                            result += dst.write(line.as_bytes())?;
+                            line.clear();
+                        } else {
+                            line.push(' ');
+                        }
+                    }
+                    line.push_str(&v);
+                }
+            }
+            line.push('\n');
+            result += dst.write(line.as_bytes())?;
+        }
+        Ok(result)
+    }
+
+    /// Canvas の内容を 8 bit RGB の PNG 形式にして出力する。
+    /// 画像データは固定 Huffman 符号の deflate で圧縮して格納する。
+    ///
+    /// # Argumets
+    /// * `dst` - 出力先
+    ///
+    /// # Failures
+    /// 出力に失敗
+    pub fn to_png(&self, dst: &mut dyn Write) -> Result<()> {
+        dst.write_all(&PNG_SIGNATURE)?;
+
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend_from_slice(&(self.width as u32).to_be_bytes());
+        ihdr.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // bit depth 8, color type 2 (RGB), compression, filter, interlace
+        ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+        write_png_chunk(dst, b"IHDR", &ihdr)?;
+
+        // 各 scanline の先頭に filter type 0 (None) を置く
+        let mut raw = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for i in 0..self.height {
+            raw.push(0);
+            for j in 0..self.width {
+                let (r, g, b) = to_rgb8(self.color_at(j, i), self.gamma);
+                raw.extend_from_slice(&[r, g, b]);
+            }
+        }
+        write_png_chunk(dst, b"IDAT", &zlib_compress(&raw))?;
+        write_png_chunk(dst, b"IEND", &[])?;
+
+        Ok(())
+    }
+
+    /// Canvas の内容をファイルに保存する。
+    /// 拡張子が png の場合は PNG 形式、それ以外は PPM 形式で出力する。
+    ///
+    /// # Argumets
+    /// * `path` - 出力先のファイル名
+    ///
+    /// # Failures
+    /// ファイルの作成、または出力に失敗
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let is_png = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        if is_png {
+            self.to_png(&mut writer)?;
+        } else {
+            self.to_ppm(&mut writer)?;
+        }
+        writer.flush()
+    }
+
+    /// Canvas の内容を独自のバイナリ形式で出力する。
+    /// 先頭に識別子と幅、高さ (u32 little endian) を置き、続けて各 pixel の
+    /// red, green, blue を FLOAT の little endian で並べる。
+    /// 色を丸めずにそのまま保存するため、read_bin で完全に復元できる。
+    /// ガンマ値は保存しない。
+    ///
+    /// # Argumets
+    /// * `dst` - 出力先
+    ///
+    /// # Failures
+    /// 出力に失敗
+    pub fn write_bin(&self, dst: &mut dyn Write) -> Result<()> {
+        dst.write_all(&BIN_MAGIC)?;
+        dst.write_all(&(self.width as u32).to_le_bytes())?;
+        dst.write_all(&(self.height as u32).to_le_bytes())?;
+        for c in self.colors.iter() {
+            for v in [c.red, c.green, c.blue].iter() {
+                dst.write_all(&v.to_le_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// write_bin で出力したバイナリ形式から Canvas を作成する。
+    ///
+    /// # Argumets
+    /// * `src` - 読み込み元
+    ///
+    /// # Failures
+    /// 読み込みに失敗した場合や、識別子が一致しない場合、
+    /// 幅と高さに対して pixel のデータが足りない場合
+    pub fn read_bin(src: &mut dyn Read) -> Result<Canvas> {
+        let mut magic = [0; 4];
+        src.read_exact(&mut magic)?;
+        if magic != BIN_MAGIC {
+            return Err(Error::new(ErrorKind::InvalidData, "not a canvas"));
+        }
+
+        let mut size = [0; 4];
+        src.read_exact(&mut size)?;
+        let width = u32::from_le_bytes(size) as usize;
+        src.read_exact(&mut size)?;
+        let height = u32::from_le_bytes(size) as usize;
+
+        let pixels = width.checked_mul(height).ok_or_else(|| {
+            Error::new(ErrorKind::InvalidData, "canvas size overflows")
+        })?;
+
+        // ヘッダの大きさを信用せず、読み込めた分だけ領域を広げる
+        let mut colors =
+            Vec::with_capacity(pixels.min(BIN_PREALLOCATED_PIXELS));
+        let mut value = [0; std::mem::size_of::<FLOAT>()];
+        let mut read_value = |src: &mut dyn Read| -> Result<FLOAT> {
+            src.read_exact(&mut value).map_err(|e| {
+                if e.kind() == ErrorKind::UnexpectedEof {
+                    Error::new(ErrorKind::InvalidData, "canvas is truncated")
+                } else {
+                    e
+                }
+            })?;
+            Ok(FLOAT::from_le_bytes(value))
+        };
+        for _ in 0..pixels {
+            let red = read_value(src)?;
+            let green = read_value(src)?;
+            let blue = read_value(src)?;
+            colors.push(Color::new(red, green, blue));
+        }
+
+        Ok(Canvas {
+            width,
+            height,
+            colors,
+            gamma: 1.0,
+        })
+    }
+
+    /// Reinhard のトーンマッピングを適用する。
+    /// 各成分 c を c / (1 + c) に変換し、明るい部分の階調を残したまま
+    /// 0.0 - 1.0 の範囲に収める。
+    pub fn tone_map_reinhard(&mut self) {
+        let map = |c: FLOAT| c / (1.0 + c);
+        for c in self.colors.iter_mut() {
+            *c = Color::new(map(c.red), map(c.green), map(c.blue));
+        }
+    }
+
+    /// other を sample_index 番目 (0 始まり) のサンプルとして、
+    /// self に保持している平均に加える。
+    /// 全てのサンプルを保持せずに、複数回のレンダリング結果を平均できる。
+    ///
+    /// # Argumets
+    /// * `other` - 加えるサンプル。self と同じ大きさでなければならない。
+    /// * `sample_index` - other が何番目のサンプルか
+    pub fn accumulate(&mut self, other: &Canvas, sample_index: usize) {
+        assert_eq!(self.width, other.width);
+        assert_eq!(self.height, other.height);
+
+        let weight = 1.0 / (sample_index + 1) as FLOAT;
+        for (mean, sample) in self.colors.iter_mut().zip(other.colors.iter()) {
+            *mean = &*mean + &(&(sample - mean) * weight);
+        }
+    }
+
+    /// 中心からの距離に応じて周辺部を暗くした Canvas を作成する。
+    /// 中心からの距離は四隅で 1 となるように正規化する。
+    ///
+    /// # Argumets
+    /// * `strength` - 暗くする強さ。0.0 で変化なし、1.0 で四隅が黒になる。
+    pub fn vignette(&self, strength: FLOAT) -> Canvas {
+        let cx = self.width as FLOAT / 2.0;
+        let cy = self.height as FLOAT / 2.0;
+        let max_dist2 = cx * cx + cy * cy;
+
+        let mut result = Canvas::new(self.width, self.height);
+        result.gamma = self.gamma;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                // pixel の中心で距離を求める
+                let dx = x as FLOAT + 0.5 - cx;
+                let dy = y as FLOAT + 0.5 - cy;
+                let d2 = (dx * dx + dy * dy) / max_dist2;
+                let factor = (1.0 - strength * d2).max(0.0);
+                *result.color_at_mut(x, y) = self.color_at(x, y) * factor;
+            }
+        }
+        result
+    }
+}
+
+/// Color にガンマ補正を適用し、0 - 255 の範囲に丸める
+///
+/// # Argumets
+/// * `c` - 変換する Color
+/// * `gamma` - ガンマ値
+fn to_rgb8(c: &Color, gamma: FLOAT) -> (u8, u8, u8) {
+    let convert = |v: FLOAT| {
+        // 負の値を累乗すると NaN になるので先に切り捨てる
+        let v = v.max(0.0).powf(1.0 / gamma);
+        (v * 255.0).round().clamp(0.0, 255.0) as u8
+    };
+    (convert(c.red), convert(c.green), convert(c.blue))
+}
+
+/// write_bin で出力するバイナリ形式の識別子
+const BIN_MAGIC: [u8; 4] = *b"RTCV";
+/// read_bin で読み込む前にあらかじめ確保する pixel 数の上限。
+/// 壊れたヘッダで巨大な領域を確保しないよう、これを超える分は
+/// 実際に読み込めた分だけ確保する。
+const BIN_PREALLOCATED_PIXELS: usize = 1 << 20;
+
+/// PNG ファイルの先頭に置くシグネチャ
+const PNG_SIGNATURE: [u8; 8] =
+    [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// PNG の chunk を出力する
+///
+/// # Argumets
+/// * `dst` - 出力先
+/// * `chunk_type` - chunk の種類
+/// * `data` - chunk の内容
+fn write_png_chunk(
+    dst: &mut dyn Write,
+    chunk_type: &[u8; 4],
+    data: &[u8],
+) -> Result<()> {
+    dst.write_all(&(data.len() as u32).to_be_bytes())?;
+    dst.write_all(chunk_type)?;
+    dst.write_all(data)?;
+
+    let mut crc = crc32_update(0xffff_ffff, chunk_type);
+    crc = crc32_update(crc, data);
+    dst.write_all(&(crc ^ 0xffff_ffff).to_be_bytes())
+}
+
+/// CRC-32 (ISO 3309) を更新する
+///
+/// # Argumets
+/// * `crc` - これまでの値
+/// * `data` - 追加するデータ
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+/// deflate で一致を探す範囲 (バイト数)
+const DEFLATE_WINDOW: usize = 32768;
+/// deflate で一致を探す際にたどる候補の数の上限
+const DEFLATE_MAX_CHAIN: usize = 64;
+/// 一致を探すためのハッシュ表の大きさ
+const DEFLATE_HASH_SIZE: usize = 1 << 15;
+/// 長さの符号 (257 - 285) が表す長さの最小値
+const LENGTH_BASE: [usize; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59,
+    67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+/// 長さの符号に続く追加ビット数
+const LENGTH_EXTRA: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5,
+    5, 5, 5, 0,
+];
+/// 距離の符号 (0 - 29) が表す距離の最小値
+const DIST_BASE: [usize; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513,
+    769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+/// 距離の符号に続く追加ビット数
+const DIST_EXTRA: [u32; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10,
+    11, 11, 12, 12, 13, 13,
+];
+
+/// deflate のビット列を下位ビットから順に書き込む
+struct BitWriter {
+    /// 書き込み済みのバイト列
+    out: Vec<u8>,
+    /// まだバイト列に書き込んでいないビット
+    bits: u32,
+    /// bits のうち有効なビット数
+    count: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter {
+            out: vec![],
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    /// value の下位 n ビットを下位ビットから順に書き込む
+    ///
+    /// # Argumets
+    /// * `value` - 書き込む値
+    /// * `n` - ビット数
+    fn write_bits(&mut self, value: u32, n: u32) {
+        self.bits |= value << self.count;
+        self.count += n;
+        while 8 <= self.count {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// n ビットの Huffman 符号 code を上位ビットから順に書き込む
+    ///
+    /// # Argumets
+    /// * `code` - 書き込む符号
+    /// * `n` - 符号のビット数
+    fn write_code(&mut self, code: u32, n: u32) {
+        self.write_bits(code.reverse_bits() >> (32 - n), n);
+    }
+
+    /// 固定 Huffman 符号でリテラル/長さの記号を書き込む
+    ///
+    /// # Argumets
+    /// * `symbol` - 記号 (0 - 287)
+    fn write_symbol(&mut self, symbol: u32) {
+        match symbol {
+            0..=143 => self.write_code(0x30 + symbol, 8),
+            144..=255 => self.write_code(0x190 + symbol - 144, 9),
+            256..=279 => self.write_code(symbol - 256, 7),
+            _ => self.write_code(0xc0 + symbol - 280, 8),
+        }
+    }
+
+    /// 端数のビットを書き出し、バイト列を返す
+    fn finish(mut self) -> Vec<u8> {
+        if 0 < self.count {
+            self.out.push(self.bits as u8);
+        }
+        self.out
+    }
+}
+
+/// data を固定 Huffman 符号の deflate ブロック 1 つに圧縮する。
+/// 直前の DEFLATE_WINDOW バイトから 3 バイト以上の一致を探し、
+/// 見つかった場合は長さと距離で置き換える (LZ77)。
+///
+/// # Argumets
+/// * `data` - 圧縮するデータ
+fn deflate_fixed(data: &[u8]) -> Vec<u8> {
+    const NONE: usize = usize::MAX;
+    let hash = |i: usize| {
+        ((data[i] as usize) << 10
+            ^ (data[i + 1] as usize) << 5
+            ^ data[i + 2] as usize)
+            & (DEFLATE_HASH_SIZE - 1)
+    };
+    // ハッシュごとの最新の位置と、同じハッシュを持つ 1 つ前の位置
+    let mut head = vec![NONE; DEFLATE_HASH_SIZE];
+    let mut prev = vec![NONE; DEFLATE_WINDOW];
+    let insert = |i: usize, head: &mut [usize], prev: &mut [usize]| {
+        if i + 3 <= data.len() {
+            let h = hash(i);
+            prev[i % DEFLATE_WINDOW] = head[h];
+            head[h] = i;
+        }
+    };
+
+    let mut w = BitWriter::new();
+    // BFINAL = 1, BTYPE = 01 (固定 Huffman 符号)
+    w.write_bits(1, 1);
+    w.write_bits(1, 2);
+
+    let mut i = 0;
+    while i < data.len() {
+        let mut best_len = 0;
+        let mut best_dist = 0;
+        if i + 3 <= data.len() {
+            let max_len = (data.len() - i).min(258);
+            let mut candidate = head[hash(i)];
+            let mut chain = 0;
+            while candidate != NONE
+                && i - candidate <= DEFLATE_WINDOW
+                && chain < DEFLATE_MAX_CHAIN
+            {
+                let len = (0..max_len)
+                    .take_while(|&k| data[candidate + k] == data[i + k])
+                    .count();
+                if best_len < len {
+                    best_len = len;
+                    best_dist = i - candidate;
+                    if len == max_len {
+                        break;
+                    }
+                }
+                let next = prev[candidate % DEFLATE_WINDOW];
+                // 上書きされた古い候補はたどらない
+                if next == NONE || candidate <= next {
+                    break;
+                }
+                candidate = next;
+                chain += 1;
+            }
+        }
+
+        if 3 <= best_len {
+            let code =
+                LENGTH_BASE.iter().rposition(|&b| b <= best_len).unwrap();
+            w.write_symbol(257 + code as u32);
+            w.write_bits(
+                (best_len - LENGTH_BASE[code]) as u32,
+                LENGTH_EXTRA[code],
+            );
+            let code = DIST_BASE.iter().rposition(|&b| b <= best_dist).unwrap();
+            w.write_code(code as u32, 5);
+            w.write_bits(
+                (best_dist - DIST_BASE[code]) as u32,
+                DIST_EXTRA[code],
+            );
+            for k in i..i + best_len {
+                insert(k, &mut head, &mut prev);
+            }
+            i += best_len;
+        } else {
+            w.write_symbol(data[i] as u32);
+            insert(i, &mut head, &mut prev);
+            i += 1;
+        }
+    }
+    // end of block
+    w.write_symbol(256);
+
+    w.finish()
+}
+
+/// data を deflate で圧縮して zlib 形式に格納する
+///
+/// # Argumets
+/// * `data` - 格納するデータ
+fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    // deflate, 32K window, check bits
+    let mut result = vec![0x78, 0x01];
+    result.append(&mut deflate_fixed(data));
+
+    // Adler-32
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+    for &d in data {
+        a = (a + d as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    result.extend_from_slice(&((b << 16) | a).to_be_bytes());
+
+    result
 }
 
 #[cfg(test)]
@@ -226,4 +737,302 @@ mod tests {
 
         assert_eq!('\n', char::from(ppm[ppm.len() - 1]));
     }
+
+    #[test]
+    fn vignette_darkens_corners_more_than_the_center() {
+        let mut c = Canvas::new(11, 9);
+        for y in 0..c.height() {
+            for x in 0..c.width() {
+                *c.color_at_mut(x, y) = Color::WHITE;
+            }
+        }
+
+        let v = c.vignette(0.5);
+        let center = v.color_at(5, 4);
+        let corner = v.color_at(0, 0);
+        assert!(corner.red < center.red);
+        assert!(corner.green < center.green);
+        assert!(corner.blue < center.blue);
+
+        let v = c.vignette(0.0);
+        assert_eq!(Color::WHITE, *v.color_at(5, 4));
+        assert_eq!(Color::WHITE, *v.color_at(0, 0));
+        assert_eq!(Color::WHITE, *v.color_at(10, 8));
+    }
+
+    #[test]
+    fn writing_a_png_file() {
+        let mut c = Canvas::new(2, 2);
+        *c.color_at_mut(0, 0) = Color::new(0.0, 0.0, 0.0);
+        *c.color_at_mut(1, 0) = Color::new(0.5, 0.5, 0.5);
+        *c.color_at_mut(0, 1) = Color::new(0.5, 0.5, 0.5);
+        *c.color_at_mut(1, 1) = Color::new(1.0, 1.0, 1.0);
+
+        let mut png = Vec::new();
+        c.to_png(&mut png).unwrap();
+
+        assert_eq!(PNG_SIGNATURE, png[..8]);
+
+        // IHDR
+        assert_eq!(13u32.to_be_bytes(), png[8..12]);
+        assert_eq!(b"IHDR", &png[12..16]);
+        assert_eq!(2u32.to_be_bytes(), png[16..20]);
+        assert_eq!(2u32.to_be_bytes(), png[20..24]);
+        assert_eq!([8, 2, 0, 0, 0], png[24..29]);
+        let crc = crc32_update(0xffff_ffff, &png[12..29]) ^ 0xffff_ffff;
+        assert_eq!(crc.to_be_bytes(), png[29..33]);
+
+        // IDAT を展開すると各 scanline が得られる
+        let len = u32::from_be_bytes([png[33], png[34], png[35], png[36]]);
+        assert_eq!(b"IDAT", &png[37..41]);
+        let idat = &png[41..41 + len as usize];
+        assert_eq!([0x78, 0x01], idat[..2]);
+        assert_eq!(
+            vec![0, 0, 0, 0, 128, 128, 128, 0, 128, 128, 128, 255, 255, 255],
+            inflate_fixed(&idat[2..idat.len() - 4])
+        );
+
+        assert_eq!(b"IEND", &png[png.len() - 8..png.len() - 4]);
+    }
+
+    /// 固定 Huffman 符号のブロックのみからなる deflate のデータを展開する
+    fn inflate_fixed(data: &[u8]) -> Vec<u8> {
+        let mut pos = 0;
+        let mut read_bits = |n: u32| {
+            let mut v = 0;
+            for k in 0..n {
+                let bit = (data[pos / 8] >> (pos % 8)) & 1;
+                v |= (bit as usize) << k;
+                pos += 1;
+            }
+            v
+        };
+
+        let mut out: Vec<u8> = vec![];
+        loop {
+            let last = read_bits(1);
+            assert_eq!(1, read_bits(2));
+            loop {
+                let mut code = 0;
+                let mut n = 0;
+                let symbol = loop {
+                    code = (code << 1) | read_bits(1);
+                    n += 1;
+                    match (n, code) {
+                        (7, 0..=0x17) => break code + 256,
+                        (8, 0x30..=0xbf) => break code - 0x30,
+                        (8, 0xc0..=0xc7) => break code - 0xc0 + 280,
+                        (9, 0x190..=0x1ff) => break code - 0x190 + 144,
+                        _ => assert!(n < 9),
+                    }
+                };
+                if symbol < 256 {
+                    out.push(symbol as u8);
+                    continue;
+                }
+                if symbol == 256 {
+                    break;
+                }
+                let c = symbol - 257;
+                let len = LENGTH_BASE[c] + read_bits(LENGTH_EXTRA[c]);
+                let mut c = 0;
+                for _ in 0..5 {
+                    c = (c << 1) | read_bits(1);
+                }
+                let dist = DIST_BASE[c] + read_bits(DIST_EXTRA[c]);
+                for _ in 0..len {
+                    out.push(out[out.len() - dist]);
+                }
+            }
+            if last == 1 {
+                return out;
+            }
+        }
+    }
+
+    #[test]
+    fn deflating_and_inflating_gives_the_original_data() {
+        let mut seed: u32 = 1;
+        let mut noise = || {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) as u8
+        };
+        let cases: Vec<Vec<u8>> = vec![
+            vec![],
+            vec![42],
+            vec![7; 1000],
+            (0..70000).map(|i| (i % 251) as u8).collect(),
+            (0..5000).map(|_| noise() % 4).collect(),
+            (0..5000).map(|_| noise()).collect(),
+        ];
+
+        for data in cases.iter() {
+            assert_eq!(*data, inflate_fixed(&deflate_fixed(data)));
+        }
+    }
+
+    #[test]
+    fn a_png_of_a_flat_image_is_compressed() {
+        let mut c = Canvas::new(64, 64);
+        for y in 0..64 {
+            for x in 0..64 {
+                *c.color_at_mut(x, y) = Color::new(0.2, 0.4, 0.6);
+            }
+        }
+
+        let mut png = Vec::new();
+        c.to_png(&mut png).unwrap();
+
+        // 無圧縮の scanline だけで (64 * 3 + 1) * 64 バイトになる
+        assert!(png.len() < (64 * 3 + 1) * 64 / 10);
+    }
+
+    #[test]
+    fn crc32_of_a_known_string() {
+        let crc = crc32_update(0xffff_ffff, b"123456789") ^ 0xffff_ffff;
+        assert_eq!(0xcbf4_3926, crc);
+    }
+
+    #[test]
+    fn compact_ppm_lines_do_not_exceed_70_columns() {
+        let width = 10;
+        let height = 2;
+        let mut c = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                *c.color_at_mut(x, y) = Color::new(1.0, 0.8, 0.6);
+            }
+        }
+
+        let mut ppm = Vec::new();
+        let _result = c.to_ppm_compact(&mut ppm).unwrap();
+        let ppm = String::from_utf8(ppm).unwrap();
+
+        let lines: Vec<&str> = ppm.lines().collect();
+        assert_eq!("P3", lines[0]);
+        assert_eq!("10 2", lines[1]);
+        assert_eq!("255", lines[2]);
+        assert_eq!(
+            "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204",
+            lines[3]
+        );
+        assert!(lines.iter().all(|l| l.len() <= 70));
+
+        let values: Vec<&str> =
+            lines[3..].iter().flat_map(|l| l.split(' ')).collect();
+        assert_eq!(width * height * 3, values.len());
+        for rgb in values.chunks(3) {
+            assert_eq!(["255", "204", "153"], rgb);
+        }
+        assert!(ppm.ends_with('\n'));
+    }
+
+    #[test]
+    fn saving_to_a_file_chooses_the_format_from_the_extension() {
+        let c = Canvas::new(2, 2);
+        let dir = std::env::temp_dir();
+
+        let ppm_path = dir.join("raytracer_save_to_file_test.ppm");
+        c.save_to_file(&ppm_path).unwrap();
+        let ppm = std::fs::read(&ppm_path).unwrap();
+        std::fs::remove_file(&ppm_path).unwrap();
+        assert_eq!(b"P3\n2 2\n255\n", &ppm[..11]);
+
+        let png_path = dir.join("raytracer_save_to_file_test.png");
+        c.save_to_file(&png_path).unwrap();
+        let png = std::fs::read(&png_path).unwrap();
+        std::fs::remove_file(&png_path).unwrap();
+        assert_eq!(PNG_SIGNATURE, png[..8]);
+    }
+
+    #[test]
+    fn gamma_correction_is_applied_on_output() {
+        let mut c = Canvas::new(1, 1);
+        *c.color_at_mut(0, 0) = Color::new(0.25, 0.0, 1.0);
+
+        let mut ppm = Vec::new();
+        c.to_ppm(&mut ppm).unwrap();
+        assert_eq!(b"64 0 255\n", &ppm[11..]);
+
+        *c.gamma_mut() = 2.0;
+        let mut ppm = Vec::new();
+        c.to_ppm(&mut ppm).unwrap();
+        assert_eq!(b"128 0 255\n", &ppm[11..]);
+    }
+
+    #[test]
+    fn reinhard_tone_mapping() {
+        let mut c = Canvas::new(2, 1);
+        *c.color_at_mut(0, 0) = Color::new(3.0, 0.0, 1.0);
+        *c.color_at_mut(1, 0) = Color::new(0.5, 0.25, 9.0);
+
+        c.tone_map_reinhard();
+        assert_eq!(Color::new(0.75, 0.0, 0.5), *c.color_at(0, 0));
+        assert_eq!(Color::new(1.0 / 3.0, 0.2, 0.9), *c.color_at(1, 0));
+    }
+
+    #[test]
+    fn accumulating_samples_into_a_running_mean() {
+        let samples = [
+            Color::new(0.3, 0.0, 1.0),
+            Color::new(0.6, 0.5, 1.0),
+            Color::new(0.0, 1.0, 0.4),
+        ];
+        let mut mean = Canvas::new(2, 2);
+        for (i, c) in samples.iter().enumerate() {
+            let mut sample = Canvas::new(2, 2);
+            for y in 0..2 {
+                for x in 0..2 {
+                    *sample.color_at_mut(x, y) = *c;
+                }
+            }
+            mean.accumulate(&sample, i);
+        }
+
+        for y in 0..2 {
+            for x in 0..2 {
+                assert_eq!(Color::new(0.3, 0.5, 0.8), *mean.color_at(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn a_canvas_survives_a_binary_round_trip() {
+        let mut c = Canvas::new(3, 2);
+        *c.color_at_mut(0, 0) = Color::new(0.1, 0.2, 0.3);
+        *c.color_at_mut(2, 0) = Color::new(1.0 / 3.0, 7.5, -0.25);
+        *c.color_at_mut(1, 1) = Color::new(1e-300, 2.0, 0.7);
+
+        let mut bin = Vec::new();
+        c.write_bin(&mut bin).unwrap();
+        let d = Canvas::read_bin(&mut &bin[..]).unwrap();
+
+        assert_eq!(3, d.width());
+        assert_eq!(2, d.height());
+        for (a, b) in c.colors.iter().zip(d.colors.iter()) {
+            assert_eq!(a.red.to_bits(), b.red.to_bits());
+            assert_eq!(a.green.to_bits(), b.green.to_bits());
+            assert_eq!(a.blue.to_bits(), b.blue.to_bits());
+        }
+
+        assert!(Canvas::read_bin(&mut &b"P3\n2 2\n"[..]).is_err());
+    }
+
+    #[test]
+    fn a_truncated_binary_canvas_is_rejected_without_allocating_it() {
+        let mut bin = BIN_MAGIC.to_vec();
+        bin.extend_from_slice(&u32::MAX.to_le_bytes());
+        bin.extend_from_slice(&u32::MAX.to_le_bytes());
+        bin.extend_from_slice(&1.0f64.to_le_bytes());
+
+        let e = Canvas::read_bin(&mut &bin[..]).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, e.kind());
+
+        // pixel が 1 つ足りない
+        let mut bin = vec![];
+        Canvas::new(2, 2).write_bin(&mut bin).unwrap();
+        bin.truncate(bin.len() - 3 * std::mem::size_of::<FLOAT>());
+        let e = Canvas::read_bin(&mut &bin[..]).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, e.kind());
+    }
 }
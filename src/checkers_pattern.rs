@@ -1,5 +1,6 @@
 use super::{
     color::Color, pattern::Pattern, point3d::Point3D, transform::Transform,
+    vector3d::Vector3D, FLOAT,
 };
 
 /// 3 次元空間内の市松模様
@@ -7,6 +8,8 @@ use super::{
 pub struct CheckersPattern {
     a: Color,
     b: Color,
+    /// 各軸方向の繰り返しの周期
+    period: Vector3D,
     /// Pattern -> Shape Transform
     transform: Transform,
 }
@@ -16,9 +19,32 @@ impl CheckersPattern {
         CheckersPattern {
             a,
             b,
+            period: Vector3D::new(1.0, 1.0, 1.0),
             transform: Transform::identity(),
         }
     }
+
+    /// 全ての軸方向の周期を period に設定する
+    ///
+    /// # Argumets
+    /// * `period` - 繰り返しの周期
+    pub fn with_period(mut self, period: FLOAT) -> Self {
+        self.period = Vector3D::new(period, period, period);
+        self
+    }
+
+    /// 各軸方向の周期を個別に設定する。
+    /// transform による拡大と異なり、入れ子にしたパターンを歪めずに
+    /// 細長いマスを作ることができる。
+    ///
+    /// # Argumets
+    /// * `sx` - x 軸方向の周期
+    /// * `sy` - y 軸方向の周期
+    /// * `sz` - z 軸方向の周期
+    pub fn with_periods(mut self, sx: FLOAT, sy: FLOAT, sz: FLOAT) -> Self {
+        self.period = Vector3D::new(sx, sy, sz);
+        self
+    }
 }
 
 impl Pattern for CheckersPattern {
@@ -31,7 +57,10 @@ impl Pattern for CheckersPattern {
     }
 
     fn pattern_at(&self, p: &Point3D) -> Color {
-        if (p.x.floor() + p.y.floor() + p.z.floor()) as i32 % 2 == 0 {
+        let x = (p.x / self.period.x).floor();
+        let y = (p.y / self.period.y).floor();
+        let z = (p.z / self.period.z).floor();
+        if (x + y + z) as i32 % 2 == 0 {
             self.a
         } else {
             self.b
@@ -96,4 +125,54 @@ mod tests {
             pattern.pattern_at(&Point3D::new(0.0, 0.0, 1.01))
         );
     }
+
+    #[test]
+    fn checkers_with_a_shorter_period() {
+        let pattern =
+            CheckersPattern::new(Color::WHITE, Color::BLACK).with_period(0.5);
+
+        assert_eq!(
+            Color::WHITE,
+            pattern.pattern_at(&Point3D::new(0.25, 0.0, 0.0))
+        );
+        assert_eq!(
+            Color::BLACK,
+            pattern.pattern_at(&Point3D::new(0.75, 0.0, 0.0))
+        );
+        assert_eq!(
+            Color::WHITE,
+            pattern.pattern_at(&Point3D::new(0.75, 0.75, 0.0))
+        );
+        assert_eq!(
+            Color::BLACK,
+            pattern.pattern_at(&Point3D::new(0.75, 0.75, 0.75))
+        );
+    }
+
+    #[test]
+    fn checkers_with_independent_periods_per_axis() {
+        let pattern = CheckersPattern::new(Color::WHITE, Color::BLACK)
+            .with_periods(2.0, 1.0, 0.5);
+
+        assert_eq!(
+            Color::WHITE,
+            pattern.pattern_at(&Point3D::new(0.0, 0.0, 0.0))
+        );
+        assert_eq!(
+            Color::WHITE,
+            pattern.pattern_at(&Point3D::new(1.99, 0.0, 0.0))
+        );
+        assert_eq!(
+            Color::BLACK,
+            pattern.pattern_at(&Point3D::new(2.01, 0.0, 0.0))
+        );
+        assert_eq!(
+            Color::BLACK,
+            pattern.pattern_at(&Point3D::new(0.0, 1.01, 0.0))
+        );
+        assert_eq!(
+            Color::BLACK,
+            pattern.pattern_at(&Point3D::new(0.0, 0.0, 0.51))
+        );
+    }
 }
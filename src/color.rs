@@ -1,4 +1,7 @@
-use std::ops::{Add, Mul, Sub};
+use std::{
+    fmt,
+    ops::{Add, Mul, Sub},
+};
 
 use super::{approx_eq, FLOAT};
 
@@ -39,8 +42,71 @@ impl Color {
     pub fn new(red: FLOAT, green: FLOAT, blue: FLOAT) -> Self {
         Color { red, green, blue }
     }
+
+    /// `#rrggbb` または `rrggbb` 形式の 16 進数文字列から Color を作成する
+    ///
+    /// # Argumets
+    /// * `s` - 16 進数文字列
+    ///
+    /// # Failures
+    /// 文字数が 6 桁でない場合や、16 進数でない文字を含む場合
+    pub fn from_hex(s: &str) -> Result<Self, ColorParseError> {
+        let digits = s.strip_prefix('#').unwrap_or(s);
+        if digits.len() != 6 {
+            return Err(ColorParseError::InvalidLength(s.to_string()));
+        }
+        // from_str_radix は先頭の '+' を受け付けるため、先に全ての文字を調べる
+        if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(ColorParseError::InvalidDigit(s.to_string()));
+        }
+
+        let channel = |i: usize| {
+            digits
+                .get(i..i + 2)
+                .and_then(|d| u8::from_str_radix(d, 16).ok())
+                .map(|v| v as FLOAT / 255.0)
+                .ok_or_else(|| ColorParseError::InvalidDigit(s.to_string()))
+        };
+        Ok(Color::new(channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    /// `#rrggbb` 形式の 16 進数文字列に変換する。
+    /// 各成分は 0.0 - 1.0 の範囲に切り詰める。
+    pub fn to_hex(&self) -> String {
+        let convert = |v: FLOAT| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        format!(
+            "#{:02x}{:02x}{:02x}",
+            convert(self.red),
+            convert(self.green),
+            convert(self.blue)
+        )
+    }
+}
+
+/// 文字列から Color を作成する際に発生するエラー
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorParseError {
+    /// 文字数が正しくない
+    InvalidLength(String),
+    /// 16 進数でない文字を含む
+    InvalidDigit(String),
 }
 
+impl fmt::Display for ColorParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColorParseError::InvalidLength(s) => {
+                write!(f, "invalid length for a hex color: {}", s)
+            }
+            ColorParseError::InvalidDigit(s) => {
+                write!(f, "invalid digit in a hex color: {}", s)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ColorParseError {}
+
 impl PartialEq for Color {
     /// 2 つの Color が等しいかをテストする。
     /// float 同士の比較なので、ある程度の誤差を許容する。
@@ -163,4 +229,30 @@ mod tests {
 
         assert_eq!(Color::new(0.9, 0.2, 0.04), &c1 * &c2);
     }
+
+    #[test]
+    fn converting_a_color_from_and_to_hex() {
+        let c = Color::from_hex("#ff8000").unwrap();
+        assert_eq!(Color::new(1.0, 0.50196, 0.0), c);
+        assert_eq!("#ff8000", c.to_hex());
+
+        assert_eq!(c, Color::from_hex("FF8000").unwrap());
+        assert_eq!("#ff0000", Color::new(1.5, -0.2, 0.0).to_hex());
+    }
+
+    #[test]
+    fn parsing_an_invalid_hex_color() {
+        assert_eq!(
+            Err(ColorParseError::InvalidLength("#ff800".to_string())),
+            Color::from_hex("#ff800")
+        );
+        assert_eq!(
+            Err(ColorParseError::InvalidDigit("#ff80zz".to_string())),
+            Color::from_hex("#ff80zz")
+        );
+        assert_eq!(
+            Err(ColorParseError::InvalidDigit("#+F0000".to_string())),
+            Color::from_hex("#+F0000")
+        );
+    }
 }
@@ -1,7 +1,15 @@
 use crate::{
-    approx_eq, intersection::Intersection, material::Material, node::Node,
-    point3d::Point3D, ray::Ray, shape::Shape, vector3d::Vector3D, EPSILON,
-    FLOAT, INFINITY,
+    approx_eq,
+    bounding_box::BoundingBox,
+    cylinder::{check_bounds, InvalidBoundsError},
+    intersection::Intersection,
+    material::Material,
+    node::Node,
+    point3d::Point3D,
+    ray::Ray,
+    shape::Shape,
+    vector3d::Vector3D,
+    EPSILON, FLOAT, INFINITY,
 };
 
 /// Axis Aligned な cube
@@ -43,6 +51,28 @@ impl Cone {
         &mut self.maximum
     }
 
+    /// y 方向の範囲を設定する。
+    /// minimum_mut と maximum_mut による設定と異なり、範囲が逆転していないかを
+    /// 確かめる。
+    ///
+    /// # Argumets
+    /// * `minimum` - y の最小値
+    /// * `maximum` - y の最大値
+    ///
+    /// # Failures
+    /// minimum が maximum より大きい場合は InvalidBoundsError を返し、
+    /// 範囲は変更しない
+    pub fn set_bounds(
+        &mut self,
+        minimum: FLOAT,
+        maximum: FLOAT,
+    ) -> Result<(), InvalidBoundsError> {
+        check_bounds(minimum, maximum)?;
+        self.minimum = minimum;
+        self.maximum = maximum;
+        Ok(())
+    }
+
     pub fn closed(&self) -> bool {
         self.closed
     }
@@ -75,35 +105,25 @@ impl Cone {
         // by intersecting the ray with the plane at y = cyl.minimum
         let t = (self.minimum() - r.origin().y) / r.direction().y;
         if check_cap(&r, t) {
-            xs.push(Intersection {
-                t: t,
-                object: n,
-                u: 0.0,
-                v: 0.0,
-            });
+            xs.push(Intersection::new(t, n));
         }
 
         // check for an intersection with the upper end cap
         // by intersecting the ray with the plane at y = cyl.maximum
         let t = (self.maximum() - r.origin().y) / r.direction().y;
         if check_cap(&r, t) {
-            xs.push(Intersection {
-                t: t,
-                object: n,
-                u: 0.0,
-                v: 0.0,
-            });
+            xs.push(Intersection::new(t, n));
         }
     }
 }
 
 impl Shape for Cone {
-    fn material(&self) -> &Material {
-        &self.material
+    fn material(&self) -> Option<&Material> {
+        Some(&self.material)
     }
 
-    fn material_mut(&mut self) -> &mut Material {
-        &mut self.material
+    fn material_mut(&mut self) -> Option<&mut Material> {
+        Some(&mut self.material)
     }
 
     fn local_intersect<'a>(
@@ -121,12 +141,7 @@ impl Shape for Cone {
         if approx_eq(0.0, a) {
             if !approx_eq(0.0, b) {
                 let t = -c / (2.0 * b);
-                xs.push(Intersection {
-                    t: t,
-                    object: n,
-                    u: 0.0,
-                    v: 0.0,
-                });
+                xs.push(Intersection::new(t, n));
             }
         } else {
             let disc = b * b - 4.0 * a * c;
@@ -139,21 +154,11 @@ impl Shape for Cone {
 
                 let y0 = o.y + t0 * d.y;
                 if self.minimum() < y0 && y0 < self.maximum() {
-                    xs.push(Intersection {
-                        t: t0,
-                        object: n,
-                        u: 0.0,
-                        v: 0.0,
-                    });
+                    xs.push(Intersection::new(t0, n));
                 }
                 let y1 = o.y + t1 * d.y;
                 if self.minimum() < y1 && y1 < self.maximum() {
-                    xs.push(Intersection {
-                        t: t1,
-                        object: n,
-                        u: 0.0,
-                        v: 0.0,
-                    });
+                    xs.push(Intersection::new(t1, n));
                 }
             }
         }
@@ -163,19 +168,27 @@ impl Shape for Cone {
     }
 
     fn local_normal_at(&self, p: &Point3D, _: &Intersection) -> Vector3D {
-        let mut y = (p.x * p.x + p.z * p.z).sqrt();
-        if y < 0.0 {
-            y = -y;
-        }
+        let dist = p.x * p.x + p.z * p.z;
+        // 端面の半径は |y| に等しいので、Cylinder と同様に 2 乗で比較する
+        let on_cap = dist <= p.y * p.y + EPSILON;
 
-        if y < p.y.abs() && p.y >= self.maximum() - EPSILON {
+        if on_cap && p.y >= self.maximum() - EPSILON {
             Vector3D::new(0.0, 1.0, 0.0)
-        } else if y < p.y.abs() && p.y <= self.minimum() + EPSILON {
+        } else if on_cap && p.y <= self.minimum() + EPSILON {
             Vector3D::new(0.0, -1.0, 0.0)
         } else {
-            Vector3D::new(p.x, y, p.z)
+            Vector3D::new(p.x, dist.sqrt(), p.z)
         }
     }
+
+    fn bounds(&self) -> BoundingBox {
+        // 半径は |y| に等しいため、両端のうち大きい方が x, z の範囲になる
+        let limit = self.minimum.abs().max(self.maximum.abs());
+        BoundingBox::new(
+            Point3D::new(-limit, self.minimum, -limit),
+            Point3D::new(limit, self.maximum, limit),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -262,6 +275,7 @@ mod tests {
             object: &Node::new(Box::new(Cone::new())),
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
 
         let n = shape.local_normal_at(&Point3D::new(0.0, 0.0, 0.0), &i);
@@ -285,6 +299,7 @@ mod tests {
             object: &Node::new(Box::new(Cone::new())),
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
 
         let n = shape.local_normal_at(&Point3D::new(0.0, -1.0, 0.0), &i);
@@ -305,4 +320,35 @@ mod tests {
         let n = shape.local_normal_at(&Point3D::new(0.0, 2.0, 1.9), &i);
         assert_eq!(Vector3D::new(0.0, 1.0, 0.0), n);
     }
+
+    #[test]
+    fn the_normal_vector_on_the_rim_of_a_cones_bottom_cap() {
+        let mut shape = Cone::new();
+        *shape.minimum_mut() = -1.0;
+        *shape.maximum_mut() = 2.0;
+        *shape.closed_mut() = true;
+        let i = Intersection {
+            t: 0.0,
+            object: &Node::new(Box::new(Cone::new())),
+            u: 0.0,
+            v: 0.0,
+            front_face: true,
+        };
+
+        // 0.6^2 + 0.8^2 は丸め誤差により 1.0 をわずかに超える
+        let n = shape.local_normal_at(&Point3D::new(0.6, -1.0, 0.8), &i);
+        assert_eq!(Vector3D::new(0.0, -1.0, 0.0), n);
+    }
+
+    #[test]
+    fn setting_inverted_bounds_of_a_cone_is_rejected() {
+        let mut shape = Cone::new();
+        assert!(shape.set_bounds(2.0, 1.0).is_err());
+        assert_eq!(-INFINITY, shape.minimum());
+        assert_eq!(INFINITY, shape.maximum());
+
+        assert!(shape.set_bounds(-1.0, 0.0).is_ok());
+        assert_eq!(-1.0, shape.minimum());
+        assert_eq!(0.0, shape.maximum());
+    }
 }
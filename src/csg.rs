@@ -0,0 +1,392 @@
+use crate::{
+    bounding_box::BoundingBox, intersection::Intersection, node::Node,
+    point3d::Point3D, ray::Ray, shape::Shape, vector3d::Vector3D,
+};
+
+/// CSG の演算の種類
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsgOperation {
+    /// 和
+    Union,
+    /// 積
+    Intersection,
+    /// 差
+    Difference,
+}
+
+/// 2 つの Node を組み合わせた Constructive Solid Geometry
+#[derive(Debug)]
+pub struct Csg {
+    /// 演算の種類
+    operation: CsgOperation,
+    /// 左右の Node。最初に追加したものが左、2 番目に追加したものが右となる。
+    /// Node はその子から親として参照されるため、Vec が再確保されても
+    /// 移動しないよう Box に入れておく。
+    #[allow(clippy::vec_box)]
+    children: Vec<Box<Node>>,
+}
+
+impl Csg {
+    /// 新規に Csg を作成する。
+    /// 左右の Node は Node::add_child でこの順に追加する。
+    ///
+    /// # Argumets
+    /// * `operation` - 演算の種類
+    pub fn new(operation: CsgOperation) -> Self {
+        Csg {
+            operation,
+            children: Vec::with_capacity(2),
+        }
+    }
+
+    /// 演算の種類を取得する
+    pub fn operation(&self) -> CsgOperation {
+        self.operation
+    }
+
+    /// 左の Node を取得する。まだ追加されていない場合は None を返す。
+    pub fn left(&self) -> Option<&Node> {
+        self.children.first().map(|child| &**child)
+    }
+
+    /// 右の Node を取得する。まだ追加されていない場合は None を返す。
+    pub fn right(&self) -> Option<&Node> {
+        self.children.get(1).map(|child| &**child)
+    }
+
+    /// 演算に従って残すべき交点のみを取り出す
+    ///
+    /// # Argumets
+    /// * `xs` - t でソートされた左右の Node の交点
+    fn filter_intersections<'a>(
+        &self,
+        xs: Vec<Intersection<'a>>,
+    ) -> Vec<Intersection<'a>> {
+        let mut inl = false;
+        let mut inr = false;
+
+        let mut result = vec![];
+        for i in xs {
+            let lhit = self.left().is_some_and(|l| l.includes(i.object));
+            if intersection_allowed(self.operation, lhit, inl, inr) {
+                result.push(i);
+            }
+
+            if lhit {
+                inl = !inl;
+            } else {
+                inr = !inr;
+            }
+        }
+
+        result
+    }
+}
+
+/// CSG の交点として採用するかを判定する
+///
+/// # Argumets
+/// * `op` - 演算の種類
+/// * `lhit` - 左の Node との交点であるか
+/// * `inl` - 左の Node の内部にいるか
+/// * `inr` - 右の Node の内部にいるか
+pub fn intersection_allowed(
+    op: CsgOperation,
+    lhit: bool,
+    inl: bool,
+    inr: bool,
+) -> bool {
+    match op {
+        CsgOperation::Union => (lhit && !inr) || (!lhit && !inl),
+        CsgOperation::Intersection => (lhit && inr) || (!lhit && inl),
+        CsgOperation::Difference => (lhit && !inr) || (!lhit && inl),
+    }
+}
+
+impl Shape for Csg {
+    fn add_child(&mut self, child: Box<Node>) {
+        assert!(self.children.len() < 2);
+        self.children.push(child);
+    }
+
+    fn child_at(&self, idx: usize) -> &Box<Node> {
+        &self.children[idx]
+    }
+
+    fn child_count(&self) -> usize {
+        self.children.len()
+    }
+
+    fn children(&self) -> &[Box<Node>] {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Node> {
+        self.children.iter_mut().map(|child| &mut **child).collect()
+    }
+
+    fn includes(&self, n: &Node) -> bool {
+        self.children().iter().any(|child| child.includes(n))
+    }
+
+    fn take_children(&mut self) -> Vec<Box<Node>> {
+        std::mem::take(&mut self.children)
+    }
+
+    fn local_intersect<'a>(
+        &'a self,
+        r: &Ray,
+        _n: &'a Node,
+    ) -> Vec<Intersection<'a>> {
+        if !self.bounds().intersects(r) {
+            return vec![];
+        }
+
+        // 追加されていない Node は交点を持たないものとして扱う
+        let mut xs = vec![];
+        for child in &self.children {
+            child.intersect_into(r, &mut xs);
+        }
+        xs.sort_unstable_by(Intersection::cmp_t);
+
+        self.filter_intersections(xs)
+    }
+
+    fn local_normal_at(&self, _p: &Point3D, _: &Intersection) -> Vector3D {
+        panic!()
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut b = BoundingBox::empty();
+        for child in self.children() {
+            b.add_box(&child.parent_space_bounds());
+        }
+        b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{
+            approx_eq, cube::Cube, group::Group, sphere::Sphere,
+            transform::Transform, FLOAT,
+        },
+        *,
+    };
+
+    #[test]
+    fn csg_is_created_with_an_operation_and_two_shapes() {
+        let s1 = Node::new(Box::new(Sphere::new()));
+        let s2 = Node::new(Box::new(Cube::new()));
+        let s1_ptr = &*s1 as *const Node;
+        let s2_ptr = &*s2 as *const Node;
+
+        let mut c = Node::new(Box::new(Csg::new(CsgOperation::Union)));
+        c.add_child(s1);
+        c.add_child(s2);
+
+        assert!(std::ptr::eq(s1_ptr, &**c.child_at(0)));
+        assert!(std::ptr::eq(s2_ptr, &**c.child_at(1)));
+        assert!(c.includes(unsafe { &*s1_ptr }));
+        assert!(c.includes(unsafe { &*s2_ptr }));
+    }
+
+    #[test]
+    fn a_csg_without_its_children() {
+        let mut c = Node::new(Box::new(Csg::new(CsgOperation::Union)));
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+
+        assert!(c.intersect(&r).is_empty());
+
+        c.add_child(Node::new(Box::new(Sphere::new())));
+        assert_eq!(2, c.intersect(&r).len());
+    }
+
+    #[test]
+    fn dividing_reaches_the_children_of_a_csg() {
+        let mut g = Node::new(Box::new(Group::new()));
+        for x in 0..4 {
+            let mut s = Node::new(Box::new(Sphere::new()));
+            s.set_transform(Transform::translation(3.0 * x as FLOAT, 0.0, 0.0));
+            g.add_child(s);
+        }
+        let mut c = Node::new(Box::new(Csg::new(CsgOperation::Difference)));
+        c.add_child(g);
+        c.add_child(Node::new(Box::new(Cube::new())));
+
+        c.divide(1);
+
+        // Csg の左右はそのままで、左の Group が分割される
+        assert_eq!(2, c.child_count());
+        assert_eq!(2, c.child_at(0).child_count());
+        assert_eq!(2, c.child_at(0).child_at(0).child_count());
+        assert_eq!(0, c.child_at(1).child_count());
+    }
+
+    #[test]
+    fn evaluating_the_rule_for_a_csg_operation() {
+        use CsgOperation::*;
+        let cases = [
+            (Union, true, true, true, false),
+            (Union, true, true, false, true),
+            (Union, true, false, true, false),
+            (Union, true, false, false, true),
+            (Union, false, true, true, false),
+            (Union, false, true, false, false),
+            (Union, false, false, true, true),
+            (Union, false, false, false, true),
+            (Intersection, true, true, true, true),
+            (Intersection, true, true, false, false),
+            (Intersection, true, false, true, true),
+            (Intersection, true, false, false, false),
+            (Intersection, false, true, true, true),
+            (Intersection, false, true, false, true),
+            (Intersection, false, false, true, false),
+            (Intersection, false, false, false, false),
+            (Difference, true, true, true, false),
+            (Difference, true, true, false, true),
+            (Difference, true, false, true, false),
+            (Difference, true, false, false, true),
+            (Difference, false, true, true, true),
+            (Difference, false, true, false, true),
+            (Difference, false, false, true, false),
+            (Difference, false, false, false, false),
+        ];
+
+        for (op, lhit, inl, inr, expected) in cases.iter() {
+            assert_eq!(*expected, intersection_allowed(*op, *lhit, *inl, *inr));
+        }
+    }
+
+    #[test]
+    fn filtering_a_list_of_intersections() {
+        let cases = [
+            (CsgOperation::Union, 0, 3),
+            (CsgOperation::Intersection, 1, 2),
+            (CsgOperation::Difference, 0, 1),
+        ];
+
+        for (op, x0, x1) in cases.iter() {
+            let s1 = Node::new(Box::new(Sphere::new()));
+            let s2 = Node::new(Box::new(Cube::new()));
+            let s1_ptr = &*s1 as *const Node;
+            let s2_ptr = &*s2 as *const Node;
+            let csg = Csg {
+                operation: *op,
+                children: vec![s1, s2],
+            };
+            let s1 = unsafe { &*s1_ptr };
+            let s2 = unsafe { &*s2_ptr };
+            let xs = vec![
+                Intersection {
+                    t: 1.0,
+                    object: s1,
+                    u: 0.0,
+                    v: 0.0,
+                    front_face: true,
+                },
+                Intersection {
+                    t: 2.0,
+                    object: s2,
+                    u: 0.0,
+                    v: 0.0,
+                    front_face: true,
+                },
+                Intersection {
+                    t: 3.0,
+                    object: s1,
+                    u: 0.0,
+                    v: 0.0,
+                    front_face: true,
+                },
+                Intersection {
+                    t: 4.0,
+                    object: s2,
+                    u: 0.0,
+                    v: 0.0,
+                    front_face: true,
+                },
+            ];
+            let expected = [xs[*x0].t, xs[*x1].t];
+
+            let result = csg.filter_intersections(xs);
+            assert_eq!(2, result.len());
+            assert_eq!(expected[0], result[0].t);
+            assert_eq!(expected[1], result[1].t);
+        }
+    }
+
+    #[test]
+    fn a_ray_misses_a_csg_object() {
+        let mut c = Node::new(Box::new(Csg::new(CsgOperation::Union)));
+        c.add_child(Node::new(Box::new(Sphere::new())));
+        c.add_child(Node::new(Box::new(Cube::new())));
+        let r = Ray::new(
+            Point3D::new(0.0, 2.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+
+        let xs = c.intersect(&r);
+        assert_eq!(0, xs.len());
+    }
+
+    #[test]
+    fn a_ray_hits_a_csg_object() {
+        let s1 = Node::new(Box::new(Sphere::new()));
+        let mut s2 = Node::new(Box::new(Sphere::new()));
+        s2.set_transform(Transform::translation(0.0, 0.0, 0.5));
+        let s1_ptr = &*s1 as *const Node;
+        let s2_ptr = &*s2 as *const Node;
+
+        let mut c = Node::new(Box::new(Csg::new(CsgOperation::Union)));
+        c.add_child(s1);
+        c.add_child(s2);
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+
+        let xs = c.intersect(&r);
+        assert_eq!(2, xs.len());
+        assert!(approx_eq(4.0, xs[0].t));
+        assert!(std::ptr::eq(s1_ptr, xs[0].object));
+        assert!(approx_eq(6.5, xs[1].t));
+        assert!(std::ptr::eq(s2_ptr, xs[1].object));
+    }
+
+    #[test]
+    fn a_ray_through_a_sphere_minus_a_cube() {
+        let mut s = Node::new(Box::new(Sphere::new()));
+        s.set_transform(Transform::scaling(1.5, 1.5, 1.5));
+        let c = Node::new(Box::new(Cube::new()));
+        let s_ptr = &*s as *const Node;
+        let c_ptr = &*c as *const Node;
+
+        let mut d = Node::new(Box::new(Csg::new(CsgOperation::Difference)));
+        d.add_child(s);
+        d.add_child(c);
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+
+        // 球の表面から入り、cube の表面で抜ける。反対側も同様。
+        let xs = d.intersect(&r);
+        assert_eq!(4, xs.len());
+        assert!(approx_eq(3.5, xs[0].t));
+        assert!(std::ptr::eq(s_ptr, xs[0].object));
+        assert!(approx_eq(4.0, xs[1].t));
+        assert!(std::ptr::eq(c_ptr, xs[1].object));
+        assert!(approx_eq(6.0, xs[2].t));
+        assert!(std::ptr::eq(c_ptr, xs[2].object));
+        assert!(approx_eq(6.5, xs[3].t));
+        assert!(std::ptr::eq(s_ptr, xs[3].object));
+
+        let n = xs[1].object.normal_at(&r.position(xs[1].t), &xs[1]);
+        assert_eq!(Vector3D::new(0.0, 0.0, -1.0), n);
+    }
+}
@@ -1,7 +1,14 @@
 use super::{
-    intersection::Intersection, material::Material, node::Node,
-    point3d::Point3D, ray::Ray, shape::Shape, vector3d::Vector3D, EPSILON,
-    FLOAT, INFINITY,
+    bounding_box::BoundingBox,
+    intersection::Intersection,
+    material::Material,
+    node::Node,
+    point3d::Point3D,
+    ray::Ray,
+    shape::Shape,
+    uv_mapping::{cube_face, uv_cube_face, CubeFace},
+    vector3d::Vector3D,
+    EPSILON, FLOAT, INFINITY,
 };
 
 /// Axis Aligned な cube
@@ -18,15 +25,25 @@ impl Cube {
             material: Material::new(),
         }
     }
+
+    /// local 座標系の点 p が Cube のどの面にあるかを返す。
+    /// 面内の (u, v) は uv_mapping::uv_cube_face、または面ごとの
+    /// uv_mapping::cube_uv_front などで求められる。
+    ///
+    /// # Argumets
+    /// * `p` - Cube 上の点
+    pub fn face_from_point(p: &Point3D) -> CubeFace {
+        cube_face(p)
+    }
 }
 
 impl Shape for Cube {
-    fn material(&self) -> &Material {
-        &self.material
+    fn material(&self) -> Option<&Material> {
+        Some(&self.material)
     }
 
-    fn material_mut(&mut self) -> &mut Material {
-        &mut self.material
+    fn material_mut(&mut self) -> Option<&mut Material> {
+        Some(&mut self.material)
     }
 
     fn local_intersect<'a>(
@@ -74,23 +91,12 @@ impl Shape for Cube {
         if tmin > tmax {
             vec![]
         } else {
-            vec![
-                Intersection {
-                    t: tmin,
-                    object: n,
-                    u: 0.0,
-                    v: 0.0,
-                },
-                Intersection {
-                    t: tmax,
-                    object: n,
-                    u: 0.0,
-                    v: 0.0,
-                },
-            ]
+            vec![Intersection::new(tmin, n), Intersection::new(tmax, n)]
         }
     }
 
+    /// 辺や頂点のように複数の成分の絶対値が等しい場合は、
+    /// x, y, z の順に優先して法線の向きを決める。
     fn local_normal_at(&self, p: &Point3D, _: &Intersection) -> Vector3D {
         let maxc = p.x.abs().max(p.y.abs()).max(p.z.abs());
 
@@ -102,11 +108,26 @@ impl Shape for Cube {
             Vector3D::new(0.0, 0.0, p.z)
         }
     }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point3D::new(-1.0, -1.0, -1.0),
+            Point3D::new(1.0, 1.0, 1.0),
+        )
+    }
+
+    /// p がある面を求め、その面内の (u, v) を返す。
+    fn uv_at(&self, p: &Point3D) -> (FLOAT, FLOAT) {
+        uv_cube_face(cube_face(p), p)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{super::approx_eq, super::vector3d::Vector3D, *};
+    use super::{
+        super::{approx_eq, uv_mapping::cube_uv_front, vector3d::Vector3D},
+        *,
+    };
 
     #[test]
     fn a_ray_intersects_a_cube() {
@@ -229,6 +250,7 @@ mod tests {
             object: &Node::new(Box::new(Cube::new())),
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
 
         let p = Point3D::new(1.0, 0.5, -0.8);
@@ -271,4 +293,65 @@ mod tests {
         let normal = c.local_normal_at(&p, &i);
         assert_eq!(n, normal);
     }
+
+    #[test]
+    fn the_normal_on_an_edge_of_a_cube_prefers_y_over_z() {
+        let c = Cube::new();
+        let dummy_node = Node::new(Box::new(Cube::new()));
+        let i = Intersection::new(0.0, &dummy_node);
+
+        let normal = c.local_normal_at(&Point3D::new(0.5, 1.0, -1.0), &i);
+        assert_eq!(Vector3D::new(0.0, 1.0, 0.0), normal);
+    }
+
+    #[test]
+    fn the_normal_when_a_ray_leaves_a_cube_through_a_corner() {
+        let dummy_node = Node::new(Box::new(Cube::new()));
+        let c = Cube::new();
+        let mut direction = Vector3D::new(1.0, 1.0, 1.0);
+        direction.normalize();
+        let r = Ray::new(Point3D::new(0.0, 0.0, 0.0), direction);
+
+        let xs = c.local_intersect(&r, &dummy_node);
+        assert_eq!(2, xs.len());
+        assert!(xs[0].t < 0.0);
+        assert!(0.0 < xs[1].t);
+
+        let p = r.position(xs[1].t);
+        let normal = c.local_normal_at(&p, &xs[1]);
+        assert_eq!(Vector3D::new(1.0, 0.0, 0.0), normal);
+    }
+
+    #[test]
+    fn finding_the_face_and_uv_of_a_point_on_a_cube() {
+        let p = Point3D::new(-0.5, 0.5, 1.0);
+
+        let face = Cube::face_from_point(&p);
+        assert_eq!(CubeFace::Front, face);
+
+        let (u, v) = uv_cube_face(face, &p);
+        assert!(approx_eq(0.25, u));
+        assert!(approx_eq(0.75, v));
+
+        let (u, v) = cube_uv_front(&p);
+        assert!(approx_eq(0.25, u));
+        assert!(approx_eq(0.75, v));
+    }
+
+    #[test]
+    fn the_uv_of_points_on_a_cube() {
+        let c = Cube::new();
+        let cases = [
+            (Point3D::new(-0.5, 0.5, 1.0), 0.25, 0.75),
+            (Point3D::new(1.0, 0.5, -0.5), 0.75, 0.75),
+            (Point3D::new(-0.5, 1.0, -0.5), 0.25, 0.75),
+            (Point3D::new(0.5, -1.0, 0.5), 0.75, 0.75),
+        ];
+
+        for (p, u, v) in cases.iter() {
+            let (actual_u, actual_v) = c.uv_at(p);
+            assert!(approx_eq(*u, actual_u));
+            assert!(approx_eq(*v, actual_v));
+        }
+    }
 }
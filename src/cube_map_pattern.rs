@@ -0,0 +1,166 @@
+use super::{
+    canvas::Canvas,
+    color::Color,
+    pattern::Pattern,
+    point3d::Point3D,
+    texture_pattern::uv_lookup,
+    transform::Transform,
+    uv_mapping::{cube_face, uv_cube_face, CubeFace},
+};
+
+/// 立方体の 6 面にそれぞれ画像を貼り付けるパターン。
+/// 原点を中心とした立方体の表面、または原点からの方向に対して色を返す。
+#[derive(Debug)]
+pub struct CubeMapPattern {
+    /// -x 面
+    left: Canvas,
+    /// +z 面
+    front: Canvas,
+    /// +x 面
+    right: Canvas,
+    /// -z 面
+    back: Canvas,
+    /// +y 面
+    up: Canvas,
+    /// -y 面
+    down: Canvas,
+    /// Pattern -> Shape Transform
+    transform: Transform,
+}
+
+impl CubeMapPattern {
+    /// 新規に CubeMapPattern を作成する
+    ///
+    /// # Argumets
+    /// * `left` - -x 面の画像
+    /// * `front` - +z 面の画像
+    /// * `right` - +x 面の画像
+    /// * `back` - -z 面の画像
+    /// * `up` - +y 面の画像
+    /// * `down` - -y 面の画像
+    pub fn new(
+        left: Canvas,
+        front: Canvas,
+        right: Canvas,
+        back: Canvas,
+        up: Canvas,
+        down: Canvas,
+    ) -> Self {
+        CubeMapPattern {
+            left,
+            front,
+            right,
+            back,
+            up,
+            down,
+            transform: Transform::identity(),
+        }
+    }
+
+    /// 面 face に貼り付ける画像を取得する
+    ///
+    /// # Argumets
+    /// * `face` - 立方体の面
+    fn face(&self, face: CubeFace) -> &Canvas {
+        match face {
+            CubeFace::Left => &self.left,
+            CubeFace::Front => &self.front,
+            CubeFace::Right => &self.right,
+            CubeFace::Back => &self.back,
+            CubeFace::Up => &self.up,
+            CubeFace::Down => &self.down,
+        }
+    }
+}
+
+impl Pattern for CubeMapPattern {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn transform_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+
+    fn pattern_at(&self, p: &Point3D) -> Color {
+        let face = cube_face(p);
+        // 方向として与えられた場合にも対応するため、立方体の表面に投影する
+        let coord = p.x.abs().max(p.y.abs()).max(p.z.abs());
+        let on_cube = Point3D::new(p.x / coord, p.y / coord, p.z / coord);
+        let (u, v) = uv_cube_face(face, &on_cube);
+
+        uv_lookup(self.face(face), u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(c: Color) -> Canvas {
+        let mut canvas = Canvas::new(1, 1);
+        *canvas.color_at_mut(0, 0) = c;
+        canvas
+    }
+
+    fn test_pattern() -> CubeMapPattern {
+        CubeMapPattern::new(
+            solid(Color::new(1.0, 0.0, 0.0)),
+            solid(Color::new(0.0, 1.0, 0.0)),
+            solid(Color::new(0.0, 0.0, 1.0)),
+            solid(Color::new(1.0, 1.0, 0.0)),
+            solid(Color::new(0.0, 1.0, 1.0)),
+            solid(Color::new(1.0, 0.0, 1.0)),
+        )
+    }
+
+    #[test]
+    fn a_cube_map_chooses_the_face_of_the_dominant_axis() {
+        let pattern = test_pattern();
+        let cases = [
+            (Point3D::new(-1.0, 0.5, -0.25), Color::new(1.0, 0.0, 0.0)),
+            (Point3D::new(0.1, 0.6, 0.9), Color::new(0.0, 1.0, 0.0)),
+            (Point3D::new(1.1, -0.75, 0.8), Color::new(0.0, 0.0, 1.0)),
+            (Point3D::new(-0.7, 0.0, -2.0), Color::new(1.0, 1.0, 0.0)),
+            (Point3D::new(0.5, 1.0, 0.9), Color::new(0.0, 1.0, 1.0)),
+            (Point3D::new(-0.2, -1.3, 1.1), Color::new(1.0, 0.0, 1.0)),
+        ];
+
+        for (p, c) in cases.iter() {
+            assert_eq!(*c, pattern.pattern_at(p));
+        }
+    }
+
+    #[test]
+    fn a_cube_map_looks_up_the_uv_within_a_face() {
+        // 左面のみ 2x2 の画像にする。左上から時計回りに 赤, 緑, 青, 白
+        let mut left = Canvas::new(2, 2);
+        *left.color_at_mut(0, 0) = Color::new(1.0, 0.0, 0.0);
+        *left.color_at_mut(1, 0) = Color::new(0.0, 1.0, 0.0);
+        *left.color_at_mut(1, 1) = Color::new(0.0, 0.0, 1.0);
+        *left.color_at_mut(0, 1) = Color::WHITE;
+        let pattern = CubeMapPattern::new(
+            left,
+            solid(Color::BLACK),
+            solid(Color::BLACK),
+            solid(Color::BLACK),
+            solid(Color::BLACK),
+            solid(Color::BLACK),
+        );
+
+        // 左面の (u, v) = (0.375, 0.75) は左上
+        assert_eq!(
+            Color::new(1.0, 0.0, 0.0),
+            pattern.pattern_at(&Point3D::new(-1.0, 0.5, -0.25))
+        );
+        // 方向として与えても同じ位置を参照する
+        assert_eq!(
+            Color::new(1.0, 0.0, 0.0),
+            pattern.pattern_at(&Point3D::new(-4.0, 2.0, -1.0))
+        );
+        assert_eq!(
+            Color::new(0.0, 0.0, 1.0),
+            pattern.pattern_at(&Point3D::new(-1.0, -0.9, 0.9))
+        );
+    }
+}
@@ -1,8 +1,46 @@
 use crate::{
-    approx_eq, intersection::Intersection, material::Material, node::Node,
-    point3d::Point3D, ray::Ray, shape::Shape, vector3d::Vector3D, EPSILON,
-    FLOAT, INFINITY,
+    approx_eq, bounding_box::BoundingBox, intersection::Intersection,
+    material::Material, node::Node, point3d::Point3D, ray::Ray, shape::Shape,
+    uv_mapping::uv_cylinder, vector3d::Vector3D, EPSILON, FLOAT, INFINITY,
 };
+use std::fmt;
+
+/// minimum が maximum より大きい範囲を設定しようとした際に発生するエラー
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidBoundsError {
+    /// 設定しようとした最小値
+    pub minimum: FLOAT,
+    /// 設定しようとした最大値
+    pub maximum: FLOAT,
+}
+
+impl fmt::Display for InvalidBoundsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "minimum {} is greater than maximum {}",
+            self.minimum, self.maximum
+        )
+    }
+}
+
+impl std::error::Error for InvalidBoundsError {}
+
+/// minimum <= maximum であることを確かめる。NaN を含む場合もエラーとする。
+///
+/// # Argumets
+/// * `minimum` - 最小値
+/// * `maximum` - 最大値
+pub(crate) fn check_bounds(
+    minimum: FLOAT,
+    maximum: FLOAT,
+) -> Result<(), InvalidBoundsError> {
+    if minimum <= maximum {
+        Ok(())
+    } else {
+        Err(InvalidBoundsError { minimum, maximum })
+    }
+}
 
 /// Cylinder
 #[derive(Debug)]
@@ -43,6 +81,28 @@ impl Cylinder {
         &mut self.maximum
     }
 
+    /// y 方向の範囲を設定する。
+    /// minimum_mut と maximum_mut による設定と異なり、範囲が逆転していないかを
+    /// 確かめる。
+    ///
+    /// # Argumets
+    /// * `minimum` - y の最小値
+    /// * `maximum` - y の最大値
+    ///
+    /// # Failures
+    /// minimum が maximum より大きい場合は InvalidBoundsError を返し、
+    /// 範囲は変更しない
+    pub fn set_bounds(
+        &mut self,
+        minimum: FLOAT,
+        maximum: FLOAT,
+    ) -> Result<(), InvalidBoundsError> {
+        check_bounds(minimum, maximum)?;
+        self.minimum = minimum;
+        self.maximum = maximum;
+        Ok(())
+    }
+
     pub fn closed(&self) -> bool {
         self.closed
     }
@@ -74,35 +134,25 @@ impl Cylinder {
         // by intersecting the ray with the plane at y = cyl.minimum
         let t = (self.minimum() - r.origin().y) / r.direction().y;
         if check_cap(&r, t) {
-            xs.push(Intersection {
-                t: t,
-                object: n,
-                u: 0.0,
-                v: 0.0,
-            });
+            xs.push(Intersection::new(t, n));
         }
 
         // check for an intersection with the upper end cap
         // by intersecting the ray with the plane at y = cyl.maximum
         let t = (self.maximum() - r.origin().y) / r.direction().y;
         if check_cap(&r, t) {
-            xs.push(Intersection {
-                t: t,
-                object: n,
-                u: 0.0,
-                v: 0.0,
-            });
+            xs.push(Intersection::new(t, n));
         }
     }
 }
 
 impl Shape for Cylinder {
-    fn material(&self) -> &Material {
-        &self.material
+    fn material(&self) -> Option<&Material> {
+        Some(&self.material)
     }
 
-    fn material_mut(&mut self) -> &mut Material {
-        &mut self.material
+    fn material_mut(&mut self) -> Option<&mut Material> {
+        Some(&mut self.material)
     }
 
     fn local_intersect<'a>(
@@ -128,21 +178,11 @@ impl Shape for Cylinder {
 
                 let y0 = o.y + t0 * dir.y;
                 if self.minimum() < y0 && y0 < self.maximum() {
-                    xs.push(Intersection {
-                        t: t0,
-                        object: n,
-                        u: 0.0,
-                        v: 0.0,
-                    });
+                    xs.push(Intersection::new(t0, n));
                 }
                 let y1 = o.y + t1 * dir.y;
                 if self.minimum() < y1 && y1 < self.maximum() {
-                    xs.push(Intersection {
-                        t: t1,
-                        object: n,
-                        u: 0.0,
-                        v: 0.0,
-                    });
+                    xs.push(Intersection::new(t1, n));
                 }
             }
         }
@@ -162,6 +202,18 @@ impl Shape for Cylinder {
             Vector3D::new(p.x, 0.0, p.z)
         }
     }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point3D::new(-1.0, self.minimum, -1.0),
+            Point3D::new(1.0, self.maximum, 1.0),
+        )
+    }
+
+    /// y 軸回りの角度を u、高さを v とする。
+    fn uv_at(&self, p: &Point3D) -> (FLOAT, FLOAT) {
+        uv_cylinder(p)
+    }
 }
 
 #[cfg(test)]
@@ -231,6 +283,7 @@ mod tests {
             object: &Node::new(Box::new(Cylinder::new())),
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
 
         let n = cyl.local_normal_at(&Point3D::new(1.0, 0.0, 0.0), &i);
@@ -357,6 +410,7 @@ mod tests {
             object: &Node::new(Box::new(Cylinder::new())),
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
 
         let n = cyl.local_normal_at(&Point3D::new(0.0, 1.0, 0.0), &i);
@@ -377,4 +431,50 @@ mod tests {
         let n = cyl.local_normal_at(&Point3D::new(0.0, 2.0, 0.5), &i);
         assert_eq!(Vector3D::new(0.0, 1.0, 0.0), n);
     }
+
+    #[test]
+    fn a_bounded_cylinder_has_a_bounding_box() {
+        let mut cyl = Cylinder::new();
+        *cyl.minimum_mut() = -5.0;
+        *cyl.maximum_mut() = 3.0;
+        let b = cyl.bounds();
+
+        assert_eq!(Point3D::new(-1.0, -5.0, -1.0), *b.min());
+        assert_eq!(Point3D::new(1.0, 3.0, 1.0), *b.max());
+    }
+
+    #[test]
+    fn setting_the_bounds_of_a_cylinder() {
+        let mut cyl = Cylinder::new();
+        assert_eq!(Ok(()), cyl.set_bounds(1.0, 2.0));
+        assert_eq!(1.0, cyl.minimum());
+        assert_eq!(2.0, cyl.maximum());
+
+        assert_eq!(
+            Err(InvalidBoundsError {
+                minimum: 2.0,
+                maximum: 1.0
+            }),
+            cyl.set_bounds(2.0, 1.0)
+        );
+        assert_eq!(1.0, cyl.minimum());
+        assert_eq!(2.0, cyl.maximum());
+    }
+
+    #[test]
+    fn the_uv_of_points_on_a_cylinder() {
+        let cyl = Cylinder::new();
+        let cases = [
+            (Point3D::new(0.0, 0.0, -1.0), 0.0, 0.0),
+            (Point3D::new(1.0, 0.25, 0.0), 0.75, 0.25),
+            (Point3D::new(0.0, 1.5, 1.0), 0.5, 0.5),
+            (Point3D::new(-1.0, -0.25, 0.0), 0.25, 0.75),
+        ];
+
+        for (p, u, v) in cases.iter() {
+            let (actual_u, actual_v) = cyl.uv_at(p);
+            assert!(approx_eq(*u, actual_u));
+            assert!(approx_eq(*v, actual_v));
+        }
+    }
 }
@@ -0,0 +1,161 @@
+use super::{
+    bounding_box::BoundingBox, intersection::Intersection, material::Material,
+    node::Node, point3d::Point3D, ray::Ray, shape::Shape, vector3d::Vector3D,
+    EPSILON, FLOAT,
+};
+
+/// xz 平面 (y = 0) 上にある、原点を中心とした円盤。
+/// inner_radius を指定すると中央に穴の空いた円環となる。
+#[derive(Debug)]
+pub struct Disk {
+    /// マテリアル
+    material: Material,
+    /// 半径
+    radius: FLOAT,
+    /// 内側の穴の半径
+    inner_radius: FLOAT,
+}
+
+impl Disk {
+    /// 新規に半径 1 の Disk を作成する
+    pub fn new() -> Self {
+        Disk {
+            material: Material::new(),
+            radius: 1.0,
+            inner_radius: 0.0,
+        }
+    }
+
+    pub fn radius(&self) -> FLOAT {
+        self.radius
+    }
+
+    pub fn radius_mut(&mut self) -> &mut FLOAT {
+        &mut self.radius
+    }
+
+    pub fn inner_radius(&self) -> FLOAT {
+        self.inner_radius
+    }
+
+    pub fn inner_radius_mut(&mut self) -> &mut FLOAT {
+        &mut self.inner_radius
+    }
+}
+
+impl Default for Disk {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shape for Disk {
+    fn material(&self) -> Option<&Material> {
+        Some(&self.material)
+    }
+
+    fn material_mut(&mut self) -> Option<&mut Material> {
+        Some(&mut self.material)
+    }
+
+    fn local_intersect<'a>(
+        &self,
+        r: &Ray,
+        n: &'a Node,
+    ) -> Vec<Intersection<'a>> {
+        if r.direction().y.abs() < EPSILON {
+            return vec![];
+        }
+
+        let t = -r.origin().y / r.direction().y;
+        let x = r.origin().x + t * r.direction().x;
+        let z = r.origin().z + t * r.direction().z;
+        let dist2 = x * x + z * z;
+        if dist2 < self.inner_radius * self.inner_radius
+            || self.radius * self.radius < dist2
+        {
+            return vec![];
+        }
+
+        vec![Intersection::new(t, n)]
+    }
+
+    fn local_normal_at(&self, _: &Point3D, _: &Intersection) -> Vector3D {
+        Vector3D::new(0.0, 1.0, 0.0)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point3D::new(-self.radius, 0.0, -self.radius),
+            Point3D::new(self.radius, 0.0, self.radius),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_normal_of_a_disk_is_constant_everywhere() {
+        let d = Disk::new();
+        let i = Intersection {
+            t: 0.0,
+            object: &Node::new(Box::new(Disk::new())),
+            u: 0.0,
+            v: 0.0,
+            front_face: true,
+        };
+        let n1 = d.local_normal_at(&Point3D::new(0.0, 0.0, 0.0), &i);
+        let n2 = d.local_normal_at(&Point3D::new(0.5, 0.0, -0.5), &i);
+
+        assert_eq!(Vector3D::new(0.0, 1.0, 0.0), n1);
+        assert_eq!(Vector3D::new(0.0, 1.0, 0.0), n2);
+    }
+
+    #[test]
+    fn a_ray_through_the_hole_of_a_ring_disk_misses() {
+        let dummy_node = Node::new(Box::new(Disk::new()));
+
+        let mut d = Disk::new();
+        *d.inner_radius_mut() = 0.5;
+        let r = Ray::new(
+            Point3D::new(0.0, 1.0, 0.0),
+            Vector3D::new(0.0, -1.0, 0.0),
+        );
+
+        let xs = d.local_intersect(&r, &dummy_node);
+        assert_eq!(0, xs.len());
+    }
+
+    #[test]
+    fn a_ray_through_the_annulus_of_a_ring_disk_hits() {
+        let dummy_node = Node::new(Box::new(Disk::new()));
+
+        let mut d = Disk::new();
+        *d.inner_radius_mut() = 0.5;
+        let r = Ray::new(
+            Point3D::new(0.75, 1.0, 0.0),
+            Vector3D::new(0.0, -1.0, 0.0),
+        );
+
+        let xs = d.local_intersect(&r, &*dummy_node);
+        assert_eq!(1, xs.len());
+        assert_eq!(1.0, xs[0].t);
+        assert!(std::ptr::eq(xs[0].object, &*dummy_node));
+    }
+
+    #[test]
+    fn a_ray_outside_the_radius_misses() {
+        let dummy_node = Node::new(Box::new(Disk::new()));
+
+        let d = Disk::new();
+        let r = Ray::new(
+            Point3D::new(1.5, 1.0, 0.0),
+            Vector3D::new(0.0, -1.0, 0.0),
+        );
+
+        let xs = d.local_intersect(&r, &dummy_node);
+        assert_eq!(0, xs.len());
+    }
+}
@@ -1,5 +1,6 @@
 use super::{
     color::Color, pattern::Pattern, point3d::Point3D, transform::Transform,
+    FLOAT,
 };
 
 /// x 軸方向に変化するグラデーションパターン
@@ -7,6 +8,8 @@ use super::{
 pub struct GradientPattern {
     a: Color,
     b: Color,
+    /// x 軸方向の繰り返しの周期
+    period: FLOAT,
     /// Pattern -> Shape Transform
     transform: Transform,
 }
@@ -16,9 +19,19 @@ impl GradientPattern {
         GradientPattern {
             a,
             b,
+            period: 1.0,
             transform: Transform::identity(),
         }
     }
+
+    /// 繰り返しの周期を period に設定する
+    ///
+    /// # Argumets
+    /// * `period` - 繰り返しの周期
+    pub fn with_period(mut self, period: FLOAT) -> Self {
+        self.period = period;
+        self
+    }
 }
 
 impl Pattern for GradientPattern {
@@ -32,7 +45,8 @@ impl Pattern for GradientPattern {
 
     fn pattern_at(&self, p: &Point3D) -> Color {
         let distance = &self.b - &self.a;
-        let fraction = p.x - p.x.floor();
+        let x = p.x / self.period;
+        let fraction = x - x.floor();
 
         &self.a + &(&distance * fraction)
     }
@@ -63,4 +77,23 @@ mod tests {
             pattern.pattern_at(&Point3D::new(0.75, 0.0, 0.0))
         );
     }
+
+    #[test]
+    fn a_gradient_with_a_longer_period() {
+        let pattern =
+            GradientPattern::new(Color::WHITE, Color::BLACK).with_period(2.0);
+
+        assert_eq!(
+            Color::new(0.75, 0.75, 0.75),
+            pattern.pattern_at(&Point3D::new(0.5, 0.0, 0.0))
+        );
+        assert_eq!(
+            Color::new(0.25, 0.25, 0.25),
+            pattern.pattern_at(&Point3D::new(1.5, 0.0, 0.0))
+        );
+        assert_eq!(
+            Color::WHITE,
+            pattern.pattern_at(&Point3D::new(2.0, 0.0, 0.0))
+        );
+    }
 }
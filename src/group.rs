@@ -1,23 +1,29 @@
 use crate::{
-    intersection::Intersection, material::Material, node::Node,
-    point3d::Point3D, ray::Ray, shape::Shape, vector3d::Vector3D,
+    bounding_box::BoundingBox, intersection::Intersection, node::Node,
+    point3d::Point3D, ray::Ray, shape::Shape, vector3d::Vector3D, FLOAT,
 };
 
 #[derive(Debug)]
 pub struct Group {
     /// 子 Node
     children: Vec<Box<Node>>,
+    /// 子 Node 全体を囲む BoundingBox
+    bounds: BoundingBox,
 }
 
 impl Group {
     /// 新規に Group を作成する
     pub fn new() -> Self {
-        Group { children: vec![] }
+        Group {
+            children: vec![],
+            bounds: BoundingBox::empty(),
+        }
     }
 }
 
 impl Shape for Group {
     fn add_child(&mut self, child: Box<Node>) {
+        self.bounds.add_box(&child.parent_space_bounds());
         self.children.push(child);
     }
 
@@ -25,38 +31,77 @@ impl Shape for Group {
         &self.children[idx]
     }
 
-    fn material(&self) -> &Material {
-        panic!()
+    fn child_count(&self) -> usize {
+        self.children.len()
     }
-    fn material_mut(&mut self) -> &mut Material {
-        panic!()
+
+    fn includes(&self, n: &Node) -> bool {
+        self.children.iter().any(|child| child.includes(n))
+    }
+
+    fn children(&self) -> &[Box<Node>] {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Node> {
+        self.children.iter_mut().map(|child| &mut **child).collect()
+    }
+
+    fn is_group(&self) -> bool {
+        true
+    }
+
+    fn take_children(&mut self) -> Vec<Box<Node>> {
+        self.bounds = BoundingBox::empty();
+        std::mem::take(&mut self.children)
     }
 
     fn local_intersect<'a>(
         &'a self,
         r: &Ray,
-        _n: &'a Node,
+        n: &'a Node,
     ) -> Vec<Intersection<'a>> {
         let mut xs = vec![];
+        self.local_intersect_into(r, n, &mut xs);
+        xs.sort_unstable_by(Intersection::cmp_t);
+
+        xs
+    }
+
+    /// 子 Node の交点を、一時的な Vec を作らずに out へ直接追加する。
+    fn local_intersect_into<'a>(
+        &'a self,
+        r: &Ray,
+        _n: &'a Node,
+        out: &mut Vec<Intersection<'a>>,
+    ) {
+        if !self.bounds.intersects(r) {
+            return;
+        }
 
         for child in &self.children {
-            xs.append(&mut child.intersect(r));
+            child.intersect_into(r, out);
         }
+    }
 
-        xs.sort_unstable_by(|i1, i2| {
-            if i1.t < i2.t {
-                std::cmp::Ordering::Less
-            } else {
-                std::cmp::Ordering::Greater
-            }
-        });
+    /// 子 Node を順に判定し、交差が見つかった時点で打ち切る。
+    fn local_any_hit_before(&self, r: &Ray, max_t: FLOAT, _n: &Node) -> bool {
+        if !self.bounds.intersects(r) {
+            return false;
+        }
 
-        xs
+        self.children
+            .iter()
+            .any(|child| child.any_hit_before(r, max_t))
     }
 
     fn local_normal_at(&self, _p: &Point3D, _: &Intersection) -> Vector3D {
         panic!()
     }
+
+    fn bounds(&self) -> BoundingBox {
+        self.bounds.clone()
+    }
 }
 
 #[cfg(test)]
@@ -73,6 +118,14 @@ mod tests {
         assert_eq!(0, g.children.len());
     }
 
+    #[test]
+    fn a_group_has_no_material() {
+        let mut g = Node::new(Box::new(Group::new()));
+
+        assert!(g.material().is_none());
+        assert!(g.material_mut().is_none());
+    }
+
     #[test]
     fn adding_a_child_to_a_group() {
         let mut p = Node::new(Box::new(Group::new()));
@@ -123,6 +176,28 @@ mod tests {
         assert!(std::ptr::eq(s1_ptr, xs[3].object));
     }
 
+    #[test]
+    fn intersecting_a_group_appends_to_the_buffer() {
+        let mut g = Node::new(Box::new(Group::new()));
+        let s1 = Node::new(Box::new(Sphere::new()));
+        let mut s2 = Node::new(Box::new(Sphere::new()));
+        s2.set_transform(Transform::translation(0.0, 0.0, -3.0));
+        g.add_child(s1);
+        g.add_child(s2);
+        let other = Node::new(Box::new(Sphere::new()));
+
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+        let mut xs = vec![Intersection::new(100.0, &other)];
+        g.intersect_into(&r, &mut xs);
+
+        // 既存の内容は残し、子の交点をソートせずにそのまま追加する
+        let ts: Vec<FLOAT> = xs.iter().map(|i| i.t).collect();
+        assert_eq!(vec![100.0, 4.0, 6.0, 1.0, 3.0], ts);
+    }
+
     #[test]
     fn intersecting_a_transformed_group() {
         let mut g = Node::new(Box::new(Group::new()));
@@ -160,7 +235,7 @@ mod tests {
             s_ptr
                 .as_ref()
                 .unwrap()
-                .world_to_object(&Point3D::new(-2.0, 0.0, -10.0))
+                .world_to_object(&Point3D::new(-2.0, 0.0, -10.0), 0.0)
         };
         assert_eq!(Point3D::new(0.0, 0.0, -1.0), p);
     }
@@ -181,11 +256,14 @@ mod tests {
         g1.add_child(g2);
 
         let n = unsafe {
-            s_ptr.as_ref().unwrap().normal_to_world(&Vector3D::new(
-                (3.0 as FLOAT).sqrt() / 3.0,
-                (3.0 as FLOAT).sqrt() / 3.0,
-                (3.0 as FLOAT).sqrt() / 3.0,
-            ))
+            s_ptr.as_ref().unwrap().normal_to_world(
+                &Vector3D::new(
+                    (3.0 as FLOAT).sqrt() / 3.0,
+                    (3.0 as FLOAT).sqrt() / 3.0,
+                    (3.0 as FLOAT).sqrt() / 3.0,
+                ),
+                0.0,
+            )
         };
         assert_eq!(Vector3D::new(0.285714, 0.428571, -0.857143), n);
     }
@@ -213,6 +291,7 @@ mod tests {
                     object: &g1,
                     u: 0.0,
                     v: 0.0,
+                    front_face: true,
                 },
             )
         };
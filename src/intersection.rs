@@ -9,6 +9,36 @@ pub struct Intersection<'a> {
     pub object: &'a Node,
     pub(crate) u: FLOAT,
     pub(crate) v: FLOAT,
+    /// 三角形の表側 (法線の向いている側) から交差したか。
+    /// 三角形以外のオブジェクトでは常に true となる。
+    pub front_face: bool,
+}
+
+impl<'a> Intersection<'a> {
+    /// 新規に Intersection を作成する。
+    /// u, v は 0.0、front_face は true となる。
+    ///
+    /// # Argumets
+    /// * `t` - 交差する Ray の始点からの距離
+    /// * `object` - Ray と交差したオブジェクト
+    pub fn new(t: FLOAT, object: &'a Node) -> Self {
+        Intersection {
+            t,
+            object,
+            u: 0.0,
+            v: 0.0,
+            front_face: true,
+        }
+    }
+
+    /// t の大小で 2 つの Intersection を比較する。
+    /// NaN も含めた全順序で比較するため、ソートに用いることができる。
+    ///
+    /// # Argumets
+    /// * `other` - 比較対象となる Intersection
+    pub fn cmp_t(&self, other: &Intersection) -> std::cmp::Ordering {
+        self.t.total_cmp(&other.t)
+    }
 }
 
 /// 複数の交点のうち、Ray の始点よりも先で最も手前にあるものを返す。
@@ -49,12 +79,44 @@ mod tests {
             object: &s,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
 
         assert_eq!(3.5, i.t);
         assert!(std::ptr::eq(&s as &Node, i.object));
     }
 
+    #[test]
+    fn a_new_intersection_has_zero_u_and_v() {
+        let s = Node::new(Box::new(Sphere::new()));
+        let i = Intersection::new(3.5, &s);
+
+        assert_eq!(3.5, i.t);
+        assert!(std::ptr::eq(&s as &Node, i.object));
+        assert_eq!(0.0, i.u);
+        assert_eq!(0.0, i.v);
+        assert!(i.front_face);
+    }
+
+    #[test]
+    fn sorting_intersections_by_t() {
+        let s = Node::new(Box::new(Sphere::new()));
+        let mut xs: Vec<Intersection> = [2.0, 0.0, -1.0, 2.0, 0.5]
+            .iter()
+            .map(|&t| Intersection {
+                t,
+                object: &s,
+                u: 0.0,
+                v: 0.0,
+                front_face: true,
+            })
+            .collect();
+
+        xs.sort_unstable_by(Intersection::cmp_t);
+        let ts: Vec<FLOAT> = xs.iter().map(|i| i.t).collect();
+        assert_eq!(vec![-1.0, 0.0, 0.5, 2.0, 2.0], ts);
+    }
+
     #[test]
     fn aggregating_intersections() {
         let s = Node::new(Box::new(Sphere::new()));
@@ -63,12 +125,14 @@ mod tests {
             object: &s,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let i2 = Intersection {
             t: 2.0,
             object: &s,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let xs = vec![i1, i2];
 
@@ -85,12 +149,14 @@ mod tests {
             object: &s,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let i2 = Intersection {
             t: 2.0,
             object: &s,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let xs = vec![i2, i1];
 
@@ -109,12 +175,14 @@ mod tests {
             object: &s,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let i2 = Intersection {
             t: 1.0,
             object: &s,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let xs = vec![i2, i1];
 
@@ -133,12 +201,14 @@ mod tests {
             object: &s,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let i2 = Intersection {
             t: -1.0,
             object: &s,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let xs = vec![i2, i1];
 
@@ -154,24 +224,28 @@ mod tests {
             object: &s,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let i2 = Intersection {
             t: 7.0,
             object: &s,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let i3 = Intersection {
             t: -3.0,
             object: &s,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let i4 = Intersection {
             t: 2.0,
             object: &s,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let xs = vec![i1, i2, i3, i4];
 
@@ -195,6 +269,7 @@ mod tests {
             object: &node,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
 
         let comps = IntersectionState::new(&i, &r, &vec![]);
@@ -209,6 +284,7 @@ mod tests {
             object: &node,
             u: 0.2,
             v: 0.4,
+            front_face: true,
         };
 
         assert_eq!(0.2, i.u);
@@ -30,9 +30,67 @@ pub struct IntersectionState<'a> {
     pub(crate) n2: FLOAT,
     /// Ray の起点が object 内部であるか
     pub(crate) inside: bool,
+    /// Ray が発せられた時刻
+    pub(crate) time: FLOAT,
 }
 
 impl<'a> IntersectionState<'a> {
+    /// Ray と object が交差する場所での t を取得する
+    pub fn t(&self) -> FLOAT {
+        self.t
+    }
+
+    /// Ray と交差した object を取得する
+    pub fn object(&self) -> &'a Node {
+        self.object
+    }
+
+    /// ワールド座標系における交差位置を取得する
+    pub fn point(&self) -> &Point3D {
+        &self.point
+    }
+
+    /// 法線と逆向きに point をずらした、Shape 内へ入っていく場合の起点を
+    /// 取得する。屈折した Ray の起点には用いず、屈折方向にずらした点を用いる。
+    pub fn under_point(&self) -> &Point3D {
+        &self.under_point
+    }
+
+    /// ワールド座標系における視線ベクトルを取得する
+    pub fn eyev(&self) -> &Vector3D {
+        &self.eyev
+    }
+
+    /// ワールド座標系における法線ベクトルを取得する
+    pub fn normalv(&self) -> &Vector3D {
+        &self.normalv
+    }
+
+    /// 反射方向のベクトルを取得する
+    pub fn reflectv(&self) -> &Vector3D {
+        &self.reflectv
+    }
+
+    /// Ray の起点が object 内部であるか
+    pub fn inside(&self) -> bool {
+        self.inside
+    }
+
+    /// self intersection を避けるため point をずらす距離を取得する。
+    /// 遠方の交点ほど誤差が大きくなるため、t に応じて広げる。
+    pub(crate) fn offset(&self) -> FLOAT {
+        Self::offset_at(self.t)
+    }
+
+    /// t における交点をずらす距離を求める
+    ///
+    /// # Arguments
+    ///
+    /// * `t` - 交点の t
+    fn offset_at(t: FLOAT) -> FLOAT {
+        EPSILON * t.abs().max(1.0)
+    }
+
     /// 新規に IntersectionState を作成する
     ///
     /// # Arguments
@@ -41,7 +99,7 @@ impl<'a> IntersectionState<'a> {
     /// * `r`  - Ray
     /// * `xs` - r に関する全ての交点
     pub(crate) fn new(
-        hit: &'a Intersection,
+        hit: &Intersection<'a>,
         r: &Ray,
         xs: &Vec<Intersection>,
     ) -> Self {
@@ -49,15 +107,16 @@ impl<'a> IntersectionState<'a> {
         let object = hit.object;
         let point = r.position(hit.t);
         let eyev = -r.direction();
-        let mut normalv = object.normal_at(&point, hit);
+        let mut normalv = object.normal_at_time(&point, hit, r.time());
         let inside = if normalv.dot(&eyev) < 0.0 {
             normalv = -&normalv;
             true
         } else {
             false
         };
-        let over_point = &point + &(&normalv * EPSILON);
-        let under_point = &point - &(&normalv * EPSILON);
+        let offset = Self::offset_at(t);
+        let over_point = &point + &(&normalv * offset);
+        let under_point = &point - &(&normalv * offset);
         let reflectv = r.direction().reflect(&normalv);
 
         let mut containers: Vec<&Node> = Vec::with_capacity(xs.len());
@@ -65,11 +124,10 @@ impl<'a> IntersectionState<'a> {
         let mut n2 = 1.0;
         for i in xs {
             if std::ptr::eq(i, hit) {
-                if let Some(shape) = containers.last() {
-                    n1 = shape.material().refractive_index;
-                } else {
-                    n1 = 1.0;
-                }
+                n1 = containers
+                    .last()
+                    .and_then(|shape| shape.material())
+                    .map_or(1.0, |m| m.refractive_index);
             }
 
             if let Some(pos) = containers
@@ -84,11 +142,10 @@ impl<'a> IntersectionState<'a> {
             }
 
             if std::ptr::eq(i, hit) {
-                if let Some(shape) = containers.last() {
-                    n2 = shape.material().refractive_index;
-                } else {
-                    n2 = 1.0;
-                }
+                n2 = containers
+                    .last()
+                    .and_then(|shape| shape.material())
+                    .map_or(1.0, |m| m.refractive_index);
             }
         }
 
@@ -104,11 +161,18 @@ impl<'a> IntersectionState<'a> {
             n1,
             n2,
             inside,
+            time: r.time(),
         }
     }
 
-    /// 反射と屈折の割合を計算する
+    /// 反射と屈折の割合を計算する。結果は [0, 1] の範囲に収める。
+    /// 屈折率の和が 0 となる誤った設定の場合は、屈折を計算できないため
+    /// 全て反射するものとして 1.0 を返す。
     pub(crate) fn schlick(&self) -> FLOAT {
+        if (self.n1 + self.n2).abs() < EPSILON {
+            return 1.0;
+        }
+
         let mut cos = self.eyev.dot(&self.normalv);
         // total internal reflection can only occur if n1 > n2
         if self.n1 > self.n2 {
@@ -124,7 +188,7 @@ impl<'a> IntersectionState<'a> {
         let r0 = (self.n1 - self.n2) / (self.n1 + self.n2);
         let r0 = r0 * r0;
 
-        r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+        (r0 + (1.0 - r0) * (1.0 - cos).powi(5)).clamp(0.0, 1.0)
     }
 }
 
@@ -140,8 +204,8 @@ mod tests {
 
     fn glass_sphere() -> Sphere {
         let mut sphere = Sphere::new();
-        sphere.material_mut().transparency = 1.0;
-        sphere.material_mut().refractive_index = 1.5;
+        sphere.material_mut().unwrap().transparency = 1.0;
+        sphere.material_mut().unwrap().refractive_index = 1.5;
 
         sphere
     }
@@ -158,6 +222,7 @@ mod tests {
             object: &node,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
 
         let comps = IntersectionState::new(&i, &r, &vec![]);
@@ -179,6 +244,7 @@ mod tests {
             object: &node,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
 
         let comps = IntersectionState::new(&i, &r, &vec![]);
@@ -195,6 +261,7 @@ mod tests {
             object: &node,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
 
         let comps = IntersectionState::new(&i, &r, &vec![]);
@@ -220,6 +287,7 @@ mod tests {
             object: &node,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let comps = IntersectionState::new(&i, &r, &vec![]);
 
@@ -236,17 +304,17 @@ mod tests {
     #[test]
     fn finding_n1_and_n2_at_various_intersections() {
         let mut a = glass_sphere();
-        a.material_mut().refractive_index = 1.5;
+        a.material_mut().unwrap().refractive_index = 1.5;
         let mut a = Node::new(Box::new(a));
         a.set_transform(Transform::scaling(2.0, 2.0, 2.0));
 
         let mut b = glass_sphere();
-        b.material_mut().refractive_index = 2.0;
+        b.material_mut().unwrap().refractive_index = 2.0;
         let mut b = Node::new(Box::new(b));
         b.set_transform(Transform::translation(0.0, 0.0, -0.25));
 
         let mut c = glass_sphere();
-        c.material_mut().refractive_index = 2.5;
+        c.material_mut().unwrap().refractive_index = 2.5;
         let mut c = Node::new(Box::new(c));
         c.set_transform(Transform::translation(0.0, 0.0, 0.25));
 
@@ -260,36 +328,42 @@ mod tests {
                 object: &a,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
             Intersection {
                 t: 2.75,
                 object: &b,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
             Intersection {
                 t: 3.25,
                 object: &c,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
             Intersection {
                 t: 4.75,
                 object: &b,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
             Intersection {
                 t: 5.25,
                 object: &c,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
             Intersection {
                 t: 6.0,
                 object: &a,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
         ];
 
@@ -331,6 +405,7 @@ mod tests {
             object: &node,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let xs = vec![i];
 
@@ -352,12 +427,14 @@ mod tests {
                 object: &node,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
             Intersection {
                 t: 2f32.sqrt() as FLOAT / 2.0,
                 object: &node,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
         ];
         let comps = IntersectionState::new(&xs[1], &r, &xs);
@@ -376,12 +453,14 @@ mod tests {
                 object: &node,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
             Intersection {
                 t: 1.0,
                 object: &node,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
         ];
         let comps = IntersectionState::new(&xs[1], &r, &xs);
@@ -402,10 +481,28 @@ mod tests {
             object: &node,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         }];
         let comps = IntersectionState::new(&xs[0], &r, &xs);
         let reflectance = comps.schlick();
 
         assert!(approx_eq(0.48873, reflectance));
     }
+
+    #[test]
+    fn the_schlick_approximation_with_zero_refractive_indices() {
+        let node = Node::new(Box::new(glass_sphere()));
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+        let xs = vec![Intersection::new(4.0, &node)];
+        let mut comps = IntersectionState::new(&xs[0], &r, &xs);
+        comps.n1 = 0.0;
+        comps.n2 = 0.0;
+        let reflectance = comps.schlick();
+
+        assert!(reflectance.is_finite());
+        assert_eq!(1.0, reflectance);
+    }
 }
@@ -1,10 +1,15 @@
+pub mod bounded_plane;
+pub mod bounding_box;
 pub mod camera;
 pub mod canvas;
 pub mod checkers_pattern;
 pub mod color;
 pub mod cone;
+pub mod csg;
 pub mod cube;
+pub mod cube_map_pattern;
 pub mod cylinder;
+pub mod disk;
 pub mod gradient_pattern;
 pub mod group;
 pub mod intersection;
@@ -12,19 +17,29 @@ pub mod intersection_state;
 pub mod light;
 pub mod material;
 pub mod matrix4x4;
+pub mod nested_pattern;
 pub mod node;
 pub mod obj_file;
 pub mod pattern;
 pub mod plane;
 pub mod point3d;
+pub mod random;
 pub mod ray;
 pub mod ring_pattern;
+pub mod scene;
+pub mod sdf_shape;
 pub mod shape;
 pub mod smooth_triangle;
+pub mod solid_pattern;
 pub mod sphere;
+pub mod ssao;
 pub mod stripe_pattern;
+pub mod texture_pattern;
 pub mod transform;
 pub mod triangle;
+pub mod uv_align_check_pattern;
+pub mod uv_checkers_pattern;
+pub mod uv_mapping;
 pub mod vector3d;
 pub mod world;
 
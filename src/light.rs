@@ -1,4 +1,4 @@
-use super::{color::Color, point3d::Point3D};
+use super::{color::Color, point3d::Point3D, FLOAT};
 
 /// 点光源
 #[derive(Debug)]
@@ -7,6 +7,10 @@ pub struct Light {
     position: Point3D,
     /// 色
     intensity: Color,
+    /// 柔らかい影を落とす際に光源とみなす球の半径。0 の場合は点光源となる
+    radius: FLOAT,
+    /// 柔らかい影の判定に用いる球面上の点の数
+    samples: usize,
 }
 
 impl Light {
@@ -20,9 +24,23 @@ impl Light {
         Light {
             position,
             intensity,
+            radius: 0.0,
+            samples: 1,
         }
     }
 
+    /// 光源を半径 radius の球とみなし、柔らかい影を落とすようにする。
+    ///
+    /// # Argumets
+    ///
+    /// * `radius` - 光源とみなす球の半径
+    /// * `samples` - 影の判定に用いる球面上の点の数
+    pub fn with_soft_shadow(mut self, radius: FLOAT, samples: usize) -> Self {
+        self.radius = radius;
+        self.samples = samples;
+        self
+    }
+
     /// 光源位置を取得する
     pub fn position(&self) -> &Point3D {
         &self.position
@@ -32,6 +50,16 @@ impl Light {
     pub fn intensity(&self) -> &Color {
         &self.intensity
     }
+
+    /// 柔らかい影を落とす際に光源とみなす球の半径を取得する
+    pub fn radius(&self) -> FLOAT {
+        self.radius
+    }
+
+    /// 柔らかい影の判定に用いる球面上の点の数を取得する
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
 }
 
 #[cfg(test)]
@@ -47,5 +75,15 @@ mod tests {
 
         assert_eq!(position, *light.position());
         assert_eq!(intensity, *light.intensity());
+        assert_eq!(0.0, light.radius());
+    }
+
+    #[test]
+    fn a_light_with_a_soft_shadow() {
+        let light = Light::new(Point3D::new(0.0, 0.0, 0.0), Color::WHITE)
+            .with_soft_shadow(0.5, 16);
+
+        assert_eq!(0.5, light.radius());
+        assert_eq!(16, light.samples());
     }
 }
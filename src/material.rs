@@ -1,8 +1,17 @@
 use super::{
-    color::Color, light::Light, node::Node, pattern::Pattern, point3d::Point3D,
-    vector3d::Vector3D, FLOAT,
+    approx_eq, color::Color, light::Light, node::Node, pattern::Pattern,
+    point3d::Point3D, vector3d::Vector3D, FLOAT,
 };
 
+/// 鏡面反射光の計算方法
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpecularModel {
+    /// 反射ベクトルと視線ベクトルから計算する
+    Phong,
+    /// 光源方向と視線方向の中間ベクトルと法線から計算する
+    BlinnPhong,
+}
+
 /// マテリアル
 #[derive(Debug)]
 pub struct Material {
@@ -16,14 +25,30 @@ pub struct Material {
     pub specular: FLOAT,
     /// 鏡面反射光の広がり。大きい程、狭く強い。
     pub shininess: FLOAT,
+    /// 鏡面反射光の計算方法
+    pub specular_model: SpecularModel,
+    /// 鏡面反射光を Schlick の近似によるフレネル係数で変調するか
+    pub fresnel: bool,
     /// 反射率(0.0 で反射なし、1.0 で完全反射)
     pub reflective: FLOAT,
+    /// 反射光に乗算する色。None の場合は反射率のみを適用する。
+    pub reflect_color: Option<Color>,
     /// 透明度(0.0 で不透明、1.0 で完全透明)
     pub transparency: FLOAT,
     /// 屈折率
     pub refractive_index: FLOAT,
+    /// 不透明度 (0.0 で完全に透過、1.0 で不透明)。
+    /// 表面の色と、屈折せずにそのまま透過した先の色を混ぜる割合を表す。
+    pub opacity: FLOAT,
+    /// 影を落とすか
+    pub casts_shadow: bool,
+    /// 自己発光色。光源や影によらず加算される。
+    pub emission: Color,
     /// パターン。None の場合は使用しない。
     pattern: Option<Box<dyn Pattern>>,
+    /// 法線を摂動させるパターン。色を接空間の法線として解釈する。
+    /// None の場合は使用しない。
+    normal_pattern: Option<Box<dyn Pattern>>,
 }
 
 impl Material {
@@ -35,10 +60,60 @@ impl Material {
             diffuse: 0.9,
             specular: 0.9,
             shininess: 200.0,
+            specular_model: SpecularModel::Phong,
+            fresnel: false,
             reflective: 0.0,
+            reflect_color: None,
             transparency: 0.0,
             refractive_index: 1.0,
+            opacity: 1.0,
+            casts_shadow: true,
+            emission: Color::BLACK,
             pattern: None,
+            normal_pattern: None,
+        }
+    }
+
+    /// ガラスの Material を作成する。
+    /// 色はほぼ黒で、光の大部分を屈折させ、一部を反射する。
+    pub fn glass() -> Self {
+        Material {
+            color: Color::BLACK,
+            ambient: 0.0,
+            diffuse: 0.1,
+            specular: 1.0,
+            shininess: 300.0,
+            reflective: 1.0,
+            transparency: 1.0,
+            refractive_index: 1.5,
+            ..Material::new()
+        }
+    }
+
+    /// 鏡の Material を作成する。
+    /// 光をほぼ全て反射し、自身の色はほとんど寄与しない。
+    pub fn mirror() -> Self {
+        Material {
+            color: Color::BLACK,
+            ambient: 0.0,
+            diffuse: 0.0,
+            specular: 1.0,
+            shininess: 300.0,
+            reflective: 1.0,
+            ..Material::new()
+        }
+    }
+
+    /// 艶のない Material を作成する。
+    /// 鏡面反射光を持たず、拡散反射光のみで照らされる。
+    ///
+    /// # Argumets
+    /// * `color` - 色
+    pub fn matte(color: Color) -> Self {
+        Material {
+            color,
+            specular: 0.0,
+            ..Material::new()
         }
     }
 
@@ -52,7 +127,81 @@ impl Material {
         &mut self.pattern
     }
 
+    /// 法線を摂動させるパターンを取得する
+    pub fn normal_pattern(&self) -> &Option<Box<dyn Pattern>> {
+        &self.normal_pattern
+    }
+
+    /// 法線を摂動させるパターンを取得する
+    pub fn normal_pattern_mut(&mut self) -> &mut Option<Box<dyn Pattern>> {
+        &mut self.normal_pattern
+    }
+
+    /// 法線パターンに従って local 座標系の法線ベクトルを摂動させる。
+    /// パターンの色 (r, g, b) を [-1, 1] に変換し、接線、従法線、法線を
+    /// 軸とする接空間のベクトルとして解釈する。
+    ///
+    /// # Argumets
+    /// * `p` - local 座標系の点
+    /// * `normalv` - p における local 座標系の法線ベクトル
+    pub(crate) fn perturb_normal(
+        &self,
+        p: &Point3D,
+        mut normalv: Vector3D,
+    ) -> Vector3D {
+        let pattern = match self.normal_pattern {
+            Some(ref pattern) => pattern,
+            None => return normalv,
+        };
+        let c = pattern.pattern_at(&(pattern.transform().inv() * p));
+        let tx = 2.0 * c.red - 1.0;
+        let ty = 2.0 * c.green - 1.0;
+        let tz = 2.0 * c.blue - 1.0;
+        // Cylinder などの local 座標系の法線は単位ベクトルとは限らないため、
+        // 正規化してから接空間を求める
+        normalv.normalize();
+
+        // 法線と平行にならない軸から接線を求める
+        let axis = if normalv.y.abs() < 0.999 {
+            Vector3D::new(0.0, 1.0, 0.0)
+        } else {
+            Vector3D::new(1.0, 0.0, 0.0)
+        };
+        let mut tangent = axis.cross(&normalv);
+        tangent.normalize();
+        let bitangent = normalv.cross(&tangent);
+
+        let mut n = &(&(&tangent * tx) + &(&bitangent * ty)) + &(&normalv * tz);
+        n.normalize();
+        n
+    }
+
+    /// 時刻 time において object 上の点 point における色を返す。
+    /// パターンが設定されている場合はパターンの色、それ以外は color となる。
+    ///
+    /// # Argumets
+    ///
+    /// * `object` - この Material を持つオブジェクト
+    /// * `point` - World 座標系における点
+    /// * `time` - Ray が発せられた時刻
+    pub fn color_at_shape(
+        &self,
+        object: &Node,
+        point: &Point3D,
+        time: FLOAT,
+    ) -> Color {
+        // color は SolidPattern と同じ結果になるが、pattern_at_shape を通すと
+        // 全ての交点で object 座標系への変換が必要になるため、直接用いる
+        match self.pattern {
+            Some(ref pattern) => {
+                pattern.pattern_at_shape_at(object, point, time)
+            }
+            None => self.color,
+        }
+    }
+
     /// ライティングの計算を行う。
+    /// パターンは時刻 0 において評価する。
     ///
     /// # Argumets
     ///
@@ -60,7 +209,8 @@ impl Material {
     /// * `point` - 計算を行うオブジェクト上の点
     /// * `eyev` - 視線ベクトル
     /// * `normalv` - point における法線ベクトル
-    /// * `in_shadow` - 影の中にいるか
+    /// * `light_intensity` - 光源が遮られずに届く割合 (0.0 - 1.0)。
+    ///   拡散反射光と鏡面反射光をこの割合で減衰させる。環境光は常に加える。
     pub fn lighting(
         &self,
         object: &Node,
@@ -68,18 +218,45 @@ impl Material {
         point: &Point3D,
         eyev: &Vector3D,
         normalv: &Vector3D,
-        in_shadow: bool,
+        light_intensity: FLOAT,
     ) -> Color {
-        let color = match self.pattern {
-            Some(ref pattern) => pattern.pattern_at_shape(object, &point),
-            None => self.color,
-        };
+        let color = self.color_at_shape(object, point, 0.0);
+        self.lighting_with_color(
+            &color,
+            light,
+            point,
+            eyev,
+            normalv,
+            light_intensity,
+        )
+    }
 
-        let effective_color = &color * light.intensity();
+    /// 表面の色を color としてライティングの計算を行う。
+    /// 時刻によって変わる色は、color_at_shape で求めてから渡す。
+    ///
+    /// # Argumets
+    ///
+    /// * `color` - point における表面の色
+    /// * `light` - 光源
+    /// * `point` - 計算を行うオブジェクト上の点
+    /// * `eyev` - 視線ベクトル
+    /// * `normalv` - point における法線ベクトル
+    /// * `light_intensity` - 光源が遮られずに届く割合 (0.0 - 1.0)。
+    ///   拡散反射光と鏡面反射光をこの割合で減衰させる。環境光は常に加える。
+    pub fn lighting_with_color(
+        &self,
+        color: &Color,
+        light: &Light,
+        point: &Point3D,
+        eyev: &Vector3D,
+        normalv: &Vector3D,
+        light_intensity: FLOAT,
+    ) -> Color {
+        let effective_color = color * light.intensity();
         let mut lightv = light.position() - point;
         lightv.normalize();
         let ambient = &effective_color * self.ambient;
-        if in_shadow {
+        if light_intensity <= 0.0 {
             return ambient;
         }
 
@@ -90,27 +267,125 @@ impl Material {
             return ambient;
         } else {
             diffuse = &(&effective_color * self.diffuse) * light_dot_normal;
-            let reflectv = (-&lightv).reflect(&normalv);
-            let reflect_dot_eye = reflectv.dot(eyev);
-            if reflect_dot_eye <= 0.0 {
+            let specular_dot = match self.specular_model {
+                SpecularModel::Phong => {
+                    let reflectv = (-&lightv).reflect(normalv);
+                    reflectv.dot(eyev)
+                }
+                SpecularModel::BlinnPhong => {
+                    let mut halfway = &lightv + eyev;
+                    halfway.normalize();
+                    normalv.dot(&halfway)
+                }
+            };
+            if specular_dot <= 0.0 {
                 specular = Color::BLACK;
             } else {
-                let factor = reflect_dot_eye.powf(self.shininess);
+                let mut factor = specular_dot.powf(self.shininess);
+                if self.fresnel {
+                    factor *= self.schlick(eyev.dot(normalv));
+                }
                 specular = self.specular * factor * light.intensity();
             }
         }
 
-        &(&ambient + &diffuse) + &specular
+        &ambient + &(&(&diffuse + &specular) * light_intensity)
+    }
+
+    /// 空気中から表面を見たときの、Schlick の近似による反射率を返す。
+    ///
+    /// # Argumets
+    /// * `cos` - 視線ベクトルと法線ベクトルのなす角の cos
+    fn schlick(&self, cos: FLOAT) -> FLOAT {
+        let r0 = (1.0 - self.refractive_index) / (1.0 + self.refractive_index);
+        let r0 = r0 * r0;
+
+        r0 + (1.0 - r0) * (1.0 - cos.max(0.0)).powi(5)
+    }
+}
+
+impl PartialEq for Material {
+    /// 2 つの Material が等しいかをテストする。
+    /// float 同士の比較なので、ある程度の誤差を許容する。
+    /// パターンは同一のインスタンスを指している場合のみ等しいとみなす。
+    ///
+    /// # Argumets
+    ///
+    /// * `other` - 比較対象となる Material
+    fn eq(&self, other: &Material) -> bool {
+        let same_pattern =
+            |a: &Option<Box<dyn Pattern>>, b: &Option<Box<dyn Pattern>>| match (
+                a, b,
+            ) {
+                (None, None) => true,
+                (Some(a), Some(b)) => std::ptr::eq(
+                    &**a as *const _ as *const (),
+                    &**b as *const _ as *const (),
+                ),
+                _ => false,
+            };
+
+        self.color == other.color
+            && approx_eq(self.ambient, other.ambient)
+            && approx_eq(self.diffuse, other.diffuse)
+            && approx_eq(self.specular, other.specular)
+            && approx_eq(self.shininess, other.shininess)
+            && self.specular_model == other.specular_model
+            && self.fresnel == other.fresnel
+            && approx_eq(self.reflective, other.reflective)
+            && self.reflect_color == other.reflect_color
+            && approx_eq(self.transparency, other.transparency)
+            && approx_eq(self.refractive_index, other.refractive_index)
+            && self.casts_shadow == other.casts_shadow
+            && self.emission == other.emission
+            && same_pattern(&self.pattern, &other.pattern)
+            && same_pattern(&self.normal_pattern, &other.normal_pattern)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        super::{sphere::Sphere, stripe_pattern::StripePattern},
+        super::{
+            solid_pattern::SolidPattern, sphere::Sphere,
+            stripe_pattern::StripePattern, transform::Transform,
+        },
         *,
     };
 
+    #[test]
+    fn perturbing_a_normal_that_is_not_a_unit_vector() {
+        let mut m = Material::new();
+        *m.normal_pattern_mut() =
+            Some(Box::new(SolidPattern::new(Color::new(1.0, 0.5, 1.0))));
+        let p = Point3D::new(0.0, 0.0, -1.0);
+
+        // Cylinder などの法線は正規化されていないが、摂動の強さは変わらない
+        assert_eq!(
+            m.perturb_normal(&p, Vector3D::new(0.0, 0.0, -1.0)),
+            m.perturb_normal(&p, Vector3D::new(0.0, 0.0, -3.0))
+        );
+    }
+
+    #[test]
+    fn material_presets() {
+        let glass = Material::glass();
+        assert_eq!(1.0, glass.transparency);
+        assert_eq!(1.5, glass.refractive_index);
+        assert_eq!(1.0, glass.reflective);
+
+        let mirror = Material::mirror();
+        assert_eq!(1.0, mirror.reflective);
+        assert_eq!(0.0, mirror.transparency);
+        assert_eq!(0.0, mirror.diffuse);
+
+        let red = Color::new(1.0, 0.0, 0.0);
+        let matte = Material::matte(red);
+        assert_eq!(red, matte.color);
+        assert_eq!(0.0, matte.specular);
+        assert_eq!(0.0, matte.reflective);
+    }
+
     #[test]
     fn the_default_material() {
         let m = Material::new();
@@ -120,6 +395,8 @@ mod tests {
         assert_eq!(0.9, m.diffuse);
         assert_eq!(0.9, m.specular);
         assert_eq!(200.0, m.shininess);
+        assert_eq!(SpecularModel::Phong, m.specular_model);
+        assert!(m.casts_shadow);
     }
 
     #[test]
@@ -131,7 +408,7 @@ mod tests {
         let normalv = Vector3D::new(0.0, 0.0, -1.0);
         let light = Light::new(Point3D::new(0.0, 0.0, -10.0), Color::WHITE);
 
-        let result = m.lighting(&object, &light, &p, &eyev, &normalv, false);
+        let result = m.lighting(&object, &light, &p, &eyev, &normalv, 1.0);
         assert_eq!(Color::new(1.9, 1.9, 1.9), result);
     }
 
@@ -148,7 +425,7 @@ mod tests {
         let normalv = Vector3D::new(0.0, 0.0, -1.0);
         let light = Light::new(Point3D::new(0.0, 0.0, -10.0), Color::WHITE);
 
-        let result = m.lighting(&object, &light, &p, &eyev, &normalv, false);
+        let result = m.lighting(&object, &light, &p, &eyev, &normalv, 1.0);
         assert_eq!(Color::new(1.0, 1.0, 1.0), result);
     }
 
@@ -161,7 +438,7 @@ mod tests {
         let normalv = Vector3D::new(0.0, 0.0, -1.0);
         let light = Light::new(Point3D::new(0.0, 10.0, -10.0), Color::WHITE);
 
-        let result = m.lighting(&object, &light, &p, &eyev, &normalv, false);
+        let result = m.lighting(&object, &light, &p, &eyev, &normalv, 1.0);
         assert_eq!(Color::new(0.7364, 0.7364, 0.7364), result);
     }
 
@@ -178,10 +455,66 @@ mod tests {
         let normalv = Vector3D::new(0.0, 0.0, -1.0);
         let light = Light::new(Point3D::new(0.0, 10.0, -10.0), Color::WHITE);
 
-        let result = m.lighting(&object, &light, &p, &eyev, &normalv, false);
+        let result = m.lighting(&object, &light, &p, &eyev, &normalv, 1.0);
         assert_eq!(Color::new(1.6364, 1.6364, 1.6364), result);
     }
 
+    #[test]
+    fn blinn_phong_gives_a_broader_highlight_than_phong() {
+        let mut phong = Material::new();
+        phong.shininess = 10.0;
+        let mut blinn = Material::new();
+        blinn.shininess = 10.0;
+        blinn.specular_model = SpecularModel::BlinnPhong;
+        let object = Node::new(Box::new(Sphere::new()));
+        let p = Point3D::new(0.0, 0.0, 0.0);
+        let eyev = Vector3D::new(
+            0.0,
+            2f32.sqrt() as FLOAT / 2.0,
+            -2f32.sqrt() as FLOAT / 2.0,
+        );
+        let normalv = Vector3D::new(0.0, 0.0, -1.0);
+        let light = Light::new(Point3D::new(0.0, 0.0, -10.0), Color::WHITE);
+
+        let phong_result =
+            phong.lighting(&object, &light, &p, &eyev, &normalv, 1.0);
+        let blinn_result =
+            blinn.lighting(&object, &light, &p, &eyev, &normalv, 1.0);
+
+        // Phong: 0.9 * cos(45°)^10, Blinn-Phong: 0.9 * cos(22.5°)^10
+        assert_eq!(Color::new(1.02813, 1.02813, 1.02813), phong_result);
+        assert_eq!(Color::new(1.40775, 1.40775, 1.40775), blinn_result);
+    }
+
+    #[test]
+    fn fresnel_strengthens_the_specular_highlight_at_glancing_angles() {
+        let mut m = Material::new();
+        m.shininess = 1.0;
+        m.refractive_index = 1.5;
+        let object = Node::new(Box::new(Sphere::new()));
+        let p = Point3D::new(0.0, 0.0, 0.0);
+        let normalv = Vector3D::new(0.0, 0.0, -1.0);
+        let light = Light::new(Point3D::new(0.0, 0.0, -10.0), Color::WHITE);
+        let head_on = Vector3D::new(0.0, 0.0, -1.0);
+        let angle = 80f64.to_radians() as FLOAT;
+        let edge_on = Vector3D::new(0.0, angle.sin(), -angle.cos());
+
+        // フレネル係数なしでは正面から見た方が明るい
+        let head_on_result =
+            m.lighting(&object, &light, &p, &head_on, &normalv, 1.0);
+        let edge_on_result =
+            m.lighting(&object, &light, &p, &edge_on, &normalv, 1.0);
+        assert!(edge_on_result.red < head_on_result.red);
+
+        // 拡散反射光は同じなので、差は鏡面反射光によるもの
+        m.fresnel = true;
+        let head_on_result =
+            m.lighting(&object, &light, &p, &head_on, &normalv, 1.0);
+        let edge_on_result =
+            m.lighting(&object, &light, &p, &edge_on, &normalv, 1.0);
+        assert!(head_on_result.red < edge_on_result.red);
+    }
+
     #[test]
     fn lighting_with_the_light_behind_the_surface() {
         let m = Material::new();
@@ -191,7 +524,7 @@ mod tests {
         let normalv = Vector3D::new(0.0, 0.0, -1.0);
         let light = Light::new(Point3D::new(0.0, 0.0, 10.0), Color::WHITE);
 
-        let result = m.lighting(&object, &light, &p, &eyev, &normalv, false);
+        let result = m.lighting(&object, &light, &p, &eyev, &normalv, 1.0);
         assert_eq!(Color::new(0.1, 0.1, 0.1), result);
     }
 
@@ -203,13 +536,47 @@ mod tests {
         let eyev = Vector3D::new(0.0, 0.0, -1.0);
         let normalv = Vector3D::new(0.0, 0.0, -1.0);
         let light = Light::new(Point3D::new(0.0, 0.0, -10.0), Color::WHITE);
-        let in_shadow = true;
+        let light_intensity = 0.0;
 
         let result =
-            m.lighting(&object, &light, &p, &eyev, &normalv, in_shadow);
+            m.lighting(&object, &light, &p, &eyev, &normalv, light_intensity);
         assert_eq!(Color::new(0.1, 0.1, 0.1), result);
     }
 
+    #[test]
+    fn lighting_with_a_partially_occluded_light() {
+        let m = Material::new();
+        let object = Node::new(Box::new(Sphere::new()));
+        let p = Point3D::new(0.0, 0.0, 0.0);
+        let eyev = Vector3D::new(0.0, 0.0, -1.0);
+        let normalv = Vector3D::new(0.0, 0.0, -1.0);
+        let light = Light::new(Point3D::new(0.0, 0.0, -10.0), Color::WHITE);
+
+        // 環境光 0.1 + (拡散反射光 0.9 + 鏡面反射光 0.9) * 0.5
+        let result = m.lighting(&object, &light, &p, &eyev, &normalv, 0.5);
+        assert_eq!(Color::new(1.0, 1.0, 1.0), result);
+    }
+
+    #[test]
+    fn lighting_with_a_color_matches_a_solid_pattern() {
+        let mut solid = Material::new();
+        solid.color = Color::new(0.2, 0.4, 0.6);
+        *solid.pattern_mut() = Some(Box::new(SolidPattern::new(solid.color)));
+        let mut plain = Material::new();
+        plain.color = Color::new(0.2, 0.4, 0.6);
+        let mut object = Node::new(Box::new(Sphere::new()));
+        object.set_transform(Transform::translation(1.0, 2.0, 3.0));
+        let p = Point3D::new(1.0, 2.0, 2.0);
+        let eyev = Vector3D::new(0.0, 0.0, -1.0);
+        let normalv = Vector3D::new(0.0, 0.0, -1.0);
+        let light = Light::new(Point3D::new(0.0, 10.0, -10.0), Color::WHITE);
+
+        assert_eq!(
+            solid.lighting(&object, &light, &p, &eyev, &normalv, 1.0),
+            plain.lighting(&object, &light, &p, &eyev, &normalv, 1.0)
+        );
+    }
+
     #[test]
     fn lighting_with_a_pattern_applied() {
         let mut m = Material::new();
@@ -229,7 +596,7 @@ mod tests {
             &Point3D::new(0.9, 0.0, 0.0),
             &eyev,
             &normalv,
-            false,
+            1.0,
         );
         let c2 = m.lighting(
             &object,
@@ -237,7 +604,7 @@ mod tests {
             &Point3D::new(1.1, 0.0, 0.0),
             &eyev,
             &normalv,
-            false,
+            1.0,
         );
 
         assert_eq!(Color::WHITE, c1);
@@ -258,4 +625,28 @@ mod tests {
         assert_eq!(0.0, m.transparency);
         assert_eq!(1.0, m.refractive_index);
     }
+
+    #[test]
+    fn comparing_materials() {
+        let m1 = Material::new();
+        let mut m2 = Material::new();
+
+        assert_eq!(m1, m2);
+
+        m2.ambient = 0.5;
+        assert_ne!(m1, m2);
+    }
+
+    #[test]
+    fn materials_with_distinct_patterns_are_not_equal() {
+        let mut m1 = Material::new();
+        let mut m2 = Material::new();
+        *m1.pattern_mut() =
+            Some(Box::new(StripePattern::new(Color::WHITE, Color::BLACK)));
+        *m2.pattern_mut() =
+            Some(Box::new(StripePattern::new(Color::WHITE, Color::BLACK)));
+
+        assert_ne!(m1, m2);
+        assert_ne!(Material::new(), m1);
+    }
 }
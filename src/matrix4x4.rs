@@ -3,7 +3,7 @@ use std::{cmp::PartialEq, ops::Mul};
 use super::{approx_eq, point3d::Point3D, ray::Ray, vector3d::Vector3D, FLOAT};
 
 /// 4x4 行列を表す。
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Matrix4x4 {
     m: [FLOAT; 16],
 }
@@ -83,11 +83,33 @@ impl Matrix4x4 {
         (0..4).map(|i| self.m[i] * self.cofactor(0, i)).sum()
     }
 
+    /// self と other を要素ごとに線形補間した行列を作成する。
+    ///
+    /// # Argumets
+    /// * `other` - t = 1 における行列
+    /// * `t` - 補間パラメータ
+    pub fn lerp(&self, other: &Matrix4x4, t: FLOAT) -> Self {
+        let mut m = [0.0; 16];
+        for (i, e) in m.iter_mut().enumerate() {
+            *e = self.m[i] + (other.m[i] - self.m[i]) * t;
+        }
+        Matrix4x4 { m }
+    }
+
     /// self の逆行列を作成する。
+    ///
+    /// # Panics
+    /// self が正則でない場合
     pub fn inverse(&self) -> Self {
+        self.try_inverse().expect("matrix is not invertible")
+    }
+
+    /// self の逆行列を作成する。
+    /// self が正則でない場合は None を返す。
+    pub fn try_inverse(&self) -> Option<Self> {
         let det = self.determinant();
         if det == 0.0 {
-            panic!();
+            return None;
         }
 
         let inv_det = 1.0 / det;
@@ -100,7 +122,7 @@ impl Matrix4x4 {
             }
         }
 
-        Matrix4x4::new(m)
+        Some(Matrix4x4::new(m))
     }
 }
 
@@ -198,7 +220,7 @@ impl Mul<&Ray> for &Matrix4x4 {
         let o = self * r.origin();
         let d = self * r.direction();
 
-        Ray::new(o, d)
+        Ray::new(o, d).with_time(r.time())
     }
 }
 
@@ -494,6 +516,27 @@ mod tests {
         assert_eq!(0.0, mat.determinant());
     }
 
+    #[test]
+    fn a_singular_matrix_has_no_inverse() {
+        // 2 行目が 1 行目の 2 倍
+        let mat = Matrix4x4::new([
+            1.0, 2.0, 3.0, 4.0, 2.0, 4.0, 6.0, 8.0, 0.0, 1.0, 0.0, 2.0, 5.0,
+            0.0, 1.0, 3.0,
+        ]);
+        assert!(mat.try_inverse().is_none());
+
+        let mat = Matrix4x4::new([
+            -4.0, 2.0, -2.0, -3.0, 9.0, 6.0, 2.0, 6.0, 0.0, -5.0, 1.0, -5.0,
+            0.0, 0.0, 0.0, 0.0,
+        ]);
+        assert!(mat.try_inverse().is_none());
+
+        assert_eq!(
+            Some(Matrix4x4::identity()),
+            Matrix4x4::identity().try_inverse()
+        );
+    }
+
     #[test]
     fn calculating_the_inverse_of_a_matrix() {
         let mat = Matrix4x4::new([
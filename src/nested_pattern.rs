@@ -0,0 +1,82 @@
+use super::{
+    color::Color, pattern::Pattern, point3d::Point3D, transform::Transform,
+};
+
+/// 市松模様の各マスに別のパターンを入れ子にしたパターン
+#[derive(Debug)]
+pub struct NestedPattern {
+    a: Box<dyn Pattern>,
+    b: Box<dyn Pattern>,
+    /// Pattern -> Shape Transform
+    transform: Transform,
+}
+
+impl NestedPattern {
+    /// 新規に NestedPattern を作成する
+    ///
+    /// # Argumets
+    /// * `a` - 原点を含むマスのパターン
+    /// * `b` - a に隣接するマスのパターン
+    pub fn new(a: Box<dyn Pattern>, b: Box<dyn Pattern>) -> Self {
+        NestedPattern {
+            a,
+            b,
+            transform: Transform::identity(),
+        }
+    }
+}
+
+impl Pattern for NestedPattern {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn transform_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+
+    fn pattern_at(&self, p: &Point3D) -> Color {
+        let sum = p.x.floor() + p.y.floor() + p.z.floor();
+        let pattern = if sum as i32 % 2 == 0 {
+            &self.a
+        } else {
+            &self.b
+        };
+
+        // 子パターン自身の変換を適用してから評価する
+        let sub_p = pattern.transform().inv() * p;
+        pattern.pattern_at(&sub_p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{stripe_pattern::StripePattern, FLOAT},
+        *,
+    };
+
+    #[test]
+    fn a_checker_of_two_stripe_patterns() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let green = Color::new(0.0, 1.0, 0.0);
+        let a = StripePattern::new(Color::WHITE, Color::BLACK).with_period(0.5);
+        let mut b = StripePattern::new(red, green).with_period(0.5);
+        *b.transform_mut() =
+            Transform::rotation_y(std::f64::consts::FRAC_PI_2 as FLOAT);
+        let pattern = NestedPattern::new(Box::new(a), Box::new(b));
+
+        // (0, 0, 0) のマスは x 方向の白黒の縞
+        assert_eq!(
+            Color::WHITE,
+            pattern.pattern_at(&Point3D::new(0.25, 0.0, 0.75))
+        );
+        assert_eq!(
+            Color::BLACK,
+            pattern.pattern_at(&Point3D::new(0.75, 0.0, 0.25))
+        );
+        // 隣のマスは z 方向の赤緑の縞
+        assert_eq!(green, pattern.pattern_at(&Point3D::new(1.75, 0.0, 0.25)));
+        assert_eq!(red, pattern.pattern_at(&Point3D::new(1.25, 0.0, 0.75)));
+    }
+}
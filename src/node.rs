@@ -1,8 +1,20 @@
 use crate::{
-    intersection::Intersection, material::Material, point3d::Point3D, ray::Ray,
-    shape::Shape, transform::Transform, vector3d::Vector3D,
+    bounding_box::BoundingBox, group::Group, intersection::Intersection,
+    material::Material, point3d::Point3D, ray::Ray, shape::Shape,
+    transform::Transform, vector3d::Vector3D, FLOAT,
 };
-use std::ptr::NonNull;
+use std::{cell::Cell, ptr::NonNull};
+
+thread_local! {
+    /// このスレッドで行った、子を持たない Node と Ray の交差判定の回数
+    static INTERSECTION_TESTS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// このスレッドでこれまでに行った、子を持たない Node と Ray の交差判定の
+/// 回数を取得する。差を取ることで、ある処理の間の回数を求められる。
+pub(crate) fn intersection_tests() -> usize {
+    INTERSECTION_TESTS.with(|c| c.get())
+}
 
 #[derive(Debug)]
 pub struct Node {
@@ -12,6 +24,16 @@ pub struct Node {
     transform: Transform,
     /// 本体
     shape: Box<dyn Shape>,
+    /// 表示するか。false の場合、Ray と交差しない (影も落とさない)。
+    visible: bool,
+    /// finalize で計算した World 座標系への変換。
+    /// None の場合は親 Node をたどって計算する。
+    world_transform: Option<Transform>,
+    /// 時刻 0 と 1 における親 Node の座標系への変換。
+    /// 設定されている場合、transform の代わりに時刻で補間した変換を用いる。
+    motion: Option<(Transform, Transform)>,
+    /// 描画対象を絞り込むための名前
+    tag: Option<String>,
 }
 
 impl Node {
@@ -24,6 +46,10 @@ impl Node {
             parent: None,
             transform: Transform::identity(),
             shape,
+            visible: true,
+            world_transform: None,
+            motion: None,
+            tag: None,
         })
     }
 
@@ -36,10 +62,167 @@ impl Node {
         self.shape.add_child(child);
     }
 
+    /// 複数の子 Node をまとめて追加する
+    ///
+    /// # Argumets
+    /// * `children` - 追加する Node
+    pub fn add_children(&mut self, children: Vec<Box<Node>>) {
+        for child in children {
+            self.add_child(child);
+        }
+    }
+
     pub fn child_at(&self, idx: usize) -> &Box<Node> {
         self.shape.child_at(idx)
     }
 
+    /// 子 Node を全て取得する。子を持たない場合は空の slice を返す。
+    pub fn children(&self) -> &[Box<Node>] {
+        self.shape.children()
+    }
+
+    /// World 上の点 p に対応する、Shape 固有の (u, v) を取得する。
+    ///
+    /// # Argumets
+    /// * `p` - World 座標系における点
+    pub fn uv_at(&self, p: &Point3D) -> (FLOAT, FLOAT) {
+        self.uv_at_time(p, 0.0)
+    }
+
+    /// 時刻 time において World 上の点 p に対応する、Shape 固有の (u, v) を
+    /// 取得する。
+    ///
+    /// # Argumets
+    /// * `p` - World 座標系における点
+    /// * `time` - Ray が発せられた時刻
+    pub fn uv_at_time(&self, p: &Point3D, time: FLOAT) -> (FLOAT, FLOAT) {
+        self.shape.uv_at(&self.world_to_object(p, time))
+    }
+
+    /// 三角形 (Triangle, SmoothTriangle) であるか
+    pub fn is_triangle(&self) -> bool {
+        self.shape.is_triangle()
+    }
+
+    /// 子 Node の数を取得する
+    pub fn child_count(&self) -> usize {
+        self.shape.child_count()
+    }
+
+    /// n が self 自身または self の子孫であるか
+    ///
+    /// # Argumets
+    /// * `n` - 判定対象の Node
+    pub fn includes(&self, n: &Node) -> bool {
+        std::ptr::eq(self, n) || self.shape.includes(n)
+    }
+
+    /// 子 Node の数が threshold より多い場合、BoundingBox を最も長い軸で
+    /// 2 分割し、どちらかに収まる子 Node を新しい Group にまとめる。
+    /// 分割面をまたぐ子 Node は self に残す。子 Node に対しても再帰的に行う。
+    /// Csg のように子 Node の構成に意味がある Shape は分割せず、
+    /// 子 Node に対してのみ行う。
+    ///
+    /// # Argumets
+    /// * `threshold` - 分割を行わない子 Node の数の上限
+    pub fn divide(&mut self, threshold: usize) {
+        let bounds = self.shape.bounds();
+        let mut children = self.shape.take_children();
+
+        if self.shape.is_group()
+            && threshold < children.len()
+            && bounds.is_finite()
+        {
+            let (left_bounds, right_bounds) = bounds.split();
+            let mut left = vec![];
+            let mut right = vec![];
+            let mut rest = vec![];
+            for child in children {
+                let b = child.parent_space_bounds();
+                if left_bounds.contains_box(&b) {
+                    left.push(child);
+                } else if right_bounds.contains_box(&b) {
+                    right.push(child);
+                } else {
+                    rest.push(child);
+                }
+            }
+
+            children = rest;
+            for sub in [left, right] {
+                if sub.is_empty() {
+                    continue;
+                }
+                let mut g = Node::new(Box::new(Group::new()));
+                for child in sub {
+                    g.add_child(child);
+                }
+                children.push(g);
+            }
+        }
+
+        for mut child in children {
+            child.divide(threshold);
+            self.add_child(child);
+        }
+    }
+
+    /// 子 Node を 1 つだけ持つ Group を、変換を合成した子 Node で置き換える。
+    /// 子孫に対しても再帰的に行い、交点や法線を変えずに木を浅くする。
+    /// motion が設定された Node は変換を合成できないため置き換えない。
+    /// 名前はどちらか一方にのみ設定されている場合は引き継ぎ、両方に設定されて
+    /// いる場合は失われないように置き換えない。
+    pub fn flatten(&mut self) {
+        while self.motion.is_none()
+            && self.shape.is_group()
+            && self.shape.child_count() == 1
+            && self.shape.child_at(0).motion.is_none()
+            && (self.tag.is_none() || self.shape.child_at(0).tag.is_none())
+        {
+            let child = *self.shape.take_children().pop().unwrap();
+            self.transform = &self.transform * &child.transform;
+            self.visible &= child.visible;
+            self.tag = self.tag.take().or(child.tag);
+            self.shape = child.shape;
+            self.world_transform = None;
+
+            // 孫 Node の親を self に付け替える
+            let parent = NonNull::new(&mut *self);
+            for grandchild in self.shape.children_mut() {
+                grandchild.parent = parent;
+            }
+        }
+
+        for child in self.shape.children_mut() {
+            child.flatten();
+        }
+    }
+
+    /// self と全ての子孫について、World 座標系への変換を計算して保持する。
+    /// 以降の world_to_object と normal_to_world は親 Node をたどらずに
+    /// 計算される。motion が設定された Node とその子孫は時刻によって変換が
+    /// 変わるため保持しない。
+    /// シーングラフを構築し終えた後、ルートの Node に対して呼び出すこと。
+    /// finalize 後に変換や子 Node を変更した場合は、再度呼び出す必要がある。
+    pub fn finalize(&mut self) {
+        self.finalize_with(&Some(Transform::identity()));
+    }
+
+    /// self と全ての子孫について、World 座標系への変換を計算して保持する。
+    ///
+    /// # Argumets
+    /// * `parent` - 親 Node の World 座標系への変換
+    fn finalize_with(&mut self, parent: &Option<Transform>) {
+        let world = match (parent, &self.motion) {
+            (Some(parent), None) => Some(parent * &self.transform),
+            _ => None,
+        };
+        for child in self.shape.children_mut() {
+            child.finalize_with(&world);
+        }
+        self.world_transform = world;
+    }
+
     /// 親 Node の座標系への変換を取得する
     pub fn transform(&self) -> &Transform {
         &self.transform
@@ -53,49 +236,185 @@ impl Node {
         self.transform = transform;
     }
 
-    /// World 座表系の点 p から self の local 座標系の点を求める
+    /// 時刻 0 と 1 における親 Node の座標系への変換を取得する
+    pub fn motion(&self) -> &Option<(Transform, Transform)> {
+        &self.motion
+    }
+
+    /// 時刻 0 と 1 における親 Node の座標系への変換を取得する
+    pub fn motion_mut(&mut self) -> &mut Option<(Transform, Transform)> {
+        &mut self.motion
+    }
+
+    /// 時刻 time における親 Node の座標系への変換を取得する。
+    /// motion が設定されていない場合は transform と等しい。
     ///
     /// # Argumets
-    /// * `p` - World 座表系の点 p
-    pub(crate) fn world_to_object(&self, p: &Point3D) -> Point3D {
-        match self.parent {
-            None => self.transform().inv() * p,
-            Some(n) => unsafe {
-                self.transform().inv() * &n.as_ref().world_to_object(p)
-            },
+    /// * `time` - 時刻 [0, 1]
+    pub fn transform_at(&self, time: FLOAT) -> Transform {
+        self.with_transform_at(time, |t| t.clone())
+    }
+
+    /// 時刻 time における親 Node の座標系への変換を f に渡す。
+    /// motion が設定されていない場合は複製せずに transform を渡す。
+    ///
+    /// # Argumets
+    /// * `time` - 時刻
+    /// * `f` - 変換を用いる処理
+    fn with_transform_at<R, F>(&self, time: FLOAT, f: F) -> R
+    where
+        F: FnOnce(&Transform) -> R,
+    {
+        match self.motion {
+            Some((ref start, ref end)) => f(&start.lerp(end, time)),
+            None => f(&self.transform),
+        }
+    }
+
+    /// 表示するかを取得する
+    pub fn visible(&self) -> bool {
+        self.visible
+    }
+
+    /// 表示するかを取得する
+    pub fn visible_mut(&mut self) -> &mut bool {
+        &mut self.visible
+    }
+
+    /// 名前を取得する。
+    /// self に設定されていない場合は、最も近い祖先 Node の名前を返す。
+    pub fn tag(&self) -> Option<&str> {
+        match (&self.tag, self.parent) {
+            (Some(tag), _) => Some(tag),
+            (None, Some(n)) => unsafe { n.as_ref().tag() },
+            (None, None) => None,
         }
     }
 
-    /// local 座表系の法線ベクトル n から World 座標系の法線ベクトルを求める
+    /// self に設定された名前を取得する
+    pub fn tag_mut(&mut self) -> &mut Option<String> {
+        &mut self.tag
+    }
+
+    /// 親 Node の座標系における BoundingBox を取得する
+    /// motion が設定されている場合は、時刻 0 と 1 の位置を両方含む。
+    pub fn parent_space_bounds(&self) -> BoundingBox {
+        match self.motion {
+            Some((ref start, ref end)) => {
+                let bounds = self.shape.bounds();
+                let mut b = bounds.transform(start);
+                b.add_box(&bounds.transform(end));
+                b
+            }
+            None => self.shape.bounds().transform(&self.transform),
+        }
+    }
+
+    /// 時刻 time において World 座表系の点 p から self の local 座標系の点を
+    /// 求める
     ///
     /// # Argumets
     /// * `p` - World 座表系の点 p
-    pub(crate) fn normal_to_world(&self, n: &Vector3D) -> Vector3D {
+    /// * `time` - 時刻
+    pub(crate) fn world_to_object(&self, p: &Point3D, time: FLOAT) -> Point3D {
+        if let Some(ref world) = self.world_transform {
+            return world.inv() * p;
+        }
+
+        let parent_p = match self.parent {
+            None => p.clone(),
+            Some(n) => unsafe { n.as_ref().world_to_object(p, time) },
+        };
+        self.with_transform_at(time, |t| t.inv() * &parent_p)
+    }
+
+    /// 時刻 time において local 座表系の法線ベクトル n から World 座標系の
+    /// 法線ベクトルを求める
+    ///
+    /// # Argumets
+    /// * `n` - local 座表系の法線ベクトル
+    /// * `time` - 時刻
+    pub(crate) fn normal_to_world(
+        &self,
+        n: &Vector3D,
+        time: FLOAT,
+    ) -> Vector3D {
+        if let Some(ref world) = self.world_transform {
+            return world.apply_to_normal(n);
+        }
+
+        let parent_n = self.with_transform_at(time, |t| t.apply_to_normal(n));
         match self.parent {
-            None => self.transform.apply_to_normal(n),
+            None => parent_n,
             Some(node) => unsafe {
-                node.as_ref()
-                    .normal_to_world(&(self.transform().apply_to_normal(n)))
+                node.as_ref().normal_to_world(&parent_n, time)
             },
         }
     }
 
-    pub fn material(&self) -> &Material {
+    /// Material を取得する。Material を持たない Shape の場合は None を返す。
+    pub fn material(&self) -> Option<&Material> {
         self.shape.material()
     }
 
-    pub fn material_mut(&mut self) -> &mut Material {
+    /// Material を取得する。Material を持たない Shape の場合は None を返す。
+    pub fn material_mut(&mut self) -> Option<&mut Material> {
         self.shape.material_mut()
     }
 
-    /// ray と self の交点を求める。全ての交点を Vec に入れて返す。
-    /// 交点がない場合には空の Vec を返す。
+    /// ray と self の交点を求める。全ての交点を t の順に Vec に入れて返す。
+    /// 交点がない場合や self が非表示の場合には空の Vec を返す。
     ///
     /// # Argumets
     /// * `ray` - 交点の計算対象となる Ray
-    pub fn intersect(&self, r: &Ray) -> Vec<Intersection> {
-        let local_ray = self.transform.inv() * r;
-        self.shape.local_intersect(&local_ray, self)
+    pub fn intersect(&self, r: &Ray) -> Vec<Intersection<'_>> {
+        let mut xs = vec![];
+        self.intersect_into(r, &mut xs);
+        xs.sort_unstable_by(Intersection::cmp_t);
+
+        xs
+    }
+
+    /// ray と self の交点を求め、out の末尾に追加する。
+    /// out の内容は消去せず、ソートも行わない。
+    /// self が非表示の場合には何も追加しない。
+    ///
+    /// # Argumets
+    /// * `ray` - 交点の計算対象となる Ray
+    /// * `out` - 交点を追加するバッファ
+    pub fn intersect_into<'a>(
+        &'a self,
+        r: &Ray,
+        out: &mut Vec<Intersection<'a>>,
+    ) {
+        if !self.visible {
+            return;
+        }
+        if self.shape.child_count() == 0 {
+            INTERSECTION_TESTS.with(|c| c.set(c.get() + 1));
+        }
+        let local_ray = self.with_transform_at(r.time(), |t| t.inv() * r);
+        self.shape.local_intersect_into(&local_ray, self, out)
+    }
+
+    /// ray が t が [0, max_t) の範囲で、影を落とす self (または子孫) と
+    /// 交差するか。
+    /// Group は子を順に判定し、交差が見つかった時点で打ち切るので、
+    /// intersect と異なり全ての交点を求めたりソートしたりしない。
+    /// 影を落とさない Material のオブジェクトは無視する。
+    ///
+    /// # Argumets
+    /// * `r` - 判定対象となる Ray
+    /// * `max_t` - 判定する t の上限
+    pub fn any_hit_before(&self, r: &Ray, max_t: FLOAT) -> bool {
+        if !self.visible {
+            return false;
+        }
+        if self.shape.child_count() == 0 {
+            INTERSECTION_TESTS.with(|c| c.set(c.get() + 1));
+        }
+        let local_ray = self.with_transform_at(r.time(), |t| t.inv() * r);
+        self.shape.local_any_hit_before(&local_ray, max_t, self)
     }
 
     /// self 上の点 p における法線ベクトルを取得する。
@@ -104,16 +423,44 @@ impl Node {
     /// * `p` - self 上の点
     /// * `i` - Ray との交点に関する情報
     pub fn normal_at(&self, p: &Point3D, i: &Intersection) -> Vector3D {
-        let local_point = self.world_to_object(p);
+        self.normal_at_time(p, i, 0.0)
+    }
+
+    /// 時刻 time において self 上の点 p における法線ベクトルを取得する。
+    ///
+    /// # Argumets
+    /// * `p` - self 上の点
+    /// * `i` - Ray との交点に関する情報
+    /// * `time` - Ray が発せられた時刻
+    pub(crate) fn normal_at_time(
+        &self,
+        p: &Point3D,
+        i: &Intersection,
+        time: FLOAT,
+    ) -> Vector3D {
+        let local_point = self.world_to_object(p, time);
         let local_normal = self.shape.local_normal_at(&local_point, i);
+        let local_normal = match self.material() {
+            Some(material) => {
+                material.perturb_normal(&local_point, local_normal)
+            }
+            None => local_normal,
+        };
 
-        self.normal_to_world(&local_normal)
+        self.normal_to_world(&local_normal, time)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{super::group::Group, *};
+    use super::{
+        super::{
+            approx_eq, color::Color, group::Group,
+            smooth_triangle::SmoothTriangle, solid_pattern::SolidPattern,
+            sphere::Sphere, FLOAT,
+        },
+        *,
+    };
 
     impl Node {
         pub(crate) fn shape(&self) -> &Box<dyn Shape> {
@@ -149,4 +496,337 @@ mod tests {
         let g = Node::new(Box::new(Group::new()));
         assert_eq!(None, g.parent);
     }
+
+    #[test]
+    fn querying_a_nodes_bounding_box_in_its_parents_space() {
+        let mut node = Node::new(Box::new(Sphere::new()));
+        node.set_transform(
+            &Transform::translation(1.0, -3.0, 5.0)
+                * &Transform::scaling(0.5, 2.0, 4.0),
+        );
+        let b = node.parent_space_bounds();
+
+        assert_eq!(Point3D::new(0.5, -5.0, 1.0), *b.min());
+        assert_eq!(Point3D::new(1.5, -1.0, 9.0), *b.max());
+    }
+
+    #[test]
+    fn subdividing_a_group_partitions_its_children() {
+        let mut s1 = Node::new(Box::new(Sphere::new()));
+        s1.set_transform(Transform::translation(-2.0, -2.0, 0.0));
+        let mut s2 = Node::new(Box::new(Sphere::new()));
+        s2.set_transform(Transform::translation(-2.0, 2.0, 0.0));
+        let mut s3 = Node::new(Box::new(Sphere::new()));
+        s3.set_transform(Transform::scaling(4.0, 4.0, 4.0));
+        let s1_ptr = &*s1 as *const Node;
+        let s2_ptr = &*s2 as *const Node;
+        let s3_ptr = &*s3 as *const Node;
+
+        let mut g = Node::new(Box::new(Group::new()));
+        g.add_child(s1);
+        g.add_child(s2);
+        g.add_child(s3);
+        g.divide(1);
+
+        assert!(std::ptr::eq(s3_ptr, &**g.child_at(0)));
+        let subgroup = g.child_at(1);
+        assert!(std::ptr::eq(s1_ptr, &**subgroup.child_at(0).child_at(0)));
+        assert!(std::ptr::eq(s2_ptr, &**subgroup.child_at(1).child_at(0)));
+        assert!(std::ptr::eq(
+            &**subgroup as *const Node,
+            subgroup.child_at(0).parent.unwrap().as_ptr()
+        ));
+    }
+
+    #[test]
+    fn subdividing_a_group_with_too_few_children() {
+        let mut s1 = Node::new(Box::new(Sphere::new()));
+        s1.set_transform(Transform::translation(-2.0, 0.0, 0.0));
+        let mut s2 = Node::new(Box::new(Sphere::new()));
+        s2.set_transform(Transform::translation(2.0, 1.0, 0.0));
+        let mut s3 = Node::new(Box::new(Sphere::new()));
+        s3.set_transform(Transform::translation(2.0, -1.0, 0.0));
+        let s1_ptr = &*s1 as *const Node;
+        let s2_ptr = &*s2 as *const Node;
+        let s3_ptr = &*s3 as *const Node;
+
+        let mut subgroup = Node::new(Box::new(Group::new()));
+        subgroup.add_child(s1);
+        subgroup.add_child(s2);
+        subgroup.add_child(s3);
+        let s4 = Node::new(Box::new(Sphere::new()));
+        let s4_ptr = &*s4 as *const Node;
+
+        let mut g = Node::new(Box::new(Group::new()));
+        g.add_child(subgroup);
+        g.add_child(s4);
+        g.divide(2);
+
+        let subgroup = g.child_at(0);
+        assert!(std::ptr::eq(s4_ptr, &**g.child_at(1)));
+        assert!(std::ptr::eq(s1_ptr, &**subgroup.child_at(0).child_at(0)));
+        assert!(std::ptr::eq(s2_ptr, &**subgroup.child_at(1).child_at(0)));
+        assert!(std::ptr::eq(s3_ptr, &**subgroup.child_at(1).child_at(1)));
+    }
+
+    #[test]
+    fn adding_several_children_at_once() {
+        let mut g = Node::new(Box::new(Group::new()));
+        assert_eq!(0, g.child_count());
+
+        g.add_children(vec![
+            Node::new(Box::new(Sphere::new())),
+            Node::new(Box::new(Sphere::new())),
+            Node::new(Box::new(Sphere::new())),
+        ]);
+
+        assert_eq!(3, g.child_count());
+        for i in 0..3 {
+            assert_eq!(NonNull::new(&mut *g), g.child_at(i).parent);
+        }
+        assert_eq!(0, g.child_at(0).child_count());
+    }
+
+    #[test]
+    fn walking_the_children_of_nested_groups() {
+        fn descendants(n: &Node) -> usize {
+            n.children().iter().map(|c| 1 + descendants(c)).sum()
+        }
+
+        let mut inner = Node::new(Box::new(Group::new()));
+        inner.add_children(vec![
+            Node::new(Box::new(Sphere::new())),
+            Node::new(Box::new(Sphere::new())),
+        ]);
+        let mut outer = Node::new(Box::new(Group::new()));
+        outer.add_children(vec![inner, Node::new(Box::new(Sphere::new()))]);
+
+        assert_eq!(2, outer.children().len());
+        assert_eq!(4, descendants(&outer));
+        assert!(outer.children()[1].children().is_empty());
+    }
+
+    #[test]
+    fn flattening_a_chain_of_single_child_groups() {
+        fn chain() -> Box<Node> {
+            let mut s = Node::new(Box::new(Sphere::new()));
+            s.set_transform(Transform::translation(5.0, 0.0, 0.0));
+            let mut g1 = Node::new(Box::new(Group::new()));
+            g1.set_transform(Transform::scaling(1.0, 2.0, 1.0));
+            g1.add_child(s);
+            let mut g2 = Node::new(Box::new(Group::new()));
+            g2.set_transform(Transform::rotation_y(0.5));
+            g2.add_child(g1);
+            let mut g3 = Node::new(Box::new(Group::new()));
+            g3.set_transform(Transform::translation(0.0, 1.0, 0.0));
+            g3.add_child(g2);
+            g3
+        }
+        let nested = chain();
+        let mut flat = chain();
+        flat.flatten();
+
+        assert_eq!(0, flat.child_count());
+        assert!(flat.material().is_some());
+
+        let r = Ray::new(
+            Point3D::new(0.0, 1.5, 0.0),
+            Vector3D::new(4.4, 0.0, -2.4),
+        );
+        let expected = nested.intersect(&r);
+        let actual = flat.intersect(&r);
+        assert_eq!(2, expected.len());
+        assert_eq!(expected.len(), actual.len());
+        for (e, a) in expected.iter().zip(actual.iter()) {
+            assert!(approx_eq(e.t, a.t));
+            let p = r.position(e.t);
+            assert_eq!(e.object.normal_at(&p, e), a.object.normal_at(&p, a));
+        }
+    }
+
+    #[test]
+    fn flattening_keeps_the_tags() {
+        let mut s = Node::new(Box::new(Sphere::new()));
+        *s.tag_mut() = Some("ball".to_string());
+        let mut g = Node::new(Box::new(Group::new()));
+        g.add_child(s);
+
+        g.flatten();
+        assert_eq!(0, g.child_count());
+        assert_eq!(Some("ball"), g.tag());
+
+        // 両方に名前がある場合は置き換えない
+        let mut s = Node::new(Box::new(Sphere::new()));
+        *s.tag_mut() = Some("ball".to_string());
+        let mut g = Node::new(Box::new(Group::new()));
+        *g.tag_mut() = Some("toys".to_string());
+        g.add_child(s);
+
+        g.flatten();
+        assert_eq!(1, g.child_count());
+        assert_eq!(Some("toys"), g.tag());
+        assert_eq!(Some("ball"), g.child_at(0).tag());
+    }
+
+    #[test]
+    fn a_node_inherits_the_tag_of_its_ancestor() {
+        let mut g = Node::new(Box::new(Group::new()));
+        *g.tag_mut() = Some("group".to_string());
+        g.add_child(Node::new(Box::new(Sphere::new())));
+        let mut s = Node::new(Box::new(Sphere::new()));
+        *s.tag_mut() = Some("sphere".to_string());
+        g.add_child(s);
+
+        assert_eq!(Some("group"), g.child_at(0).tag());
+        assert_eq!(Some("sphere"), g.child_at(1).tag());
+        assert_eq!(None, Node::new(Box::new(Sphere::new())).tag());
+    }
+
+    #[test]
+    fn normal_at_passes_the_intersection_to_the_shape() {
+        let mut tri = Node::new(Box::new(SmoothTriangle::new(
+            Point3D::new(0.0, 1.0, 0.0),
+            Point3D::new(-1.0, 0.0, 0.0),
+            Point3D::new(1.0, 0.0, 0.0),
+            Vector3D::new(0.0, 1.0, 0.0),
+            Vector3D::new(-1.0, 0.0, 0.0),
+            Vector3D::new(1.0, 0.0, 0.0),
+        )));
+        tri.set_transform(Transform::translation(0.0, 0.0, 5.0));
+        let i = Intersection {
+            t: 1.0,
+            object: &tri,
+            u: 0.45,
+            v: 0.25,
+            front_face: true,
+        };
+
+        // u, v から補間された法線が返る
+        let n = tri.normal_at(&Point3D::new(0.0, 0.0, 5.0), &i);
+        assert_eq!(Vector3D::new(-0.5547, 0.83205, 0.0), n);
+    }
+
+    #[test]
+    fn a_neutral_normal_pattern_leaves_the_normal_unchanged() {
+        let mut s = Node::new(Box::new(Sphere::new()));
+        *s.material_mut().unwrap().normal_pattern_mut() =
+            Some(Box::new(SolidPattern::new(Color::new(0.5, 0.5, 1.0))));
+        let i = Intersection {
+            t: 1.0,
+            object: &s,
+            u: 0.0,
+            v: 0.0,
+            front_face: true,
+        };
+
+        let n = s.normal_at(&Point3D::new(0.0, 0.0, -1.0), &i);
+        assert_eq!(Vector3D::new(0.0, 0.0, -1.0), n);
+    }
+
+    #[test]
+    fn a_tilted_normal_pattern_bends_the_normal() {
+        let mut s = Node::new(Box::new(Sphere::new()));
+        *s.material_mut().unwrap().normal_pattern_mut() =
+            Some(Box::new(SolidPattern::new(Color::new(0.5, 1.0, 1.0))));
+        s.set_transform(Transform::translation(0.0, 0.0, 5.0));
+        let i = Intersection {
+            t: 1.0,
+            object: &s,
+            u: 0.0,
+            v: 0.0,
+            front_face: true,
+        };
+
+        // 接空間 (0, 1, 1) は法線と従法線(+y)の中間
+        let n = s.normal_at(&Point3D::new(0.0, 0.0, 4.0), &i);
+        let sqrt2_2 = (2.0 as FLOAT).sqrt() / 2.0;
+        assert_eq!(Vector3D::new(0.0, sqrt2_2, -sqrt2_2), n);
+    }
+
+    #[test]
+    fn the_effective_transform_interpolates_the_motion() {
+        let mut s = Node::new(Box::new(Sphere::new()));
+        assert_eq!(Transform::identity(), s.transform_at(0.5));
+
+        *s.motion_mut() = Some((
+            Transform::translation(0.0, 0.0, 0.0),
+            Transform::translation(2.0, 4.0, -6.0),
+        ));
+
+        assert_eq!(Transform::translation(0.0, 0.0, 0.0), s.transform_at(0.0));
+        assert_eq!(Transform::translation(2.0, 4.0, -6.0), s.transform_at(1.0));
+        assert_eq!(Transform::translation(1.0, 2.0, -3.0), s.transform_at(0.5));
+    }
+
+    #[test]
+    fn a_moving_node_is_intersected_at_the_time_of_the_ray() {
+        let mut s = Node::new(Box::new(Sphere::new()));
+        *s.motion_mut() = Some((
+            Transform::translation(0.0, 0.0, 0.0),
+            Transform::translation(5.0, 0.0, 0.0),
+        ));
+        let r = Ray::new(
+            Point3D::new(5.0, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+
+        assert_eq!(0, s.intersect(&r).len());
+        assert_eq!(2, s.intersect(&r.with_time(1.0)).len());
+    }
+
+    #[test]
+    fn any_hit_before_stops_at_the_first_hit_in_a_group() {
+        let mut g = Node::new(Box::new(Group::new()));
+        for z in 0..8 {
+            let mut s = Node::new(Box::new(Sphere::new()));
+            s.set_transform(Transform::translation(0.0, 0.0, 3.0 * z as FLOAT));
+            // 最初の球は影を落とさない
+            s.material_mut().unwrap().casts_shadow = 0 < z;
+            g.add_child(s);
+        }
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+
+        // 2 番目の球で交差が見つかるので、残りの球は判定しない
+        let before = intersection_tests();
+        assert!(g.any_hit_before(&r, 100.0));
+        assert_eq!(2, intersection_tests() - before);
+
+        assert!(!g.any_hit_before(&r, 6.5));
+        assert!(g.any_hit_before(&r, 7.5));
+    }
+
+    #[test]
+    fn finalize_gives_the_same_results_as_walking_the_parents() {
+        let mut g1 = Node::new(Box::new(Group::new()));
+        g1.set_transform(Transform::rotation_y(
+            std::f64::consts::FRAC_PI_2 as FLOAT,
+        ));
+        let mut g2 = Node::new(Box::new(Group::new()));
+        g2.set_transform(Transform::scaling(1.0, 2.0, 3.0));
+        let mut s = Node::new(Box::new(Sphere::new()));
+        s.set_transform(Transform::translation(5.0, 0.0, 0.0));
+        let s_ptr = &*s as *const Node;
+        g2.add_child(s);
+        g1.add_child(g2);
+
+        let p = Point3D::new(-2.0, 0.0, -10.0);
+        let sqrt3_3 = (3.0 as FLOAT).sqrt() / 3.0;
+        let n = Vector3D::new(sqrt3_3, sqrt3_3, sqrt3_3);
+
+        let s = unsafe { &*s_ptr };
+        let expected_point = s.world_to_object(&p, 0.0);
+        let expected_normal = s.normal_to_world(&n, 0.0);
+        assert!(s.world_transform.is_none());
+
+        g1.finalize();
+
+        let s = unsafe { &*s_ptr };
+        assert!(s.world_transform.is_some());
+        assert_eq!(expected_point, s.world_to_object(&p, 0.0));
+        assert_eq!(expected_normal, s.normal_to_world(&n, 0.0));
+        assert_eq!(Vector3D::new(0.28571, 0.42857, -0.85714), expected_normal);
+    }
 }
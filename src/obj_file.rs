@@ -1,9 +1,14 @@
 use crate::{
-    group::Group, node::Node, point3d::Point3D, shape::Shape,
-    smooth_triangle::SmoothTriangle, triangle::Triangle, vector3d::Vector3D,
-    FLOAT,
+    color::Color, group::Group, material::Material, node::Node,
+    point3d::Point3D, shape::Shape, smooth_triangle::SmoothTriangle,
+    triangle::Triangle, vector3d::Vector3D, FLOAT,
+};
+use std::{
+    collections::BTreeMap,
+    convert::From,
+    fmt,
+    io::{self, BufRead},
 };
-use std::{collections::BTreeMap, convert::From, io::BufRead};
 
 #[derive(Debug)]
 pub struct ObjParser {
@@ -13,48 +18,491 @@ pub struct ObjParser {
     groups: BTreeMap<String, Box<Node>>,
 }
 
+/// 面を分割した三角形。
+/// スレッド間で受け渡せるよう、Shape を作成する前の頂点の情報を保持する。
+#[derive(Debug)]
+enum FaceTriangle {
+    /// 頂点のみを持つ三角形
+    Flat(Point3D, Point3D, Point3D),
+    /// 頂点ごとに法線を持つ三角形
+    Smooth(Point3D, Point3D, Point3D, Vector3D, Vector3D, Vector3D),
+}
+
+impl FaceTriangle {
+    /// 対応する Shape を作成する
+    fn into_shape(self) -> Box<dyn Shape> {
+        match self {
+            FaceTriangle::Flat(p1, p2, p3) => {
+                Box::new(Triangle::new(p1, p2, p3))
+            }
+            FaceTriangle::Smooth(p1, p2, p3, n1, n2, n3) => {
+                Box::new(SmoothTriangle::new(p1, p2, p3, n1, n2, n3))
+            }
+        }
+    }
+}
+
 fn fan_triangulation(
-    vertices: &Vec<Point3D>,
-    indices: &Vec<usize>,
-) -> Vec<Box<dyn Shape>> {
-    let mut triangles: Vec<Box<dyn Shape>> = vec![];
+    vertices: &[Point3D],
+    indices: &[usize],
+) -> Vec<FaceTriangle> {
+    let mut triangles = vec![];
 
     for i in 1..indices.len() - 1 {
-        triangles.push(Box::new(Triangle::new(
+        triangles.push(FaceTriangle::Flat(
             vertices[indices[0]].clone(),
             vertices[indices[i]].clone(),
             vertices[indices[i + 1]].clone(),
-        )));
+        ));
     }
 
     triangles
 }
 
 fn fan_triangulation_smooth(
-    vertices: &Vec<Point3D>,
-    vertex_indices: &Vec<usize>,
-    normals: &Vec<Vector3D>,
-    normal_indices: &Vec<usize>,
-) -> Vec<Box<dyn Shape>> {
+    vertices: &[Point3D],
+    vertex_indices: &[usize],
+    normals: &[Vector3D],
+    normal_indices: &[usize],
+) -> Vec<FaceTriangle> {
     assert_eq!(vertex_indices.len(), normal_indices.len());
 
-    let mut triangles: Vec<Box<dyn Shape>> = vec![];
+    let mut triangles = vec![];
 
     for i in 1..vertex_indices.len() - 1 {
-        triangles.push(Box::new(SmoothTriangle::new(
+        triangles.push(FaceTriangle::Smooth(
             vertices[vertex_indices[0]].clone(),
             vertices[vertex_indices[i]].clone(),
             vertices[vertex_indices[i + 1]].clone(),
             normals[normal_indices[0]].clone(),
             normals[normal_indices[i]].clone(),
             normals[normal_indices[i + 1]].clone(),
-        )));
+        ));
     }
 
     triangles
 }
 
-pub fn parse_obj_file(reader: &mut dyn BufRead) -> ObjParser {
+/// OBJ ファイルの解析時に発生するエラー
+#[derive(Debug)]
+pub enum ObjParseError {
+    /// 読み込みに失敗した
+    Io(std::io::Error),
+    /// 数値として解釈できない値がある
+    InvalidNumber { line: usize, content: String },
+    /// 値の数が足りない
+    TooFewValues { line: usize, content: String },
+    /// 存在しない頂点または法線を参照している
+    IndexOutOfRange { line: usize, content: String },
+}
+
+impl fmt::Display for ObjParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjParseError::Io(e) => write!(f, "{}", e),
+            ObjParseError::InvalidNumber { line, content } => {
+                write!(f, "line {}: invalid number: {}", line, content)
+            }
+            ObjParseError::TooFewValues { line, content } => {
+                write!(f, "line {}: too few values: {}", line, content)
+            }
+            ObjParseError::IndexOutOfRange { line, content } => {
+                write!(f, "line {}: index out of range: {}", line, content)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ObjParseError {}
+
+impl From<std::io::Error> for ObjParseError {
+    fn from(e: std::io::Error) -> Self {
+        ObjParseError::Io(e)
+    }
+}
+
+/// "x y z" 形式の 3 つの数値を読み込む
+///
+/// # Argumets
+/// * `cs` - 行を空白で分割したもの。先頭はキーワード
+/// * `line` - 行番号
+/// * `content` - 行の内容
+fn parse_xyz(
+    cs: &[&str],
+    line: usize,
+    content: &str,
+) -> Result<(FLOAT, FLOAT, FLOAT), ObjParseError> {
+    if cs.len() < 4 {
+        return Err(ObjParseError::TooFewValues {
+            line,
+            content: content.to_string(),
+        });
+    }
+
+    let parse = |s: &str| {
+        s.parse::<FLOAT>()
+            .map_err(|_| ObjParseError::InvalidNumber {
+                line,
+                content: content.to_string(),
+            })
+    };
+    Ok((parse(cs[1])?, parse(cs[2])?, parse(cs[3])?))
+}
+
+/// 面を構成する頂点または法線のインデックスを読み込む
+///
+/// # Argumets
+/// * `s` - インデックスを表す文字列
+/// * `len` - 参照先の要素数 (先頭のダミーを含む)
+/// * `line` - 行番号
+/// * `content` - 行の内容
+fn parse_index(
+    s: &str,
+    len: usize,
+    line: usize,
+    content: &str,
+) -> Result<usize, ObjParseError> {
+    let idx = s
+        .parse::<usize>()
+        .map_err(|_| ObjParseError::InvalidNumber {
+            line,
+            content: content.to_string(),
+        })?;
+    // 0 はダミーなので参照できない
+    if idx == 0 || len <= idx {
+        return Err(ObjParseError::IndexOutOfRange {
+            line,
+            content: content.to_string(),
+        });
+    }
+    Ok(idx)
+}
+
+/// 1 つの数値を読み込む
+///
+/// # Argumets
+/// * `cs` - 行を空白で分割したもの。先頭はキーワード
+/// * `line` - 行番号
+/// * `content` - 行の内容
+fn parse_value(
+    cs: &[&str],
+    line: usize,
+    content: &str,
+) -> Result<FLOAT, ObjParseError> {
+    if cs.len() < 2 {
+        return Err(ObjParseError::TooFewValues {
+            line,
+            content: content.to_string(),
+        });
+    }
+
+    cs[1]
+        .parse::<FLOAT>()
+        .map_err(|_| ObjParseError::InvalidNumber {
+            line,
+            content: content.to_string(),
+        })
+}
+
+/// MTL ファイルで定義された Material の設定。
+/// 定義されていない項目は None となり、Material の既定値を用いる。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MtlMaterial {
+    /// 拡散反射光の色 (Kd)
+    pub diffuse: Option<Color>,
+    /// 鏡面反射光の色 (Ks)。Material には各成分の平均を強さとして設定する。
+    pub specular: Option<Color>,
+    /// 鏡面反射光の広がり (Ns)
+    pub shininess: Option<FLOAT>,
+    /// 透明度 (Tr。d の場合は 1 - d)
+    pub transparency: Option<FLOAT>,
+}
+
+impl MtlMaterial {
+    /// 定義されている項目を material に設定する
+    ///
+    /// # Argumets
+    /// * `material` - 設定先の Material
+    pub fn apply(&self, material: &mut Material) {
+        if let Some(diffuse) = self.diffuse {
+            material.color = diffuse;
+        }
+        if let Some(specular) = self.specular {
+            material.specular =
+                (specular.red + specular.green + specular.blue) / 3.0;
+        }
+        if let Some(shininess) = self.shininess {
+            material.shininess = shininess;
+        }
+        if let Some(transparency) = self.transparency {
+            material.transparency = transparency;
+        }
+    }
+}
+
+/// MTL ファイルを解析する。
+/// 対応している項目は newmtl, Kd, Ks, Ns, d, Tr のみで、それ以外は無視する。
+///
+/// # Argumets
+/// * `reader` - 読み込み元
+///
+/// # Failures
+/// 読み込みに失敗した場合や、内容に誤りがある場合は ObjParseError を返す。
+/// 行番号は MTL ファイル中のもの
+pub fn parse_mtl_file(
+    reader: &mut dyn BufRead,
+) -> Result<BTreeMap<String, MtlMaterial>, ObjParseError> {
+    let mut materials = BTreeMap::new();
+    let mut current: Option<(String, MtlMaterial)> = None;
+
+    for (n, line) in reader.lines().enumerate() {
+        let l = line?;
+        let line_no = n + 1;
+        let cs: Vec<&str> = l.split_whitespace().collect();
+        if cs.is_empty() {
+            continue;
+        }
+
+        if cs[0] == "newmtl" {
+            if cs.len() < 2 {
+                return Err(ObjParseError::TooFewValues {
+                    line: line_no,
+                    content: l.clone(),
+                });
+            }
+            if let Some((name, m)) = current.take() {
+                materials.insert(name, m);
+            }
+            current = Some((cs[1].to_string(), MtlMaterial::default()));
+            continue;
+        }
+
+        // newmtl より前の項目は無視する
+        let m = match current {
+            Some((_, ref mut m)) => m,
+            None => continue,
+        };
+        match cs[0] {
+            "Kd" => {
+                let (r, g, b) = parse_xyz(&cs, line_no, &l)?;
+                m.diffuse = Some(Color::new(r, g, b));
+            }
+            "Ks" => {
+                let (r, g, b) = parse_xyz(&cs, line_no, &l)?;
+                m.specular = Some(Color::new(r, g, b));
+            }
+            "Ns" => m.shininess = Some(parse_value(&cs, line_no, &l)?),
+            "d" => m.transparency = Some(1.0 - parse_value(&cs, line_no, &l)?),
+            "Tr" => m.transparency = Some(parse_value(&cs, line_no, &l)?),
+            _ => {}
+        }
+    }
+    if let Some((name, m)) = current {
+        materials.insert(name, m);
+    }
+
+    Ok(materials)
+}
+
+/// 面 ("f" で始まる行) を解析し、三角形に分割する。
+/// 参照できるのは vertices と normals の先頭 vertex_count, normal_count 個
+/// (先頭のダミーを含む) のみで、それ以降を参照した場合はエラーとなる。
+///
+/// # Argumets
+/// * `cs` - 行を空白で分割したもの。先頭はキーワード
+/// * `vertices` - 頂点
+/// * `vertex_count` - 参照できる頂点の数
+/// * `normals` - 法線
+/// * `normal_count` - 参照できる法線の数
+/// * `vertex_normals` - 法線が指定されていない面に用いる、頂点ごとの法線。
+///   None の場合は法線を持たない三角形とする
+/// * `line` - 行番号
+/// * `content` - 行の内容
+#[allow(clippy::too_many_arguments)]
+fn parse_face(
+    cs: &[&str],
+    vertices: &[Point3D],
+    vertex_count: usize,
+    normals: &[Vector3D],
+    normal_count: usize,
+    vertex_normals: Option<&[Vector3D]>,
+    line: usize,
+    content: &str,
+) -> Result<Vec<FaceTriangle>, ObjParseError> {
+    if cs.len() < 4 {
+        return Err(ObjParseError::TooFewValues {
+            line,
+            content: content.to_string(),
+        });
+    }
+
+    let mut use_smooth_triangle = true;
+    let mut vertex_indices = vec![];
+    let mut normal_indices = vec![];
+    for face in &cs[1..] {
+        let f: Vec<&str> = face.split('/').collect();
+        vertex_indices.push(parse_index(f[0], vertex_count, line, content)?);
+        if f.len() >= 3 {
+            normal_indices.push(parse_index(
+                f[2],
+                normal_count,
+                line,
+                content,
+            )?);
+        } else {
+            use_smooth_triangle = false;
+        }
+    }
+
+    match (use_smooth_triangle, vertex_normals) {
+        (true, _) => Ok(fan_triangulation_smooth(
+            vertices,
+            &vertex_indices,
+            normals,
+            &normal_indices,
+        )),
+        (false, Some(vertex_normals)) => Ok(fan_triangulation_smooth(
+            vertices,
+            &vertex_indices,
+            vertex_normals,
+            &vertex_indices,
+        )),
+        (false, None) => Ok(fan_triangulation(vertices, &vertex_indices)),
+    }
+}
+
+/// 各頂点に隣接する面の法線の平均を、頂点の法線として求める。
+/// 結果は頂点と同じく 1-origin で、先頭はダミーとなる。
+/// 誤りのある行は無視する (誤りは本来の解析で報告される)。
+///
+/// # Argumets
+/// * `lines` - OBJ ファイルの各行
+fn compute_vertex_normals<'a>(
+    lines: impl Iterator<Item = &'a str>,
+) -> Vec<Vector3D> {
+    let mut vertices = vec![Point3D::new(0.0, 0.0, 0.0)];
+    let mut faces = vec![];
+    for l in lines {
+        let cs: Vec<&str> = l.split_whitespace().collect();
+        match cs.first() {
+            Some(&"v") => {
+                // 誤りがあっても以降のインデックスがずれないように追加する
+                let (x, y, z) = parse_xyz(&cs, 0, l).unwrap_or((0.0, 0.0, 0.0));
+                vertices.push(Point3D::new(x, y, z));
+            }
+            Some(&"f") => {
+                let indices: Option<Vec<usize>> = cs[1..]
+                    .iter()
+                    .map(|f| {
+                        let index = f.split('/').next().unwrap();
+                        parse_index(index, vertices.len(), 0, l).ok()
+                    })
+                    .collect();
+                if let Some(indices) = indices.filter(|i| 3 <= i.len()) {
+                    faces.push(indices);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut normals = vec![Vector3D::new(0.0, 0.0, 0.0); vertices.len()];
+    for indices in faces.iter() {
+        for i in 1..indices.len() - 1 {
+            let (a, b, c) = (indices[0], indices[i], indices[i + 1]);
+            let e1 = &vertices[b] - &vertices[a];
+            let e2 = &vertices[c] - &vertices[a];
+            let mut normal = e1.cross(&e2);
+            // 縮退した三角形は法線を持たない
+            if normal.magnitude() == 0.0 {
+                continue;
+            }
+            normal.normalize();
+            for v in [a, b, c].iter() {
+                normals[*v] = &normals[*v] + &normal;
+            }
+        }
+    }
+    for n in normals.iter_mut() {
+        if 0.0 < n.magnitude() {
+            n.normalize();
+        }
+    }
+
+    normals
+}
+
+/// OBJ ファイルの解析に関する設定
+#[derive(Debug, Clone, Default)]
+pub struct ObjOptions {
+    /// 法線 (vn) が指定されていない面について、隣接する面の法線の平均を
+    /// 頂点の法線とした SmoothTriangle を作成するか。
+    /// false の場合は Triangle を作成する。
+    pub smooth_normals: bool,
+}
+
+/// OBJ ファイルを解析する。
+/// mtllib で参照される MTL ファイルは読み込まず、usemtl は無視する。
+///
+/// # Argumets
+/// * `reader` - 読み込み元
+///
+/// # Failures
+/// 読み込みに失敗した場合や、内容に誤りがある場合は ObjParseError を返す
+pub fn parse_obj_file(
+    reader: &mut dyn BufRead,
+) -> Result<ObjParser, ObjParseError> {
+    parse_obj_file_with_mtl(reader, &mut |_| Ok(Box::new(io::empty())))
+}
+
+/// OBJ ファイルを解析する。
+/// mtllib で参照される MTL ファイルを open_mtl で開いて読み込み、
+/// usemtl で指定された Material を以降の面の三角形に設定する。
+/// 定義されていない名前が usemtl で指定された場合は、既定の Material を用いる。
+/// MTL ファイルを開けなかった場合は、その MTL ファイルを無視する。
+///
+/// # Argumets
+/// * `reader` - 読み込み元
+/// * `open_mtl` - mtllib に指定された名前から MTL ファイルを開く関数
+///
+/// # Failures
+/// 読み込みに失敗した場合や、内容に誤りがある場合は ObjParseError を返す
+pub fn parse_obj_file_with_mtl(
+    reader: &mut dyn BufRead,
+    open_mtl: &mut dyn FnMut(&str) -> io::Result<Box<dyn BufRead>>,
+) -> Result<ObjParser, ObjParseError> {
+    parse_obj_file_with_options(reader, &ObjOptions::default(), open_mtl)
+}
+
+/// options に従って OBJ ファイルを解析する。
+/// MTL ファイルの扱いは parse_obj_file_with_mtl と同じ。
+///
+/// # Argumets
+/// * `reader` - 読み込み元
+/// * `options` - 解析に関する設定
+/// * `open_mtl` - mtllib に指定された名前から MTL ファイルを開く関数
+///
+/// # Failures
+/// 読み込みに失敗した場合や、内容に誤りがある場合は ObjParseError を返す
+pub fn parse_obj_file_with_options(
+    reader: &mut dyn BufRead,
+    options: &ObjOptions,
+    open_mtl: &mut dyn FnMut(&str) -> io::Result<Box<dyn BufRead>>,
+) -> Result<ObjParser, ObjParseError> {
+    // 頂点の法線を求めるには全ての面が必要なので、先に全ての行を読み込む
+    let (lines, vertex_normals): (Box<dyn Iterator<Item = _>>, _) =
+        if options.smooth_normals {
+            let lines: Vec<io::Result<String>> = reader.lines().collect();
+            let normals = compute_vertex_normals(
+                lines.iter().map_while(|l| l.as_deref().ok()),
+            );
+            (Box::new(lines.into_iter()), Some(normals))
+        } else {
+            (Box::new(reader.lines()), None)
+        };
+
+    let mut materials = BTreeMap::new();
+    let mut current_material: Option<MtlMaterial> = None;
     let mut default_group = Node::new(Box::new(Group::new()));
     let mut groups = BTreeMap::new();
 
@@ -65,8 +513,9 @@ pub fn parse_obj_file(reader: &mut dyn BufRead) -> ObjParser {
     {
         let mut current_group = &mut default_group;
 
-        for line in reader.lines() {
-            let l = line.unwrap();
+        for (n, line) in lines.enumerate() {
+            let l = line?;
+            let line_no = n + 1;
             let cs: Vec<&str> = l.trim().split_whitespace().collect();
             if cs.len() == 0 {
                 continue;
@@ -75,66 +524,59 @@ pub fn parse_obj_file(reader: &mut dyn BufRead) -> ObjParser {
             match cs[0] {
                 // vertex
                 "v" => {
-                    if cs.len() >= 4 {
-                        vertices.push(Point3D::new(
-                            cs[1].parse::<FLOAT>().unwrap(),
-                            cs[2].parse::<FLOAT>().unwrap(),
-                            cs[3].parse::<FLOAT>().unwrap(),
-                        ));
-                    }
+                    let (x, y, z) = parse_xyz(&cs, line_no, &l)?;
+                    vertices.push(Point3D::new(x, y, z));
                 }
                 // vertex normal
                 "vn" => {
-                    if cs.len() >= 4 {
-                        normals.push(Vector3D::new(
-                            cs[1].parse::<FLOAT>().unwrap(),
-                            cs[2].parse::<FLOAT>().unwrap(),
-                            cs[3].parse::<FLOAT>().unwrap(),
-                        ));
-                    }
+                    let (x, y, z) = parse_xyz(&cs, line_no, &l)?;
+                    normals.push(Vector3D::new(x, y, z));
                 }
                 // face
                 "f" => {
-                    if cs.len() >= 4 {
-                        let mut use_smooth_triangle = true;
-                        let mut vertex_indices = vec![];
-                        let mut normal_indices = vec![];
-                        for i in 1..cs.len() {
-                            let face = &cs[i];
-                            let f: Vec<&str> = face.split('/').collect();
-                            vertex_indices.push(f[0].parse::<usize>().unwrap());
-                            if f.len() >= 3 {
-                                normal_indices
-                                    .push(f[2].parse::<usize>().unwrap());
-                            } else {
-                                use_smooth_triangle = false;
-                            }
-                        }
-
-                        let triangles;
-                        if use_smooth_triangle {
-                            assert_eq!(
-                                vertex_indices.len(),
-                                normal_indices.len()
-                            );
-                            triangles = fan_triangulation_smooth(
-                                &vertices,
-                                &vertex_indices,
-                                &normals,
-                                &normal_indices,
-                            );
-                        } else {
-                            triangles =
-                                fan_triangulation(&vertices, &vertex_indices);
+                    let triangles = parse_face(
+                        &cs,
+                        &vertices,
+                        vertices.len(),
+                        &normals,
+                        normals.len(),
+                        vertex_normals.as_deref(),
+                        line_no,
+                        &l,
+                    )?;
+                    for t in triangles {
+                        let mut node = Node::new(t.into_shape());
+                        if let Some(ref m) = current_material {
+                            m.apply(node.material_mut().unwrap());
                         }
-                        for t in triangles {
-                            current_group.add_child(Node::new(t));
+                        current_group.add_child(node);
+                    }
+                }
+                // material library
+                "mtllib" => {
+                    for name in &cs[1..] {
+                        // MTL ファイルが同梱されていない OBJ ファイルも多いので、
+                        // 開けない場合は既定の Material を用いる
+                        if let Ok(mut mtl) = open_mtl(name) {
+                            materials.append(&mut parse_mtl_file(&mut mtl)?);
                         }
                     }
                 }
+                // material
+                "usemtl" => {
+                    current_material = cs
+                        .get(1)
+                        .and_then(|name| materials.get(*name))
+                        .cloned();
+                }
                 // group
                 "g" => {
-                    assert!(cs.len() >= 2);
+                    if cs.len() < 2 {
+                        return Err(ObjParseError::TooFewValues {
+                            line: line_no,
+                            content: l.clone(),
+                        });
+                    }
                     let name = cs[1].to_string();
                     let g = Node::new(Box::new(Group::new()));
                     groups.insert(name, g);
@@ -145,12 +587,278 @@ pub fn parse_obj_file(reader: &mut dyn BufRead) -> ObjParser {
         }
     }
 
-    ObjParser {
+    Ok(ObjParser {
         vertices,
         normals,
         default_group,
         groups,
+    })
+}
+
+/// 並列に解析する面の情報
+struct FaceJob {
+    /// 行の位置 (0-origin)
+    index: usize,
+    /// この面から参照できる頂点の数
+    vertex_count: usize,
+    /// この面から参照できる法線の数
+    normal_count: usize,
+    /// この面が属するグループ
+    group: usize,
+    /// この面に設定する Material
+    material: Option<MtlMaterial>,
+}
+
+/// items の各要素に f を適用した結果を、items の順序のまま返す。
+/// items を利用可能なスレッド数に分割して並列に処理する。
+///
+/// # Argumets
+/// * `items` - 処理する要素
+/// * `f` - 各要素に適用する関数
+fn parallel_map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = items.len().div_ceil(threads).max(1);
+    let f = &f;
+
+    std::thread::scope(|s| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| {
+                s.spawn(move || chunk.iter().map(f).collect::<Vec<R>>())
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect()
+    })
+}
+
+/// OBJ ファイルを並列に解析する。
+/// 頂点と法線の解析、面の解析と三角形分割を複数のスレッドで行う。
+/// 結果のグループ構造やエラーは parse_obj_file と同じになる。
+///
+/// # Argumets
+/// * `reader` - 読み込み元
+///
+/// # Failures
+/// 読み込みに失敗した場合や、内容に誤りがある場合は ObjParseError を返す。
+/// 誤りが複数ある場合は、最初の行のものを返す
+pub fn parse_obj_file_parallel(
+    reader: &mut dyn BufRead,
+) -> Result<ObjParser, ObjParseError> {
+    parse_obj_file_parallel_with_mtl(reader, &mut |_| Ok(Box::new(io::empty())))
+}
+
+/// OBJ ファイルを並列に解析する。
+/// MTL ファイルの扱いは parse_obj_file_with_mtl と同じで、結果は
+/// parse_obj_file_with_mtl と同じになる。
+/// MTL ファイルの読み込みは面の解析の前に順に行う。
+///
+/// # Argumets
+/// * `reader` - 読み込み元
+/// * `open_mtl` - mtllib に指定された名前から MTL ファイルを開く関数
+///
+/// # Failures
+/// 読み込みに失敗した場合や、内容に誤りがある場合は ObjParseError を返す。
+/// 誤りが複数ある場合は、最初の行のものを返す
+pub fn parse_obj_file_parallel_with_mtl(
+    reader: &mut dyn BufRead,
+    open_mtl: &mut dyn FnMut(&str) -> io::Result<Box<dyn BufRead>>,
+) -> Result<ObjParser, ObjParseError> {
+    parse_obj_file_parallel_with_options(
+        reader,
+        &ObjOptions::default(),
+        open_mtl,
+    )
+}
+
+/// options に従って OBJ ファイルを並列に解析する。
+/// 結果は parse_obj_file_with_options と同じになる。
+///
+/// # Argumets
+/// * `reader` - 読み込み元
+/// * `options` - 解析に関する設定
+/// * `open_mtl` - mtllib に指定された名前から MTL ファイルを開く関数
+///
+/// # Failures
+/// 読み込みに失敗した場合や、内容に誤りがある場合は ObjParseError を返す。
+/// 誤りが複数ある場合は、最初の行のものを返す
+pub fn parse_obj_file_parallel_with_options(
+    reader: &mut dyn BufRead,
+    options: &ObjOptions,
+    open_mtl: &mut dyn FnMut(&str) -> io::Result<Box<dyn BufRead>>,
+) -> Result<ObjParser, ObjParseError> {
+    // 見つかったエラーと、その行番号
+    let mut errors: Vec<(usize, ObjParseError)> = vec![];
+
+    let mut lines = vec![];
+    for (n, line) in reader.lines().enumerate() {
+        match line {
+            Ok(l) => lines.push(l),
+            Err(e) => {
+                errors.push((n + 1, e.into()));
+                break;
+            }
+        }
+    }
+
+    // 各行を分類し、面の属するグループを決める。
+    // 同じ名前のグループが再び現れた場合は新しいグループで置き換えるため、
+    // 名前ごとに最後のグループの位置を記録する
+    let mut vertex_lines = vec![];
+    let mut normal_lines = vec![];
+    let mut faces = vec![];
+    let mut group_count = 1;
+    let mut group_names: BTreeMap<String, usize> = BTreeMap::new();
+    let mut current_group = 0;
+    let mut materials = BTreeMap::new();
+    let mut current_material: Option<MtlMaterial> = None;
+    'classify: for (i, l) in lines.iter().enumerate() {
+        let cs: Vec<&str> = l.split_whitespace().collect();
+        match cs.first() {
+            Some(&"v") => vertex_lines.push(i),
+            Some(&"vn") => normal_lines.push(i),
+            Some(&"f") => faces.push(FaceJob {
+                index: i,
+                vertex_count: vertex_lines.len() + 1,
+                normal_count: normal_lines.len() + 1,
+                group: current_group,
+                material: current_material.clone(),
+            }),
+            Some(&"mtllib") => {
+                for name in &cs[1..] {
+                    let mut mtl = match open_mtl(name) {
+                        Ok(mtl) => mtl,
+                        Err(_) => continue,
+                    };
+                    match parse_mtl_file(&mut mtl) {
+                        Ok(mut m) => materials.append(&mut m),
+                        Err(e) => {
+                            errors.push((i + 1, e));
+                            break 'classify;
+                        }
+                    }
+                }
+            }
+            Some(&"usemtl") => {
+                current_material =
+                    cs.get(1).and_then(|name| materials.get(*name)).cloned();
+            }
+            Some(&"g") => {
+                if cs.len() < 2 {
+                    errors.push((
+                        i + 1,
+                        ObjParseError::TooFewValues {
+                            line: i + 1,
+                            content: l.clone(),
+                        },
+                    ));
+                    break;
+                }
+                group_names.insert(cs[1].to_string(), group_count);
+                current_group = group_count;
+                group_count += 1;
+            }
+            _ => {}
+        }
     }
+
+    let parse_line = |i: &usize| {
+        let l = &lines[*i];
+        let cs: Vec<&str> = l.split_whitespace().collect();
+        parse_xyz(&cs, i + 1, l)
+    };
+
+    // 1-origin にする
+    let mut vertices: Vec<Point3D> = vec![Point3D::new(0.0, 0.0, 0.0)];
+    for (i, r) in vertex_lines
+        .iter()
+        .zip(parallel_map(&vertex_lines, parse_line))
+    {
+        match r {
+            Ok((x, y, z)) => vertices.push(Point3D::new(x, y, z)),
+            Err(e) => errors.push((i + 1, e)),
+        }
+    }
+    let mut normals: Vec<Vector3D> = vec![Vector3D::new(0.0, 0.0, 0.0)];
+    for (i, r) in normal_lines
+        .iter()
+        .zip(parallel_map(&normal_lines, parse_line))
+    {
+        match r {
+            Ok((x, y, z)) => normals.push(Vector3D::new(x, y, z)),
+            Err(e) => errors.push((i + 1, e)),
+        }
+    }
+
+    let vertex_normals = if options.smooth_normals {
+        Some(compute_vertex_normals(lines.iter().map(|l| l.as_str())))
+    } else {
+        None
+    };
+
+    // 最初のエラーより前の面は、正しく解析できた頂点と法線のみを参照する
+    let first_error = errors.iter().map(|(line, _)| *line).min();
+    let faces: Vec<FaceJob> = faces
+        .into_iter()
+        .filter(|f| first_error.is_none_or(|line| f.index + 1 < line))
+        .collect();
+    let triangles = parallel_map(&faces, |f| {
+        let l = &lines[f.index];
+        let cs: Vec<&str> = l.split_whitespace().collect();
+        parse_face(
+            &cs,
+            &vertices,
+            f.vertex_count,
+            &normals,
+            f.normal_count,
+            vertex_normals.as_deref(),
+            f.index + 1,
+            l,
+        )
+    });
+
+    let mut group_nodes: Vec<Option<Box<Node>>> = (0..group_count)
+        .map(|_| Some(Node::new(Box::new(Group::new()))))
+        .collect();
+    for (f, r) in faces.iter().zip(triangles) {
+        match r {
+            Ok(ts) => {
+                let g = group_nodes[f.group].as_mut().unwrap();
+                for t in ts {
+                    let mut node = Node::new(t.into_shape());
+                    if let Some(ref m) = f.material {
+                        m.apply(node.material_mut().unwrap());
+                    }
+                    g.add_child(node);
+                }
+            }
+            // 面は行の順に並んでいるので、最初に見つかったものが最初のエラー
+            Err(e) => return Err(e),
+        }
+    }
+    if let Some((_, e)) = errors.into_iter().min_by_key(|(line, _)| *line) {
+        return Err(e);
+    }
+
+    let default_group = group_nodes[0].take().unwrap();
+    let groups = group_names
+        .into_iter()
+        .map(|(name, i)| (name, group_nodes[i].take().unwrap()))
+        .collect();
+
+    Ok(ObjParser {
+        vertices,
+        normals,
+        default_group,
+        groups,
+    })
 }
 
 impl From<ObjParser> for Box<Node> {
@@ -164,7 +872,7 @@ impl From<ObjParser> for Box<Node> {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use super::{super::intersection::Intersection, *};
 
     #[test]
     fn ignoring_unrecognized_lines() {
@@ -174,7 +882,7 @@ She set out one day
 in a relative way,
 and came back the previous night.";
 
-        parse_obj_file(&mut gibberish);
+        parse_obj_file(&mut gibberish).unwrap();
     }
 
     #[test]
@@ -184,7 +892,7 @@ v -1.0000 0.50000 0.0000
 v 1 0 0
 v 1 1 0";
 
-        let parser = parse_obj_file(&mut file);
+        let parser = parse_obj_file(&mut file).unwrap();
 
         assert_eq!(Point3D::new(-1.0, 1.0, 0.0), parser.vertices[1]);
         assert_eq!(Point3D::new(-1.0, 0.5, 0.0), parser.vertices[2]);
@@ -202,7 +910,7 @@ v 1 1 0
 f 1 2 3
 f 1 3 4";
 
-        let parser = parse_obj_file(&mut file);
+        let parser = parse_obj_file(&mut file).unwrap();
         let g = &parser.default_group;
         let t1 = g.child_at(0);
         let t1 = t1.shape();
@@ -229,7 +937,7 @@ f 1 3 4";
 
     f 1 2 3 4 5";
 
-        let parser = parse_obj_file(&mut file);
+        let parser = parse_obj_file(&mut file).unwrap();
         let g = &parser.default_group;
         let t1 = g.child_at(0);
         let t1 = t1.shape();
@@ -264,7 +972,7 @@ f 1 3 4";
     g SecondGroup
     f 1 3 4";
 
-        let parser = parse_obj_file(&mut file);
+        let parser = parse_obj_file(&mut file).unwrap();
         let g1 = parser.groups.get("FirstGroup").unwrap();
         let g2 = parser.groups.get("SecondGroup").unwrap();
         let t1 = g1.child_at(0);
@@ -294,7 +1002,7 @@ f 1 3 4";
     g SecondGroup
     f 1 3 4";
 
-        let parser = parse_obj_file(&mut file);
+        let parser = parse_obj_file(&mut file).unwrap();
         let v1 = parser.vertices[1].clone();
         let v2 = parser.vertices[2].clone();
         let v3 = parser.vertices[3].clone();
@@ -323,7 +1031,7 @@ f 1 3 4";
     vn 0.707 0 -0.707
     vn 1 2 3";
 
-        let parser = parse_obj_file(&mut file);
+        let parser = parse_obj_file(&mut file).unwrap();
         assert_eq!(Vector3D::new(0.0, 0.0, 1.0), parser.normals[1]);
         assert_eq!(Vector3D::new(0.707, 0.0, -0.707), parser.normals[2]);
         assert_eq!(Vector3D::new(1.0, 2.0, 3.0), parser.normals[3]);
@@ -343,7 +1051,7 @@ f 1 3 4";
     f 1/0/3 2/102/1 3/14/2
 ";
 
-        let parser = parse_obj_file(&mut file);
+        let parser = parse_obj_file(&mut file).unwrap();
         let g = &parser.default_group;
         let t1 = g.child_at(0);
         let t1 = t1.shape();
@@ -366,4 +1074,285 @@ f 1 3 4";
         assert_eq!(unsafe { (*t1).n2() }, unsafe { (*t2).n2() });
         assert_eq!(unsafe { (*t1).n3() }, unsafe { (*t2).n3() });
     }
+
+    #[test]
+    fn smoothing_the_normals_of_faces_without_normals() {
+        let file = "v 0 0 0
+v 1 0 0
+v 0 1 0
+v 0 0 1
+f 1 2 3
+f 1 4 2";
+        let origin = Point3D::new(0.0, 0.0, 0.0);
+        let options = ObjOptions {
+            smooth_normals: true,
+        };
+
+        let parser = parse_obj_file_with_options(
+            &mut file.as_bytes(),
+            &options,
+            &mut |_| Ok(Box::new(io::empty())),
+        )
+        .unwrap();
+        let g = &parser.default_group;
+        // u = v = 0 の交点での法線は 1 番目の頂点の法線
+        let i = Intersection::new(0.0, g.child_at(0));
+        let mut expected = Vector3D::new(0.0, 1.0, 1.0);
+        expected.normalize();
+        assert_eq!(expected, g.child_at(0).normal_at(&origin, &i));
+        let i = Intersection::new(0.0, g.child_at(1));
+        assert_eq!(expected, g.child_at(1).normal_at(&origin, &i));
+
+        // 指定しない場合は面の法線となる
+        let parser = parse_obj_file(&mut file.as_bytes()).unwrap();
+        let g = &parser.default_group;
+        let i = Intersection::new(0.0, g.child_at(0));
+        assert_eq!(
+            Vector3D::new(0.0, 0.0, 1.0),
+            g.child_at(0).normal_at(&origin, &i)
+        );
+    }
+
+    #[test]
+    fn an_invalid_number_is_reported_with_its_line() {
+        let mut file: &[u8] = b"v 1 abc 3
+v 1 2 3";
+
+        match parse_obj_file(&mut file) {
+            Err(ObjParseError::InvalidNumber { line, content }) => {
+                assert_eq!(1, line);
+                assert_eq!("v 1 abc 3", content);
+            }
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn a_face_referencing_a_missing_vertex_is_an_error() {
+        let mut file: &[u8] = b"v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+f 1 2 99";
+
+        match parse_obj_file(&mut file) {
+            Err(ObjParseError::IndexOutOfRange { line, .. }) => {
+                assert_eq!(5, line);
+            }
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    /// グループと法線を含む、格子状の面からなる OBJ ファイル
+    fn grid_obj(size: usize) -> String {
+        let mut obj = String::new();
+        for z in 0..=size {
+            for x in 0..=size {
+                obj += &format!("v {} {} {}\n", x, (x * z) % 3, z);
+            }
+        }
+        obj += "vn 0 1 0\nvn 0 0.6 0.8\n";
+        let index = |x: usize, z: usize| z * (size + 1) + x + 1;
+        for z in 0..size {
+            if z % 4 == 1 {
+                obj += &format!("g Group{}\n", z % 3);
+            }
+            for x in 0..size {
+                let (a, b, c, d) = (
+                    index(x, z),
+                    index(x + 1, z),
+                    index(x + 1, z + 1),
+                    index(x, z + 1),
+                );
+                if x % 2 == 0 {
+                    obj += &format!("f {} {} {} {}\n", a, b, c, d);
+                } else {
+                    obj += &format!("f {}//1 {}//2 {}//1\n", a, b, c);
+                }
+            }
+        }
+        obj
+    }
+
+    fn assert_same_group(expected: &Node, actual: &Node) {
+        let expected = expected.children();
+        let actual = actual.children();
+        assert_eq!(expected.len(), actual.len());
+        for (e, a) in expected.iter().zip(actual.iter()) {
+            assert_eq!(format!("{:?}", e.shape()), format!("{:?}", a.shape()));
+        }
+    }
+
+    #[test]
+    fn a_face_gets_the_material_of_its_usemtl() {
+        let mut file: &[u8] = b"mtllib colors.mtl
+v -1 1 0
+v -1 0 0
+v 1 0 0
+f 1 2 3
+usemtl red
+f 1 2 3";
+        let mtl = "newmtl red
+Kd 1 0 0
+Ks 0.5 0.5 0.5
+Ns 50
+d 0.75
+";
+
+        let parser = parse_obj_file_with_mtl(&mut file, &mut |name| {
+            assert_eq!("colors.mtl", name);
+            Ok(Box::new(mtl.as_bytes()))
+        })
+        .unwrap();
+        let g = &parser.default_group;
+
+        let m1 = g.child_at(0).material().unwrap();
+        assert_eq!(Material::new().color, m1.color);
+        let m2 = g.child_at(1).material().unwrap();
+        assert_eq!(Color::new(1.0, 0.0, 0.0), m2.color);
+        assert_eq!(0.5, m2.specular);
+        assert_eq!(50.0, m2.shininess);
+        assert_eq!(0.25, m2.transparency);
+    }
+
+    #[test]
+    fn parsing_in_parallel_matches_the_serial_parser() {
+        let obj = grid_obj(24);
+
+        let serial = parse_obj_file(&mut obj.as_bytes()).unwrap();
+        let parallel = parse_obj_file_parallel(&mut obj.as_bytes()).unwrap();
+
+        assert_eq!(serial.vertices, parallel.vertices);
+        assert_eq!(serial.normals, parallel.normals);
+        assert_same_group(&serial.default_group, &parallel.default_group);
+        assert_eq!(
+            serial.groups.keys().collect::<Vec<_>>(),
+            parallel.groups.keys().collect::<Vec<_>>()
+        );
+        for (name, g) in serial.groups.iter() {
+            assert_same_group(g, &parallel.groups[name]);
+        }
+    }
+
+    #[test]
+    fn smoothing_normals_in_parallel_matches_the_serial_parser() {
+        let obj = grid_obj(16);
+        let options = ObjOptions {
+            smooth_normals: true,
+        };
+        let mut open_mtl = |_: &str| -> io::Result<Box<dyn BufRead>> {
+            Ok(Box::new(io::empty()))
+        };
+
+        let serial = parse_obj_file_with_options(
+            &mut obj.as_bytes(),
+            &options,
+            &mut open_mtl,
+        )
+        .unwrap();
+        let parallel = parse_obj_file_parallel_with_options(
+            &mut obj.as_bytes(),
+            &options,
+            &mut open_mtl,
+        )
+        .unwrap();
+
+        assert_same_group(&serial.default_group, &parallel.default_group);
+        for (name, g) in serial.groups.iter() {
+            assert_same_group(g, &parallel.groups[name]);
+        }
+        assert!(parallel.default_group.child_at(0).is_triangle());
+        assert!(format!("{:?}", parallel.default_group.child_at(0).shape())
+            .starts_with("SmoothTriangle"));
+    }
+
+    #[test]
+    fn a_missing_mtl_file_falls_back_to_the_default_material() {
+        let file = "mtllib missing.mtl
+v -1 1 0
+v -1 0 0
+v 1 0 0
+usemtl red
+f 1 2 3";
+        let mut open_mtl = |_: &str| -> io::Result<Box<dyn BufRead>> {
+            Err(io::Error::new(io::ErrorKind::NotFound, "missing.mtl"))
+        };
+
+        let serial =
+            parse_obj_file_with_mtl(&mut file.as_bytes(), &mut open_mtl)
+                .unwrap();
+        let parallel = parse_obj_file_parallel_with_mtl(
+            &mut file.as_bytes(),
+            &mut open_mtl,
+        )
+        .unwrap();
+
+        for parser in [serial, parallel].iter() {
+            let m = parser.default_group.child_at(0).material().unwrap();
+            assert_eq!(Material::new().color, m.color);
+        }
+    }
+
+    #[test]
+    fn parsing_in_parallel_applies_materials() {
+        let mut obj = String::from("mtllib colors.mtl\nusemtl red\n");
+        obj +=
+            &grid_obj(8).replacen("g Group1\n", "g Group1\nusemtl blue\n", 1);
+        let mtl = "newmtl red
+Kd 1 0 0
+newmtl blue
+Kd 0 0 1
+Ns 50
+";
+        let mut open_mtl = |_: &str| -> io::Result<Box<dyn BufRead>> {
+            Ok(Box::new(mtl.as_bytes()))
+        };
+
+        let serial =
+            parse_obj_file_with_mtl(&mut obj.as_bytes(), &mut open_mtl)
+                .unwrap();
+        let parallel = parse_obj_file_parallel_with_mtl(
+            &mut obj.as_bytes(),
+            &mut open_mtl,
+        )
+        .unwrap();
+
+        assert_same_group(&serial.default_group, &parallel.default_group);
+        for (name, g) in serial.groups.iter() {
+            assert_same_group(g, &parallel.groups[name]);
+        }
+        let red = parallel.default_group.child_at(0).material().unwrap();
+        assert_eq!(Color::new(1.0, 0.0, 0.0), red.color);
+        let blue = parallel.groups["Group1"].child_at(0).material().unwrap();
+        assert_eq!(Color::new(0.0, 0.0, 1.0), blue.color);
+    }
+
+    #[test]
+    fn parsing_in_parallel_reports_the_first_error() {
+        let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+f 1 2 99
+v 1 abc 0
+g";
+
+        match parse_obj_file_parallel(&mut file.as_bytes()) {
+            Err(ObjParseError::IndexOutOfRange { line, .. }) => {
+                assert_eq!(4, line);
+            }
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let file = "v -1 1 0
+v 1 abc 0
+f 1 2 99
+g";
+        match parse_obj_file_parallel(&mut file.as_bytes()) {
+            Err(ObjParseError::InvalidNumber { line, content }) => {
+                assert_eq!(2, line);
+                assert_eq!("v 1 abc 0", content);
+            }
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
 }
@@ -1,4 +1,6 @@
-use super::{color::Color, node::Node, point3d::Point3D, transform::Transform};
+use super::{
+    color::Color, node::Node, point3d::Point3D, transform::Transform, FLOAT,
+};
 use std::fmt::Debug;
 
 pub trait Pattern: Debug {
@@ -13,11 +15,29 @@ pub trait Pattern: Debug {
     /// * `p` - pattern 座標系における点
     fn pattern_at(&self, p: &Point3D) -> Color;
     /// World 上の点 p におけるパターンの色を返す。
+    /// node が Group に含まれる場合は、親の変換も含めて object 座標系に
+    /// 変換してから評価する。
     ///
     /// # Argumets
+    /// * `node` - パターンを適用するオブジェクト
     /// * `p` - World 座標系における点
     fn pattern_at_shape(&self, node: &Node, p: &Point3D) -> Color {
-        let local_p = node.transform().inv() * p;
+        self.pattern_at_shape_at(node, p, 0.0)
+    }
+    /// 時刻 time において World 上の点 p におけるパターンの色を返す。
+    /// 移動するオブジェクトでは、time における位置を基準に評価する。
+    ///
+    /// # Argumets
+    /// * `node` - パターンを適用するオブジェクト
+    /// * `p` - World 座標系における点
+    /// * `time` - Ray が発せられた時刻
+    fn pattern_at_shape_at(
+        &self,
+        node: &Node,
+        p: &Point3D,
+        time: FLOAT,
+    ) -> Color {
+        let local_p = node.world_to_object(p, time);
         let pattern_p = self.transform().inv() * &local_p;
         self.pattern_at(&pattern_p)
     }
@@ -1,6 +1,7 @@
 use super::{
-    intersection::Intersection, material::Material, node::Node,
-    point3d::Point3D, ray::Ray, shape::Shape, vector3d::Vector3D, EPSILON,
+    bounding_box::BoundingBox, intersection::Intersection, material::Material,
+    node::Node, point3d::Point3D, ray::Ray, shape::Shape, vector3d::Vector3D,
+    EPSILON, INFINITY,
 };
 
 #[derive(Debug)]
@@ -19,12 +20,12 @@ impl Plane {
 }
 
 impl Shape for Plane {
-    fn material(&self) -> &Material {
-        &self.material
+    fn material(&self) -> Option<&Material> {
+        Some(&self.material)
     }
 
-    fn material_mut(&mut self) -> &mut Material {
-        &mut self.material
+    fn material_mut(&mut self) -> Option<&mut Material> {
+        Some(&mut self.material)
     }
 
     fn local_intersect<'a>(
@@ -37,17 +38,19 @@ impl Shape for Plane {
         }
 
         let t = -r.origin().y / r.direction().y;
-        vec![Intersection {
-            t,
-            object: n,
-            u: 0.0,
-            v: 0.0,
-        }]
+        vec![Intersection::new(t, n)]
     }
 
     fn local_normal_at(&self, _: &Point3D, _: &Intersection) -> Vector3D {
         Vector3D::new(0.0, 1.0, 0.0)
     }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point3D::new(-INFINITY, 0.0, -INFINITY),
+            Point3D::new(INFINITY, 0.0, INFINITY),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -62,6 +65,7 @@ mod tests {
             object: &Node::new(Box::new(Plane::new())),
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let n1 = p.local_normal_at(&Point3D::new(0.0, 0.0, 0.0), &i);
         let n2 = p.local_normal_at(&Point3D::new(10.0, 0.0, -10.0), &i);
@@ -27,6 +27,14 @@ impl Point3D {
     pub fn new(x: FLOAT, y: FLOAT, z: FLOAT) -> Self {
         Point3D { x, y, z }
     }
+
+    /// self と other の距離を計算する
+    ///
+    /// # Argumets
+    /// * `other` - Point3D
+    pub fn distance_to(&self, other: &Point3D) -> FLOAT {
+        (other - self).magnitude()
+    }
 }
 
 impl PartialEq for Point3D {
@@ -133,6 +141,16 @@ mod tests {
         assert_eq!(Point3D::new(1.0, 1.0, 6.0), &a1 + &a2);
     }
 
+    #[test]
+    fn adding_a_tiny_vector_to_a_distant_point_keeps_precision() {
+        let p = Point3D::new(1e8, 0.0, 0.0);
+        let v = Vector3D::new(0.25, 0.0, 0.0);
+
+        // f32 では 1e8 + 0.25 は 1e8 に丸められてしまう
+        let moved = &p + &v;
+        assert_eq!(0.25, moved.x - 1e8);
+    }
+
     #[test]
     fn subtracting_two_points() {
         let p1 = Point3D::new(3.0, 2.0, 1.0);
@@ -176,4 +194,13 @@ mod tests {
 
         assert_eq!(Point3D::new(0.5, -1.0, 1.5), &v / 2.0);
     }
+
+    #[test]
+    fn the_distance_between_two_points() {
+        let p1 = Point3D::new(0.0, 0.0, 0.0);
+        let p2 = Point3D::new(3.0, 4.0, 0.0);
+
+        assert_eq!(5.0, p1.distance_to(&p2));
+        assert_eq!(5.0, p2.distance_to(&p1));
+    }
 }
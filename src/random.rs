@@ -0,0 +1,72 @@
+use super::FLOAT;
+
+/// xorshift64* による疑似乱数生成器。
+/// 同じ seed からは常に同じ列を生成するため、レンダリング結果を再現できる。
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    /// 新規に Random を作成する
+    ///
+    /// # Argumets
+    /// * `seed` - 乱数列を決める種。0 も使用できる。
+    pub fn new(seed: u64) -> Self {
+        // splitmix64 で種を拡散し、状態が 0 にならないようにする
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+
+        Random {
+            state: if z == 0 { 1 } else { z },
+        }
+    }
+
+    /// 次の 64 bit の乱数を返す
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// [0, 1) の一様乱数を返す
+    pub fn next_float(&mut self) -> FLOAT {
+        // 上位 53 bit を仮数部として用いる
+        (self.next_u64() >> 11) as FLOAT / (1u64 << 53) as FLOAT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_seed_produces_the_same_sequence() {
+        let mut r1 = Random::new(42);
+        let mut r2 = Random::new(42);
+        let mut r3 = Random::new(43);
+
+        let s1: Vec<u64> = (0..8).map(|_| r1.next_u64()).collect();
+        let s2: Vec<u64> = (0..8).map(|_| r2.next_u64()).collect();
+        let s3: Vec<u64> = (0..8).map(|_| r3.next_u64()).collect();
+        assert_eq!(s1, s2);
+        assert_ne!(s1, s3);
+    }
+
+    #[test]
+    fn floats_are_in_the_unit_interval() {
+        let mut r = Random::new(0);
+        let mut sum = 0.0;
+        for _ in 0..1000 {
+            let f = r.next_float();
+            assert!(0.0 <= f && f < 1.0);
+            sum += f;
+        }
+
+        // 平均はおおよそ 0.5 になる
+        assert!((sum / 1000.0 - 0.5).abs() < 0.05);
+    }
+}
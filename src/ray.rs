@@ -1,4 +1,6 @@
-use super::{point3d::Point3D, vector3d::Vector3D, FLOAT};
+use super::{
+    point3d::Point3D, transform::Transform, vector3d::Vector3D, FLOAT,
+};
 
 /// Ray
 #[derive(Debug)]
@@ -7,6 +9,8 @@ pub struct Ray {
     origin: Point3D,
     /// Ray の方向
     direction: Vector3D,
+    /// Ray が発せられた時刻 [0, 1)。モーションブラーに用いる。
+    time: FLOAT,
 }
 
 impl Ray {
@@ -16,7 +20,20 @@ impl Ray {
     /// * `origin` - Ray の始点
     /// * `direction` - Ray の方向
     pub fn new(origin: Point3D, direction: Vector3D) -> Self {
-        Ray { origin, direction }
+        Ray {
+            origin,
+            direction,
+            time: 0.0,
+        }
+    }
+
+    /// 時刻を設定した Ray を作成する
+    ///
+    /// # Argumets
+    /// * `time` - Ray が発せられた時刻
+    pub fn with_time(mut self, time: FLOAT) -> Self {
+        self.time = time;
+        self
     }
 
     /// Ray の始点を取得する
@@ -29,6 +46,11 @@ impl Ray {
         &self.direction
     }
 
+    /// Ray が発せられた時刻を取得する
+    pub fn time(&self) -> FLOAT {
+        self.time
+    }
+
     /// origin から direction 方向に t だけ進んだ点を取得する
     ///
     /// # Argumets
@@ -36,6 +58,26 @@ impl Ray {
     pub fn position(&self, t: FLOAT) -> Point3D {
         &self.origin + &(t * &self.direction)
     }
+
+    /// 変換 t を適用した Ray を返す。時刻は引き継ぐ。
+    ///
+    /// # Argumets
+    /// * `t` - 適用する変換
+    pub fn transform(&self, t: &Transform) -> Ray {
+        t * self
+    }
+
+    /// 変換 t を適用した Ray を out に書き込む。
+    /// 既存の Ray を使い回すため、繰り返し変換する場合に用いる。
+    ///
+    /// # Argumets
+    /// * `t` - 適用する変換
+    /// * `out` - 結果を書き込む Ray
+    pub fn transform_into(&self, t: &Transform, out: &mut Ray) {
+        out.origin = t * &self.origin;
+        out.direction = t * &self.direction;
+        out.time = self.time;
+    }
 }
 
 #[cfg(test)]
@@ -62,4 +104,24 @@ mod tests {
         assert_eq!(Point3D::new(1.0, 3.0, 4.0), ray.position(-1.0));
         assert_eq!(Point3D::new(4.5, 3.0, 4.0), ray.position(2.5));
     }
+
+    #[test]
+    fn transforming_a_ray_in_place() {
+        let r =
+            Ray::new(Point3D::new(1.0, 2.0, 3.0), Vector3D::new(0.0, 1.0, 0.0))
+                .with_time(0.25);
+        let t = &Transform::translation(3.0, 4.0, 5.0)
+            * &Transform::scaling(2.0, 3.0, 4.0);
+        let expected = &t * &r;
+
+        let transformed = r.transform(&t);
+        assert_eq!(expected.origin(), transformed.origin());
+        assert_eq!(expected.direction(), transformed.direction());
+
+        let mut out = Ray::new(Point3D::ZERO, Vector3D::ZERO);
+        r.transform_into(&t, &mut out);
+        assert_eq!(expected.origin(), out.origin());
+        assert_eq!(expected.direction(), out.direction());
+        assert_eq!(0.25, out.time());
+    }
 }
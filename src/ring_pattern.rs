@@ -1,5 +1,6 @@
 use super::{
     color::Color, pattern::Pattern, point3d::Point3D, transform::Transform,
+    FLOAT,
 };
 
 /// xz 平面上の同心円パターン
@@ -7,6 +8,8 @@ use super::{
 pub struct RingPattern {
     a: Color,
     b: Color,
+    /// 半径方向の繰り返しの周期
+    period: FLOAT,
     /// Pattern -> Shape Transform
     transform: Transform,
 }
@@ -16,9 +19,19 @@ impl RingPattern {
         RingPattern {
             a,
             b,
+            period: 1.0,
             transform: Transform::identity(),
         }
     }
+
+    /// 繰り返しの周期を period に設定する
+    ///
+    /// # Argumets
+    /// * `period` - 繰り返しの周期
+    pub fn with_period(mut self, period: FLOAT) -> Self {
+        self.period = period;
+        self
+    }
 }
 
 impl Pattern for RingPattern {
@@ -31,7 +44,9 @@ impl Pattern for RingPattern {
     }
 
     fn pattern_at(&self, p: &Point3D) -> Color {
-        let distance = (&p.x * &p.x + &p.z * &p.z).floor() as i32;
+        let x = p.x / self.period;
+        let z = p.z / self.period;
+        let distance = (x * x + z * z).floor() as i32;
         if distance % 2 == 0 {
             self.a
         } else {
@@ -65,4 +80,19 @@ mod tests {
             pattern.pattern_at(&Point3D::new(0.708, 0.0, 0.708))
         );
     }
+
+    #[test]
+    fn rings_with_a_longer_period() {
+        let pattern =
+            RingPattern::new(Color::WHITE, Color::BLACK).with_period(2.0);
+
+        assert_eq!(
+            Color::WHITE,
+            pattern.pattern_at(&Point3D::new(1.5, 0.0, 0.0))
+        );
+        assert_eq!(
+            Color::BLACK,
+            pattern.pattern_at(&Point3D::new(0.0, 0.0, 2.0))
+        );
+    }
 }
@@ -0,0 +1,595 @@
+//! JSON 形式のシーン記述から World と Camera を構築する。
+//!
+//! ```json
+//! {
+//!   "camera": {
+//!     "width": 100, "height": 50, "field_of_view": 1.0472,
+//!     "from": [0, 1.5, -5], "to": [0, 1, 0], "up": [0, 1, 0]
+//!   },
+//!   "lights": [{ "position": [-10, 10, -10], "intensity": [1, 1, 1] }],
+//!   "shapes": [
+//!     {
+//!       "type": "sphere",
+//!       "transform": [["scale", 0.5, 0.5, 0.5], ["translate", 1.5, 0.5, 0]],
+//!       "material": {
+//!         "color": [0.5, 1, 0.1], "diffuse": 0.7,
+//!         "pattern": { "type": "checkers", "colors": [[1, 1, 1], [0, 0, 0]] }
+//!       }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! transform は記述された順に適用する。
+use super::{
+    camera::Camera, checkers_pattern::CheckersPattern, color::Color,
+    cube::Cube, gradient_pattern::GradientPattern, light::Light,
+    material::Material, node::Node, pattern::Pattern, plane::Plane,
+    point3d::Point3D, ring_pattern::RingPattern, shape::Shape, sphere::Sphere,
+    stripe_pattern::StripePattern, transform::Transform, vector3d::Vector3D,
+    world::World, FLOAT,
+};
+use std::{fmt, io::Read};
+
+/// シーン記述の読み込み時に発生するエラー
+#[derive(Debug)]
+pub enum SceneError {
+    /// 読み込みに失敗した
+    Io(std::io::Error),
+    /// JSON として解釈できない
+    Syntax { position: usize, message: String },
+    /// 必須の項目がない
+    MissingField(String),
+    /// 項目の値の型や個数が正しくない
+    InvalidValue(String),
+    /// 未対応の shape, pattern, transform の種類
+    UnknownType(String),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(f, "{}", e),
+            SceneError::Syntax { position, message } => {
+                write!(f, "position {}: {}", position, message)
+            }
+            SceneError::MissingField(name) => {
+                write!(f, "missing field: {}", name)
+            }
+            SceneError::InvalidValue(name) => {
+                write!(f, "invalid value: {}", name)
+            }
+            SceneError::UnknownType(name) => {
+                write!(f, "unknown type: {}", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<std::io::Error> for SceneError {
+    fn from(e: std::io::Error) -> Self {
+        SceneError::Io(e)
+    }
+}
+
+/// シーン記述を読み込み、World と Camera を作成する。
+///
+/// # Argumets
+/// * `reader` - JSON 形式のシーン記述
+pub fn load_scene(
+    reader: &mut dyn Read,
+) -> Result<(World, Camera), SceneError> {
+    let mut source = String::new();
+    reader.read_to_string(&mut source)?;
+    let root = JsonParser::new(&source).parse()?;
+
+    let camera = build_camera(field(&root, "camera")?)?;
+
+    let mut world = World::new();
+    if let Some(lights) = optional_field(&root, "lights") {
+        for light in as_array(lights, "lights")? {
+            world.add_light(Light::new(
+                as_point(field(light, "position")?, "position")?,
+                as_color(field(light, "intensity")?, "intensity")?,
+            ));
+        }
+    }
+    if let Some(shapes) = optional_field(&root, "shapes") {
+        for shape in as_array(shapes, "shapes")? {
+            world.add_node(build_node(shape)?);
+        }
+    }
+
+    Ok((world, camera))
+}
+
+fn build_camera(value: &JsonValue) -> Result<Camera, SceneError> {
+    let width = as_number(field(value, "width")?, "width")?;
+    let height = as_number(field(value, "height")?, "height")?;
+    let fov = as_number(field(value, "field_of_view")?, "field_of_view")?;
+    let mut camera = Camera::new(width as usize, height as usize, fov);
+
+    let from = as_point(field(value, "from")?, "from")?;
+    let to = as_point(field(value, "to")?, "to")?;
+    let up = as_vector(field(value, "up")?, "up")?;
+    *camera.transform_mut() = Transform::view_transform(&from, &to, &up);
+
+    Ok(camera)
+}
+
+fn build_node(value: &JsonValue) -> Result<Box<Node>, SceneError> {
+    let kind = as_str(field(value, "type")?, "type")?;
+    let shape: Box<dyn Shape> = match kind {
+        "sphere" => Box::new(Sphere::new()),
+        "plane" => Box::new(Plane::new()),
+        "cube" => Box::new(Cube::new()),
+        _ => return Err(SceneError::UnknownType(kind.to_string())),
+    };
+
+    let mut node = Node::new(shape);
+    if let Some(transform) = optional_field(value, "transform") {
+        node.set_transform(build_transform(transform)?);
+    }
+    if let Some(material) = optional_field(value, "material") {
+        if let Some(m) = node.material_mut() {
+            apply_material(material, m)?;
+        }
+    }
+
+    Ok(node)
+}
+
+fn build_transform(value: &JsonValue) -> Result<Transform, SceneError> {
+    let mut transform = Transform::identity();
+    for op in as_array(value, "transform")? {
+        let args = as_array(op, "transform")?;
+        if args.is_empty() {
+            return Err(SceneError::InvalidValue("transform".to_string()));
+        }
+        let name = as_str(&args[0], "transform")?;
+        let numbers = args[1..]
+            .iter()
+            .map(|v| as_number(v, name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let expected = match name {
+            "translate" | "scale" => 3,
+            "rotate-x" | "rotate-y" | "rotate-z" => 1,
+            "shear" => 6,
+            _ => return Err(SceneError::UnknownType(name.to_string())),
+        };
+        if numbers.len() != expected {
+            return Err(SceneError::InvalidValue(name.to_string()));
+        }
+
+        let n = &numbers;
+        let t = match name {
+            "translate" => Transform::translation(n[0], n[1], n[2]),
+            "scale" => Transform::scaling(n[0], n[1], n[2]),
+            "rotate-x" => Transform::rotation_x(n[0]),
+            "rotate-y" => Transform::rotation_y(n[0]),
+            "rotate-z" => Transform::rotation_z(n[0]),
+            _ => Transform::shearing(n[0], n[1], n[2], n[3], n[4], n[5]),
+        };
+        transform = transform.then(&t);
+    }
+
+    Ok(transform)
+}
+
+fn apply_material(
+    value: &JsonValue,
+    material: &mut Material,
+) -> Result<(), SceneError> {
+    if let Some(color) = optional_field(value, "color") {
+        material.color = as_color(color, "color")?;
+    }
+    let numbers: [(&str, &mut FLOAT); 8] = [
+        ("ambient", &mut material.ambient),
+        ("diffuse", &mut material.diffuse),
+        ("specular", &mut material.specular),
+        ("shininess", &mut material.shininess),
+        ("reflective", &mut material.reflective),
+        ("transparency", &mut material.transparency),
+        ("refractive_index", &mut material.refractive_index),
+        ("opacity", &mut material.opacity),
+    ];
+    for (name, target) in numbers {
+        if let Some(v) = optional_field(value, name) {
+            *target = as_number(v, name)?;
+        }
+    }
+    if let Some(emission) = optional_field(value, "emission") {
+        material.emission = as_color(emission, "emission")?;
+    }
+    if let Some(casts_shadow) = optional_field(value, "casts_shadow") {
+        material.casts_shadow = match casts_shadow {
+            JsonValue::Bool(b) => *b,
+            _ => {
+                return Err(SceneError::InvalidValue(
+                    "casts_shadow".to_string(),
+                ))
+            }
+        };
+    }
+    if let Some(pattern) = optional_field(value, "pattern") {
+        *material.pattern_mut() = Some(build_pattern(pattern)?);
+    }
+
+    Ok(())
+}
+
+fn build_pattern(value: &JsonValue) -> Result<Box<dyn Pattern>, SceneError> {
+    let kind = as_str(field(value, "type")?, "type")?;
+    let colors = as_array(field(value, "colors")?, "colors")?;
+    if colors.len() != 2 {
+        return Err(SceneError::InvalidValue("colors".to_string()));
+    }
+    let a = as_color(&colors[0], "colors")?;
+    let b = as_color(&colors[1], "colors")?;
+
+    let mut pattern: Box<dyn Pattern> = match kind {
+        "checkers" => Box::new(CheckersPattern::new(a, b)),
+        "stripes" => Box::new(StripePattern::new(a, b)),
+        "gradient" => Box::new(GradientPattern::new(a, b)),
+        "rings" => Box::new(RingPattern::new(a, b)),
+        _ => return Err(SceneError::UnknownType(kind.to_string())),
+    };
+    if let Some(transform) = optional_field(value, "transform") {
+        *pattern.transform_mut() = build_transform(transform)?;
+    }
+
+    Ok(pattern)
+}
+
+/// シーン記述の JSON の値
+#[derive(Debug, PartialEq)]
+enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(FLOAT),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+fn optional_field<'a>(
+    value: &'a JsonValue,
+    name: &str,
+) -> Option<&'a JsonValue> {
+    match value {
+        JsonValue::Object(members) => {
+            members.iter().find(|(k, _)| k == name).map(|(_, v)| v)
+        }
+        _ => None,
+    }
+}
+
+fn field<'a>(
+    value: &'a JsonValue,
+    name: &str,
+) -> Result<&'a JsonValue, SceneError> {
+    optional_field(value, name)
+        .ok_or_else(|| SceneError::MissingField(name.to_string()))
+}
+
+fn as_number(value: &JsonValue, name: &str) -> Result<FLOAT, SceneError> {
+    match value {
+        JsonValue::Number(n) => Ok(*n),
+        _ => Err(SceneError::InvalidValue(name.to_string())),
+    }
+}
+
+fn as_str<'a>(value: &'a JsonValue, name: &str) -> Result<&'a str, SceneError> {
+    match value {
+        JsonValue::String(s) => Ok(s),
+        _ => Err(SceneError::InvalidValue(name.to_string())),
+    }
+}
+
+fn as_array<'a>(
+    value: &'a JsonValue,
+    name: &str,
+) -> Result<&'a [JsonValue], SceneError> {
+    match value {
+        JsonValue::Array(values) => Ok(values),
+        _ => Err(SceneError::InvalidValue(name.to_string())),
+    }
+}
+
+fn as_triple(
+    value: &JsonValue,
+    name: &str,
+) -> Result<(FLOAT, FLOAT, FLOAT), SceneError> {
+    let values = as_array(value, name)?;
+    if values.len() != 3 {
+        return Err(SceneError::InvalidValue(name.to_string()));
+    }
+
+    Ok((
+        as_number(&values[0], name)?,
+        as_number(&values[1], name)?,
+        as_number(&values[2], name)?,
+    ))
+}
+
+fn as_point(value: &JsonValue, name: &str) -> Result<Point3D, SceneError> {
+    let (x, y, z) = as_triple(value, name)?;
+    Ok(Point3D::new(x, y, z))
+}
+
+fn as_vector(value: &JsonValue, name: &str) -> Result<Vector3D, SceneError> {
+    let (x, y, z) = as_triple(value, name)?;
+    Ok(Vector3D::new(x, y, z))
+}
+
+fn as_color(value: &JsonValue, name: &str) -> Result<Color, SceneError> {
+    let (r, g, b) = as_triple(value, name)?;
+    Ok(Color::new(r, g, b))
+}
+
+/// シーン記述に必要な範囲の JSON を解析する
+struct JsonParser<'a> {
+    source: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn new(source: &'a str) -> Self {
+        JsonParser {
+            source: source.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    /// 文字列全体を 1 つの値として解析する
+    fn parse(&mut self) -> Result<JsonValue, SceneError> {
+        let value = self.parse_value()?;
+        self.skip_whitespace();
+        if self.pos < self.source.len() {
+            return Err(self.error("trailing characters"));
+        }
+
+        Ok(value)
+    }
+
+    fn error(&self, message: &str) -> SceneError {
+        SceneError::Syntax {
+            position: self.pos,
+            message: message.to_string(),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.source.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_ascii_whitespace() {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), SceneError> {
+        self.skip_whitespace();
+        if self.peek() != Some(c) {
+            return Err(self.error(&format!("expected '{}'", c as char)));
+        }
+        self.pos += 1;
+
+        Ok(())
+    }
+
+    fn parse_value(&mut self) -> Result<JsonValue, SceneError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.parse_object(),
+            Some(b'[') => self.parse_array(),
+            Some(b'"') => Ok(JsonValue::String(self.parse_string()?)),
+            Some(b't') => self.parse_literal("true", JsonValue::Bool(true)),
+            Some(b'f') => self.parse_literal("false", JsonValue::Bool(false)),
+            Some(b'n') => self.parse_literal("null", JsonValue::Null),
+            Some(c) if c == b'-' || c.is_ascii_digit() => self.parse_number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn parse_literal(
+        &mut self,
+        literal: &str,
+        value: JsonValue,
+    ) -> Result<JsonValue, SceneError> {
+        if self.source[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(value)
+        } else {
+            Err(self.error("invalid literal"))
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, SceneError> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_digit() || b"+-.eE".contains(&c)) {
+                break;
+            }
+            self.pos += 1;
+        }
+
+        std::str::from_utf8(&self.source[start..self.pos])
+            .ok()
+            .and_then(|s| s.parse::<FLOAT>().ok())
+            .map(JsonValue::Number)
+            .ok_or_else(|| SceneError::Syntax {
+                position: start,
+                message: "invalid number".to_string(),
+            })
+    }
+
+    fn parse_string(&mut self) -> Result<String, SceneError> {
+        self.expect(b'"')?;
+        let mut bytes = vec![];
+        loop {
+            match self.peek() {
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let c = match self.peek() {
+                        Some(b'n') => b'\n',
+                        Some(b't') => b'\t',
+                        Some(c @ (b'"' | b'\\' | b'/')) => c,
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    bytes.push(c);
+                }
+                Some(c) => bytes.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+            self.pos += 1;
+        }
+        self.pos += 1;
+
+        String::from_utf8(bytes).map_err(|_| self.error("invalid utf-8"))
+    }
+
+    fn parse_array(&mut self) -> Result<JsonValue, SceneError> {
+        self.expect(b'[')?;
+        let mut values = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(JsonValue::Array(values));
+        }
+
+        loop {
+            values.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Array(values));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue, SceneError> {
+        self.expect(b'{')?;
+        let mut members = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(JsonValue::Object(members));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.expect(b':')?;
+            members.push((key, self.parse_value()?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Object(members));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO_SPHERES: &str = r#"
+        {
+          "camera": {
+            "width": 100, "height": 50, "field_of_view": 1.0472,
+            "from": [0, 1.5, -5], "to": [0, 1, 0], "up": [0, 1, 0]
+          },
+          "lights": [
+            { "position": [-10, 10, -10], "intensity": [1, 1, 1] }
+          ],
+          "shapes": [
+            {
+              "type": "sphere",
+              "material": { "color": [0.8, 1.0, 0.6], "diffuse": 0.7 }
+            },
+            {
+              "type": "sphere",
+              "transform": [["scale", 0.5, 0.5, 0.5]],
+              "material": {
+                "pattern": {
+                  "type": "checkers",
+                  "colors": [[1, 1, 1], [0, 0, 0]],
+                  "transform": [["scale", 0.25, 0.25, 0.25]]
+                }
+              }
+            }
+          ]
+        }
+    "#;
+
+    #[test]
+    fn loading_a_two_sphere_scene() {
+        let (world, camera) = load_scene(&mut TWO_SPHERES.as_bytes()).unwrap();
+
+        assert_eq!(100, camera.hsize());
+        assert_eq!(50, camera.vsize());
+        assert_eq!(1, world.lights().len());
+        assert_eq!(2, world.nodes().len());
+
+        let first = &world.nodes()[0];
+        assert_eq!(Color::new(0.8, 1.0, 0.6), first.material().unwrap().color);
+        assert_eq!(0.7, first.material().unwrap().diffuse);
+        let second = &world.nodes()[1];
+        assert_eq!(&Transform::scaling(0.5, 0.5, 0.5), second.transform());
+        assert!(second.material().unwrap().pattern().is_some());
+    }
+
+    #[test]
+    fn transforms_are_applied_in_the_listed_order() {
+        let value = JsonParser::new(
+            r#"[["rotate-x", 1.5707963267948966], ["translate", 0, 0, 5]]"#,
+        )
+        .parse()
+        .unwrap();
+
+        let t = build_transform(&value).unwrap();
+        assert_eq!(
+            Point3D::new(0.0, -1.0, 5.0),
+            &t * &Point3D::new(0.0, 0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn loading_a_scene_reports_errors() {
+        let missing_camera = r#"{ "shapes": [] }"#;
+        match load_scene(&mut missing_camera.as_bytes()) {
+            Err(SceneError::MissingField(name)) => assert_eq!("camera", name),
+            r => panic!("unexpected result: {:?}", r.map(|_| ())),
+        }
+
+        let unknown_shape = TWO_SPHERES.replacen("sphere", "teapot", 1);
+        match load_scene(&mut unknown_shape.as_bytes()) {
+            Err(SceneError::UnknownType(name)) => assert_eq!("teapot", name),
+            r => panic!("unexpected result: {:?}", r.map(|_| ())),
+        }
+
+        let broken = r#"{ "camera": { "width": 10, }"#;
+        assert!(matches!(
+            load_scene(&mut broken.as_bytes()),
+            Err(SceneError::Syntax { .. })
+        ));
+    }
+}
@@ -0,0 +1,304 @@
+use super::{
+    bounding_box::BoundingBox, intersection::Intersection, material::Material,
+    node::Node, point3d::Point3D, ray::Ray, shape::Shape, vector3d::Vector3D,
+    EPSILON, FLOAT,
+};
+use std::fmt::Debug;
+
+/// 符号付き距離関数 (signed distance function) で表される形状
+pub trait Sdf: Debug {
+    /// local 座標上の点 p から表面までの距離を返す。
+    /// 形状の内部では負の値となる。
+    ///
+    /// # Argumets
+    /// * `p` - local 座標系上の点
+    fn distance(&self, p: &Point3D) -> FLOAT;
+
+    /// local 座標系における BoundingBox を取得する。
+    /// デフォルトでは全空間を返す。
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::infinite()
+    }
+}
+
+/// Sdf を sphere tracing (ray marching) で描画する Shape。
+/// Ray の起点から表面に向かって距離関数の値ずつ進み、形状に入る点と
+/// 形状から出る点を交点として返す。
+/// 起点が形状の内部にある場合、入る点は起点より後ろ (負の t) となる。
+#[derive(Debug)]
+pub struct SdfShape {
+    /// 形状を表す距離関数
+    sdf: Box<dyn Sdf>,
+    /// マテリアル
+    material: Material,
+}
+
+impl SdfShape {
+    /// 表面に到達したとみなす距離
+    const HIT_DISTANCE: FLOAT = EPSILON * 0.5;
+    /// 進む回数の上限
+    const MAX_STEPS: usize = 256;
+    /// Ray の起点から探索する距離の上限
+    const MAX_DISTANCE: FLOAT = 1000.0;
+
+    /// 新規に SdfShape を作成する
+    ///
+    /// # Argumets
+    /// * `sdf` - 形状を表す距離関数
+    pub fn new(sdf: Box<dyn Sdf>) -> Self {
+        SdfShape {
+            sdf,
+            material: Material::new(),
+        }
+    }
+
+    /// Ray 上の距離 start から進み、距離関数の符号が sign の側から表面に
+    /// 到達したときの距離を返す。距離は Ray のパラメータ t ではなく
+    /// local 座標系での長さで表す。
+    /// over_point のように表面のすぐ近くから進み始めた場合、一度表面から
+    /// 離れるか表面を通り過ぎるまでは交点とみなさない。
+    ///
+    /// # Argumets
+    /// * `r` - local 座標系における Ray
+    /// * `speed` - r の direction の大きさ
+    /// * `start` - 進み始める距離
+    /// * `sign` - 形状の外側から進む場合は 1.0、内側から進む場合は -1.0
+    fn march(
+        &self,
+        r: &Ray,
+        speed: FLOAT,
+        start: FLOAT,
+        sign: FLOAT,
+    ) -> Option<FLOAT> {
+        let mut s = start;
+        let mut away = false;
+        for step in 0..Self::MAX_STEPS {
+            let d = sign * self.sdf.distance(&r.position(s / speed));
+            if (away && d < Self::HIT_DISTANCE) || (0 < step && d < 0.0) {
+                return Some(s);
+            }
+
+            away |= Self::HIT_DISTANCE <= d;
+            // 表面の近くでも止まらないよう、最低でも HIT_DISTANCE だけ進む
+            s += d.max(Self::HIT_DISTANCE);
+            if start + Self::MAX_DISTANCE < s {
+                break;
+            }
+        }
+
+        None
+    }
+}
+
+impl Shape for SdfShape {
+    fn material(&self) -> Option<&Material> {
+        Some(&self.material)
+    }
+
+    fn material_mut(&mut self) -> Option<&mut Material> {
+        Some(&mut self.material)
+    }
+
+    fn local_intersect<'a>(
+        &self,
+        r: &Ray,
+        n: &'a Node,
+    ) -> Vec<Intersection<'a>> {
+        // local 座標系の direction は単位ベクトルとは限らないため、
+        // 距離を Ray のパラメータ t に換算しながら進む
+        let speed = r.direction().magnitude();
+        if self.sdf.distance(r.origin()) < 0.0 {
+            // 起点より後ろで形状に入る点は、逆向きの Ray で形状から出る点
+            let back = Ray::new(r.origin().clone(), -r.direction());
+            let enter = self.march(&back, speed, 0.0, -1.0);
+            let exit = self.march(r, speed, 0.0, -1.0);
+
+            return enter
+                .map(|s| -s)
+                .into_iter()
+                .chain(exit)
+                .map(|s| Intersection::new(s / speed, n))
+                .collect();
+        }
+
+        let enter = match self.march(r, speed, 0.0, 1.0) {
+            Some(enter) => enter,
+            None => return vec![],
+        };
+        let exit = self.march(r, speed, enter, -1.0);
+
+        Some(enter)
+            .into_iter()
+            .chain(exit)
+            .map(|s| Intersection::new(s / speed, n))
+            .collect()
+    }
+
+    fn local_normal_at(&self, p: &Point3D, _: &Intersection) -> Vector3D {
+        // 距離関数の勾配を中心差分で求める
+        let h = EPSILON;
+        let dx = self.sdf.distance(&Point3D::new(p.x + h, p.y, p.z))
+            - self.sdf.distance(&Point3D::new(p.x - h, p.y, p.z));
+        let dy = self.sdf.distance(&Point3D::new(p.x, p.y + h, p.z))
+            - self.sdf.distance(&Point3D::new(p.x, p.y - h, p.z));
+        let dz = self.sdf.distance(&Point3D::new(p.x, p.y, p.z + h))
+            - self.sdf.distance(&Point3D::new(p.x, p.y, p.z - h));
+
+        let mut normal = Vector3D::new(dx, dy, dz);
+        normal.normalize();
+        normal
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.sdf.bounds()
+    }
+}
+
+/// 原点を中心とする球の距離関数
+#[derive(Debug)]
+pub struct SdfSphere {
+    /// 半径
+    radius: FLOAT,
+}
+
+impl SdfSphere {
+    /// 新規に SdfSphere を作成する
+    ///
+    /// # Argumets
+    /// * `radius` - 半径
+    pub fn new(radius: FLOAT) -> Self {
+        SdfSphere { radius }
+    }
+}
+
+impl Sdf for SdfSphere {
+    fn distance(&self, p: &Point3D) -> FLOAT {
+        (p - &Point3D::ZERO).magnitude() - self.radius
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point3D::new(-self.radius, -self.radius, -self.radius),
+            Point3D::new(self.radius, self.radius, self.radius),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{
+            color::Color, light::Light, sphere::Sphere, transform::Transform,
+            world::World,
+        },
+        *,
+    };
+
+    #[test]
+    fn the_distance_to_an_sdf_sphere() {
+        let s = SdfSphere::new(2.0);
+
+        assert_eq!(-2.0, s.distance(&Point3D::new(0.0, 0.0, 0.0)));
+        assert_eq!(0.0, s.distance(&Point3D::new(0.0, 2.0, 0.0)));
+        assert_eq!(3.0, s.distance(&Point3D::new(0.0, 0.0, -5.0)));
+    }
+
+    #[test]
+    fn an_sdf_sphere_matches_the_analytic_sphere() {
+        let transform = &Transform::translation(0.5, 0.0, 1.0)
+            * &Transform::scaling(2.0, 2.0, 2.0);
+        let mut analytic = Node::new(Box::new(Sphere::new()));
+        analytic.set_transform(transform.clone());
+        let mut marched =
+            Node::new(Box::new(SdfShape::new(Box::new(SdfSphere::new(1.0)))));
+        marched.set_transform(transform);
+
+        let r = Ray::new(
+            Point3D::new(0.0, 0.5, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+        let expected = analytic.intersect(&r);
+        let xs = marched.intersect(&r);
+
+        assert_eq!(2, xs.len());
+        assert!((expected[0].t - xs[0].t).abs() < 1e-4);
+        assert!((expected[1].t - xs[1].t).abs() < 1e-4);
+
+        let p = r.position(xs[0].t);
+        let expected_normal = analytic.normal_at(&p, &expected[0]);
+        let normal = marched.normal_at(&p, &xs[0]);
+        assert!((&expected_normal - &normal).magnitude() < 1e-4);
+    }
+
+    #[test]
+    fn a_ray_misses_an_sdf_sphere() {
+        let dummy_node =
+            Node::new(Box::new(SdfShape::new(Box::new(SdfSphere::new(1.0)))));
+        let s = SdfShape::new(Box::new(SdfSphere::new(1.0)));
+        let r = Ray::new(
+            Point3D::new(0.0, 2.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+
+        let xs = s.local_intersect(&r, &dummy_node);
+        assert_eq!(0, xs.len());
+    }
+
+    #[test]
+    fn a_ray_originates_inside_an_sdf_sphere() {
+        let dummy_node =
+            Node::new(Box::new(SdfShape::new(Box::new(SdfSphere::new(1.0)))));
+        let s = SdfShape::new(Box::new(SdfSphere::new(1.0)));
+        let r =
+            Ray::new(Point3D::new(0.0, 0.0, 0.0), Vector3D::new(0.0, 0.0, 1.0));
+
+        let xs = s.local_intersect(&r, &dummy_node);
+        assert_eq!(2, xs.len());
+        assert!((-1.0 - xs[0].t).abs() < 1e-4);
+        assert!((1.0 - xs[1].t).abs() < 1e-4);
+    }
+
+    #[test]
+    fn a_ray_leaving_the_surface_does_not_hit_it_again() {
+        let dummy_node =
+            Node::new(Box::new(SdfShape::new(Box::new(SdfSphere::new(1.0)))));
+        let s = SdfShape::new(Box::new(SdfSphere::new(1.0)));
+        // scaling した形状の over_point は、local 座標系では
+        // HIT_DISTANCE より表面に近くなる
+        let r = Ray::new(
+            Point3D::new(0.0, 1.0 + SdfShape::HIT_DISTANCE * 0.1, 0.0),
+            Vector3D::new(0.0, 1.0, 0.0),
+        );
+
+        let xs = s.local_intersect(&r, &dummy_node);
+        assert_eq!(0, xs.len());
+    }
+
+    #[test]
+    fn shading_a_scaled_sdf_sphere_matches_the_analytic_sphere() {
+        let light =
+            || Light::new(Point3D::new(-10.0, 10.0, -10.0), Color::WHITE);
+        let mut analytic = World::new();
+        analytic.add_light(light());
+        let mut s = Node::new(Box::new(Sphere::new()));
+        s.set_transform(Transform::scaling(10.0, 10.0, 10.0));
+        analytic.add_node(s);
+        let mut marched = World::new();
+        marched.add_light(light());
+        let mut s =
+            Node::new(Box::new(SdfShape::new(Box::new(SdfSphere::new(1.0)))));
+        s.set_transform(Transform::scaling(10.0, 10.0, 10.0));
+        marched.add_node(s);
+
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -50.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+        let expected = analytic.color_at(&r, 5);
+        let color = marched.color_at(&r, 5);
+
+        assert!((expected.red - color.red).abs() < 1e-3);
+        assert!((expected.green - color.green).abs() < 1e-3);
+        assert!((expected.blue - color.blue).abs() < 1e-3);
+    }
+}
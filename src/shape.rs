@@ -1,6 +1,7 @@
 use super::{
-    intersection::Intersection, material::Material, node::Node,
-    point3d::Point3D, ray::Ray, vector3d::Vector3D,
+    bounding_box::BoundingBox, intersection::Intersection, material::Material,
+    node::Node, point3d::Point3D, ray::Ray, uv_mapping::uv_plane,
+    vector3d::Vector3D, FLOAT,
 };
 use std::fmt::Debug;
 
@@ -17,10 +18,54 @@ pub trait Shape: Debug {
         panic!();
     }
 
-    /// Material を取得する
-    fn material(&self) -> &Material;
-    /// Material を取得する
-    fn material_mut(&mut self) -> &mut Material;
+    /// 子 Node の数を取得する。子を持たない Shape は 0 を返す。
+    fn child_count(&self) -> usize {
+        0
+    }
+
+    /// n が self の子孫であるか
+    ///
+    /// # Argumets
+    /// * `n` - 判定対象の Node
+    fn includes(&self, _n: &Node) -> bool {
+        false
+    }
+
+    /// 子 Node を全て取得する。子を持たない Shape は空の slice を返す。
+    fn children(&self) -> &[Box<Node>] {
+        &[]
+    }
+
+    /// 子 Node を全て取得する。子を持たない Shape は空の Vec を返す。
+    fn children_mut(&mut self) -> Vec<&mut Node> {
+        vec![]
+    }
+
+    /// 子 Node を全て取り出す。子を持たない Shape は空の Vec を返す。
+    fn take_children(&mut self) -> Vec<Box<Node>> {
+        vec![]
+    }
+
+    /// 三角形 (Triangle, SmoothTriangle) であるか
+    fn is_triangle(&self) -> bool {
+        false
+    }
+
+    /// 子 Node を自由に組み替えられる Group であるか
+    fn is_group(&self) -> bool {
+        false
+    }
+
+    /// Material を取得する。Group のように Material を持たない Shape は
+    /// None を返す。
+    fn material(&self) -> Option<&Material> {
+        None
+    }
+    /// Material を取得する。Group のように Material を持たない Shape は
+    /// None を返す。
+    fn material_mut(&mut self) -> Option<&mut Material> {
+        None
+    }
 
     /// 各 Shape 固有の衝突判定を行う
     ///
@@ -32,12 +77,60 @@ pub trait Shape: Debug {
         n: &'a Node,
     ) -> Vec<Intersection<'a>>;
 
+    /// 各 Shape 固有の衝突判定を行い、交点を out の末尾に追加する。
+    /// out の内容は消去せず、ソートも行わない。
+    /// デフォルトでは local_intersect の結果を追加する。
+    ///
+    /// # Argumets
+    /// * `r` - local 座標系における Ray
+    /// * `n` - self を持つ Node
+    /// * `out` - 交点を追加するバッファ
+    fn local_intersect_into<'a>(
+        &'a self,
+        r: &Ray,
+        n: &'a Node,
+        out: &mut Vec<Intersection<'a>>,
+    ) {
+        out.append(&mut self.local_intersect(r, n));
+    }
+
+    /// local 座標系の Ray r が、t が [0, max_t) の範囲で影を落とす
+    /// オブジェクトと交差するか。
+    /// デフォルトでは local_intersect の結果から判定する。
+    ///
+    /// # Argumets
+    /// * `r` - local 座標系における Ray
+    /// * `max_t` - 判定する t の上限
+    /// * `n` - self を持つ Node
+    fn local_any_hit_before(&self, r: &Ray, max_t: FLOAT, n: &Node) -> bool {
+        self.local_intersect(r, n).iter().any(|i| {
+            0.0 <= i.t
+                && i.t < max_t
+                && i.object.material().is_some_and(|m| m.casts_shadow)
+        })
+    }
+
     /// local 座標上の点 p における法線ベクトルを取得する。
     ///
     /// # Argumets
     /// * `p` - local 座標系上の点
     /// * `i` - Ray との交点に関する情報
     fn local_normal_at(&self, p: &Point3D, i: &Intersection) -> Vector3D;
+
+    /// local 座標系における BoundingBox を取得する。
+    /// デフォルトでは全空間を返す。
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::infinite()
+    }
+
+    /// local 座標上の点 p に対応する (u, v) を取得する。
+    /// デフォルトでは xz 平面への対応付けを用いる。
+    ///
+    /// # Argumets
+    /// * `p` - local 座標系上の点
+    fn uv_at(&self, p: &Point3D) -> (FLOAT, FLOAT) {
+        uv_plane(p)
+    }
 }
 
 #[cfg(test)]
@@ -63,12 +156,12 @@ mod tests {
     }
 
     impl Shape for TestShape {
-        fn material(&self) -> &Material {
-            &self.material
+        fn material(&self) -> Option<&Material> {
+            Some(&self.material)
         }
 
-        fn material_mut(&mut self) -> &mut Material {
-            &mut self.material
+        fn material_mut(&mut self) -> Option<&mut Material> {
+            Some(&mut self.material)
         }
 
         fn local_intersect(&self, r: &Ray, _: &Node) -> Vec<Intersection> {
@@ -94,11 +187,11 @@ mod tests {
     fn the_default_material() {
         let shape = test_shape();
 
-        assert_eq!(Color::WHITE, shape.material().color);
-        assert_eq!(0.1, shape.material().ambient);
-        assert_eq!(0.9, shape.material().diffuse);
-        assert_eq!(0.9, shape.material().specular);
-        assert_eq!(200.0, shape.material().shininess);
+        assert_eq!(Color::WHITE, shape.material().unwrap().color);
+        assert_eq!(0.1, shape.material().unwrap().ambient);
+        assert_eq!(0.9, shape.material().unwrap().diffuse);
+        assert_eq!(0.9, shape.material().unwrap().specular);
+        assert_eq!(200.0, shape.material().unwrap().shininess);
     }
 
     #[test]
@@ -107,8 +200,8 @@ mod tests {
         let mut m = Material::new();
         m.ambient = 1.0;
 
-        *shape.material_mut() = m;
-        assert_eq!(1.0, shape.material().ambient);
+        *shape.material_mut().unwrap() = m;
+        assert_eq!(1.0, shape.material().unwrap().ambient);
     }
 
     #[test]
@@ -152,6 +245,7 @@ mod tests {
                 object: &s,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
         );
 
@@ -176,6 +270,7 @@ mod tests {
                 object: &s,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
         );
 
@@ -1,6 +1,7 @@
 use crate::{
-    intersection::Intersection, material::Material, node::Node,
-    point3d::Point3D, ray::Ray, shape::Shape, vector3d::Vector3D, EPSILON,
+    bounding_box::BoundingBox, intersection::Intersection, material::Material,
+    node::Node, point3d::Point3D, ray::Ray, shape::Shape, vector3d::Vector3D,
+    EPSILON,
 };
 
 #[derive(Debug)]
@@ -73,12 +74,16 @@ impl SmoothTriangle {
 }
 
 impl Shape for SmoothTriangle {
-    fn material(&self) -> &Material {
-        &self.material
+    fn is_triangle(&self) -> bool {
+        true
     }
 
-    fn material_mut(&mut self) -> &mut Material {
-        &mut self.material
+    fn material(&self) -> Option<&Material> {
+        Some(&self.material)
+    }
+
+    fn material_mut(&mut self) -> Option<&mut Material> {
+        Some(&mut self.material)
     }
 
     fn local_intersect<'a>(
@@ -111,6 +116,7 @@ impl Shape for SmoothTriangle {
             object: n,
             u,
             v,
+            front_face: 0.0 < det,
         }]
     }
 
@@ -118,6 +124,14 @@ impl Shape for SmoothTriangle {
         &(&(&self.n2 * i.u) + &(&self.n3 * i.v))
             + &(&self.n1 * (1.0 - i.u - i.v))
     }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut b = BoundingBox::empty();
+        b.add_point(&self.p1);
+        b.add_point(&self.p2);
+        b.add_point(&self.p3);
+        b
+    }
 }
 
 #[cfg(test)]
@@ -201,6 +215,7 @@ mod tests {
             object: &tri,
             u: 0.45,
             v: 0.25,
+            front_face: true,
         };
 
         let n = tri.normal_at(&Point3D::new(0.0, 0.0, 0.0), &i);
@@ -227,6 +242,7 @@ mod tests {
             object: &tri,
             u: 0.45,
             v: 0.25,
+            front_face: true,
         };
         let xs = vec![i];
         let comps = IntersectionState::new(&xs[0], &r, &xs);
@@ -0,0 +1,68 @@
+use super::{
+    color::Color, pattern::Pattern, point3d::Point3D, transform::Transform,
+};
+
+/// 位置によらず常に同じ色を返すパターン
+#[derive(Debug)]
+pub struct SolidPattern {
+    color: Color,
+    /// Pattern -> Shape Transform
+    transform: Transform,
+}
+
+impl SolidPattern {
+    /// 新規に SolidPattern を作成する
+    ///
+    /// # Argumets
+    /// * `color` - 色
+    pub fn new(color: Color) -> Self {
+        SolidPattern {
+            color,
+            transform: Transform::identity(),
+        }
+    }
+}
+
+impl Pattern for SolidPattern {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn transform_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+
+    fn pattern_at(&self, _p: &Point3D) -> Color {
+        self.color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{node::Node, sphere::Sphere},
+        *,
+    };
+
+    #[test]
+    fn a_solid_pattern_is_constant_everywhere() {
+        let mut pattern = SolidPattern::new(Color::RED);
+
+        assert_eq!(
+            Color::RED,
+            pattern.pattern_at(&Point3D::new(0.0, 0.0, 0.0))
+        );
+        assert_eq!(
+            Color::RED,
+            pattern.pattern_at(&Point3D::new(-1.5, 20.0, 0.3))
+        );
+
+        *pattern.transform_mut() = Transform::scaling(2.0, 3.0, 4.0);
+        let mut node = Node::new(Box::new(Sphere::new()));
+        node.set_transform(Transform::translation(1.0, 2.0, 3.0));
+        assert_eq!(
+            Color::RED,
+            pattern.pattern_at_shape(&node, &Point3D::new(2.5, -3.0, 0.5))
+        );
+    }
+}
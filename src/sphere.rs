@@ -1,6 +1,7 @@
 use super::{
-    intersection::Intersection, material::Material, node::Node,
-    point3d::Point3D, ray::Ray, shape::Shape, vector3d::Vector3D,
+    bounding_box::BoundingBox, intersection::Intersection, material::Material,
+    node::Node, point3d::Point3D, ray::Ray, shape::Shape, vector3d::Vector3D,
+    FLOAT,
 };
 
 /// 原点を中心とする半径 1 の単位球
@@ -19,12 +20,12 @@ impl Sphere {
 }
 
 impl Shape for Sphere {
-    fn material(&self) -> &Material {
-        &self.material
+    fn material(&self) -> Option<&Material> {
+        Some(&self.material)
     }
 
-    fn material_mut(&mut self) -> &mut Material {
-        &mut self.material
+    fn material_mut(&mut self) -> Option<&mut Material> {
+        Some(&mut self.material)
     }
 
     fn local_intersect<'a>(
@@ -49,25 +50,38 @@ impl Shape for Sphere {
         let t1 = (-b - discriminant.sqrt()) / (2.0 * a);
         let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
 
-        return vec![
-            Intersection {
-                t: t1,
-                object: n,
-                u: 0.0,
-                v: 0.0,
-            },
-            Intersection {
-                t: t2,
-                object: n,
-                u: 0.0,
-                v: 0.0,
-            },
-        ];
+        return vec![Intersection::new(t1, n), Intersection::new(t2, n)];
     }
 
     fn local_normal_at(&self, p: &Point3D, _: &Intersection) -> Vector3D {
         Vector3D::new(p.x, p.y, p.z)
     }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point3D::new(-1.0, -1.0, -1.0),
+            Point3D::new(1.0, 1.0, 1.0),
+        )
+    }
+
+    /// u は -z 方向を 0 として y 軸回りに、v は南極を 0 として北極に向かって
+    /// 増加する。
+    fn uv_at(&self, p: &Point3D) -> (FLOAT, FLOAT) {
+        let two_pi = 2.0 * std::f64::consts::PI as FLOAT;
+
+        // 方位角 (-π, π]
+        let theta = p.x.atan2(p.z);
+        let radius = (p.x * p.x + p.y * p.y + p.z * p.z).sqrt();
+        // 極角 [0, π]
+        let phi = (p.y / radius).acos();
+
+        let raw_u = theta / two_pi;
+        // 反時計回りに増加するよう反転する
+        let u = 1.0 - (raw_u + 0.5);
+        let v = 1.0 - phi / std::f64::consts::PI as FLOAT;
+
+        (u, v)
+    }
 }
 
 #[cfg(test)]
@@ -214,6 +228,7 @@ mod tests {
                 object: &s,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
         );
 
@@ -230,6 +245,7 @@ mod tests {
                 object: &s,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
         );
 
@@ -246,6 +262,7 @@ mod tests {
                 object: &s,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
         );
 
@@ -266,6 +283,7 @@ mod tests {
                 object: &s,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
         );
 
@@ -293,6 +311,7 @@ mod tests {
                 object: &s,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
         );
 
@@ -308,6 +327,7 @@ mod tests {
                     object: &s,
                     u: 0.0,
                     v: 0.0,
+                    front_face: true,
                 },
             ),
             *n.normalize()
@@ -326,6 +346,7 @@ mod tests {
                 object: &s,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
         );
         assert_eq!(Vector3D::new(0.0, 0.70711, -0.70711), n);
@@ -350,6 +371,7 @@ mod tests {
                 object: &s,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
         );
         assert_eq!(Vector3D::new(0.0, 0.97014, -0.24254), n);
@@ -360,11 +382,7 @@ mod tests {
         let s = Sphere::new();
         let m = Material::new();
 
-        assert_eq!(m.color, s.material().color);
-        assert_eq!(m.ambient, s.material().ambient);
-        assert_eq!(m.diffuse, s.material().diffuse);
-        assert_eq!(m.specular, s.material().specular);
-        assert_eq!(m.shininess, s.material().shininess);
+        assert_eq!(m, *s.material().unwrap());
     }
 
     #[test]
@@ -373,7 +391,56 @@ mod tests {
         let mut m = Material::new();
         m.ambient = 1.0;
 
-        *s.material_mut() = m;
-        assert_eq!(1.0, s.material().ambient);
+        *s.material_mut().unwrap() = m;
+        assert_eq!(1.0, s.material().unwrap().ambient);
+    }
+
+    #[test]
+    fn a_sphere_has_a_bounding_box() {
+        let s = Sphere::new();
+        let b = s.bounds();
+
+        assert_eq!(Point3D::new(-1.0, -1.0, -1.0), *b.min());
+        assert_eq!(Point3D::new(1.0, 1.0, 1.0), *b.max());
+    }
+
+    #[test]
+    fn the_uv_of_points_on_a_sphere() {
+        let s = Sphere::new();
+        let sqrt2_2 = (2.0 as FLOAT).sqrt() / 2.0;
+        let cases = [
+            (Point3D::new(1.0, 0.0, 0.0), 0.25, 0.5),
+            (Point3D::new(0.0, 1.0, 0.0), 0.5, 1.0),
+            (Point3D::new(sqrt2_2, sqrt2_2, 0.0), 0.25, 0.75),
+        ];
+
+        for (p, u, v) in cases.iter() {
+            let (actual_u, actual_v) = s.uv_at(p);
+            assert!(approx_eq(*u, actual_u));
+            assert!(approx_eq(*v, actual_v));
+        }
+    }
+
+    #[test]
+    fn the_uv_of_a_point_on_a_transformed_sphere() {
+        let mut s = Node::new(Box::new(Sphere::new()));
+        s.set_transform(Transform::translation(0.0, 0.0, 5.0));
+
+        let (u, v) = s.uv_at(&Point3D::new(1.0, 0.0, 5.0));
+        assert!(approx_eq(0.25, u));
+        assert!(approx_eq(0.5, v));
+    }
+
+    #[test]
+    fn the_uv_of_a_point_on_a_moving_sphere() {
+        let mut s = Node::new(Box::new(Sphere::new()));
+        *s.motion_mut() = Some((
+            Transform::identity(),
+            Transform::translation(0.0, 0.0, 5.0),
+        ));
+
+        let (u, v) = s.uv_at_time(&Point3D::new(1.0, 0.0, 5.0), 1.0);
+        assert!(approx_eq(0.25, u));
+        assert!(approx_eq(0.5, v));
     }
 }
@@ -0,0 +1,94 @@
+use super::{canvas::Canvas, FLOAT};
+
+/// 深度バッファを用いて画面空間で簡易的な環境光遮蔽を行う。
+/// 周囲に自身より手前にある pixel が多いほど暗くする。
+/// 深度が無限大(何にも当たらなかった)の pixel は変更しない。
+///
+/// # Argumets
+/// * `color` - レンダリング結果
+/// * `depth` - color と同じサイズの深度バッファ(行優先)
+/// * `radius` - 周囲とみなす pixel の範囲
+/// * `strength` - 暗くする強さ。0.0 で変化なし、1.0 で完全に遮蔽された
+///   pixel が黒になる。
+pub fn ssao(
+    color: &Canvas,
+    depth: &[FLOAT],
+    radius: usize,
+    strength: FLOAT,
+) -> Canvas {
+    let width = color.width();
+    let height = color.height();
+    assert_eq!(width * height, depth.len());
+
+    let mut result = Canvas::new(width, height);
+    *result.gamma_mut() = color.gamma();
+    for y in 0..height {
+        for x in 0..width {
+            let c = color.color_at(x, y);
+            let d = depth[width * y + x];
+            if !d.is_finite() {
+                *result.color_at_mut(x, y) = *c;
+                continue;
+            }
+
+            let mut samples = 0;
+            let mut occlusion = 0.0;
+            let y_range =
+                y.saturating_sub(radius)..=(y + radius).min(height - 1);
+            for ny in y_range {
+                let x_range =
+                    x.saturating_sub(radius)..=(x + radius).min(width - 1);
+                for nx in x_range {
+                    if nx == x && ny == y {
+                        continue;
+                    }
+                    samples += 1;
+                    // 深度差が大きいほど強く遮蔽されているとみなす
+                    let nd = depth[width * ny + nx];
+                    if nd < d {
+                        occlusion += ((d - nd) / d).min(1.0);
+                    }
+                }
+            }
+
+            let ao = if samples == 0 {
+                0.0
+            } else {
+                occlusion / samples as FLOAT
+            };
+            *result.color_at_mut(x, y) = c * (1.0 - strength * ao);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::color::Color, *};
+
+    #[test]
+    fn a_pixel_next_to_a_depth_discontinuity_is_darkened() {
+        let width = 10;
+        let height = 5;
+        let mut color = Canvas::new(width, height);
+        let mut depth = vec![0.0; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                *color.color_at_mut(x, y) = Color::WHITE;
+                // 右半分が手前にある
+                depth[width * y + x] = if x < 5 { 10.0 } else { 5.0 };
+            }
+        }
+
+        let result = ssao(&color, &depth, 2, 1.0);
+
+        let flat = result.color_at(0, 2);
+        let edge = result.color_at(4, 2);
+        assert_eq!(Color::WHITE, *flat);
+        assert!(edge.red < flat.red);
+        assert!(edge.green < flat.green);
+        assert!(edge.blue < flat.blue);
+        // 手前側は暗くならない
+        assert_eq!(Color::WHITE, *result.color_at(5, 2));
+    }
+}
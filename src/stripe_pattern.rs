@@ -1,5 +1,6 @@
 use super::{
     color::Color, pattern::Pattern, point3d::Point3D, transform::Transform,
+    FLOAT,
 };
 
 /// x 軸方向に変化する縞模様のパターン
@@ -7,6 +8,8 @@ use super::{
 pub struct StripePattern {
     a: Color,
     b: Color,
+    /// x 軸方向の繰り返しの周期
+    period: FLOAT,
     /// Pattern -> Shape Transform
     transform: Transform,
 }
@@ -16,9 +19,19 @@ impl StripePattern {
         StripePattern {
             a,
             b,
+            period: 1.0,
             transform: Transform::identity(),
         }
     }
+
+    /// 繰り返しの周期を period に設定する
+    ///
+    /// # Argumets
+    /// * `period` - 繰り返しの周期
+    pub fn with_period(mut self, period: FLOAT) -> Self {
+        self.period = period;
+        self
+    }
 }
 
 impl Pattern for StripePattern {
@@ -31,7 +44,7 @@ impl Pattern for StripePattern {
     }
 
     fn pattern_at(&self, p: &Point3D) -> Color {
-        let x = p.x.floor() as i32;
+        let x = (p.x / self.period).floor() as i32;
         if x % 2 == 0 {
             self.a
         } else {
@@ -44,7 +57,8 @@ impl Pattern for StripePattern {
 mod tests {
     use super::{
         super::{
-            node::Node, shape::Shape, sphere::Sphere, transform::Transform,
+            group::Group, node::Node, shape::Shape, sphere::Sphere,
+            transform::Transform, FLOAT,
         },
         *,
     };
@@ -133,6 +147,22 @@ mod tests {
         assert_eq!(Color::WHITE, c);
     }
 
+    #[test]
+    fn stripes_on_an_object_inside_a_transformed_group() {
+        let mut group = Node::new(Box::new(Group::new()));
+        group.set_transform(Transform::rotation_y(
+            std::f64::consts::FRAC_PI_2 as FLOAT,
+        ));
+        group.add_child(Node::new(Box::new(Sphere::new())));
+        let pattern = StripePattern::new(Color::WHITE, Color::BLACK);
+
+        // World の (0, 0, 0.5) は object 座標系の (-0.5, 0, 0)
+        let c = pattern
+            .pattern_at_shape(group.child_at(0), &Point3D::new(0.0, 0.0, 0.5));
+
+        assert_eq!(Color::BLACK, c);
+    }
+
     #[test]
     fn stripes_with_a_pattern_transformation() {
         let object = Node::new(Box::new(Sphere::new()));
@@ -153,4 +183,40 @@ mod tests {
 
         assert_eq!(Color::WHITE, c);
     }
+
+    #[test]
+    fn stripes_with_a_longer_period() {
+        let pattern =
+            StripePattern::new(Color::WHITE, Color::BLACK).with_period(2.0);
+
+        for x in [0.0, 0.5, 1.0, 1.5, 1.99].iter() {
+            assert_eq!(
+                Color::WHITE,
+                pattern.pattern_at(&Point3D::new(*x, 0.0, 0.0))
+            );
+        }
+        assert_eq!(
+            Color::BLACK,
+            pattern.pattern_at(&Point3D::new(2.0, 0.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn stripes_with_a_shorter_period() {
+        let pattern =
+            StripePattern::new(Color::WHITE, Color::BLACK).with_period(0.5);
+
+        assert_eq!(
+            Color::WHITE,
+            pattern.pattern_at(&Point3D::new(0.25, 0.0, 0.0))
+        );
+        assert_eq!(
+            Color::BLACK,
+            pattern.pattern_at(&Point3D::new(0.75, 0.0, 0.0))
+        );
+        assert_eq!(
+            Color::WHITE,
+            pattern.pattern_at(&Point3D::new(1.25, 0.0, 0.0))
+        );
+    }
 }
@@ -0,0 +1,260 @@
+use super::{
+    canvas::Canvas, color::Color, pattern::Pattern, point3d::Point3D,
+    transform::Transform, uv_mapping::UvMapping, FLOAT,
+};
+
+/// (u, v) が [0, 1] の範囲外となった場合の扱い
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WrapMode {
+    /// 画像を繰り返す
+    Repeat,
+    /// 画像の端の色を引き延ばす
+    Clamp,
+    /// 画像を反転しながら繰り返す
+    Mirror,
+}
+
+impl WrapMode {
+    /// 座標 x を [0, 1] の範囲に収める
+    ///
+    /// # Argumets
+    /// * `x` - u または v
+    fn wrap(&self, x: FLOAT) -> FLOAT {
+        if (0.0..=1.0).contains(&x) {
+            // 範囲内の場合は端の 1.0 も含めてそのまま使う
+            return x;
+        }
+
+        match self {
+            WrapMode::Repeat => x.rem_euclid(1.0),
+            WrapMode::Clamp => x.clamp(0.0, 1.0),
+            WrapMode::Mirror => {
+                let x = x.rem_euclid(2.0);
+                if 1.0 < x {
+                    2.0 - x
+                } else {
+                    x
+                }
+            }
+        }
+    }
+}
+
+/// 画像の pixel の間を参照した場合の色の求め方
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    /// 最も近い pixel の色を使う
+    Nearest,
+    /// 周囲 4 pixel の色を線形補間する
+    Bilinear,
+}
+
+/// 画像を (u, v) 座標に従って貼り付けるパターン
+#[derive(Debug)]
+pub struct TexturePattern {
+    /// 貼り付ける画像
+    canvas: Canvas,
+    /// 点を (u, v) に対応付ける方法
+    mapping: UvMapping,
+    /// (u, v) が範囲外となった場合の扱い
+    wrap: WrapMode,
+    /// pixel の間を参照した場合の色の求め方
+    filter: Filter,
+    /// Pattern -> Shape Transform
+    transform: Transform,
+}
+
+impl TexturePattern {
+    /// 新規に TexturePattern を作成する
+    ///
+    /// # Argumets
+    /// * `canvas` - 貼り付ける画像
+    /// * `mapping` - 点を (u, v) に対応付ける方法
+    pub fn new(canvas: Canvas, mapping: UvMapping) -> Self {
+        TexturePattern {
+            canvas,
+            mapping,
+            wrap: WrapMode::Repeat,
+            filter: Filter::Nearest,
+            transform: Transform::identity(),
+        }
+    }
+
+    /// (u, v) が範囲外となった場合の扱いを wrap に設定する
+    ///
+    /// # Argumets
+    /// * `wrap` - 範囲外となった場合の扱い
+    pub fn with_wrap(mut self, wrap: WrapMode) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// pixel の間を参照した場合の色の求め方を filter に設定する
+    ///
+    /// # Argumets
+    /// * `filter` - pixel の間を参照した場合の色の求め方
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// (u, v) における画像の色を返す。
+    /// v は画像の下端が 0、上端が 1 となる。
+    /// 範囲外の (u, v) は設定された WrapMode に従って範囲内に収める。
+    ///
+    /// # Argumets
+    /// * `u` - 水平方向の位置
+    /// * `v` - 垂直方向の位置
+    pub fn uv_pattern_at(&self, u: FLOAT, v: FLOAT) -> Color {
+        let u = self.wrap.wrap(u);
+        let v = self.wrap.wrap(v);
+        match self.filter {
+            Filter::Nearest => uv_lookup(&self.canvas, u, v),
+            Filter::Bilinear => uv_lookup_bilinear(&self.canvas, u, v),
+        }
+    }
+}
+
+/// (u, v) に最も近い pixel の色を返す。
+/// v は画像の下端が 0、上端が 1 となる。
+///
+/// # Argumets
+/// * `canvas` - 参照する画像
+/// * `u` - 水平方向の位置
+/// * `v` - 垂直方向の位置
+pub(crate) fn uv_lookup(canvas: &Canvas, u: FLOAT, v: FLOAT) -> Color {
+    // 画像は左上が原点なので v を反転する
+    let v = 1.0 - v;
+    let x = (u * (canvas.width() - 1) as FLOAT).round() as usize;
+    let y = (v * (canvas.height() - 1) as FLOAT).round() as usize;
+
+    *canvas.color_at(x, y)
+}
+
+/// (u, v) を囲む 4 pixel の色を線形補間して返す。
+/// v は画像の下端が 0、上端が 1 となる。
+///
+/// # Argumets
+/// * `canvas` - 参照する画像
+/// * `u` - 水平方向の位置
+/// * `v` - 垂直方向の位置
+fn uv_lookup_bilinear(canvas: &Canvas, u: FLOAT, v: FLOAT) -> Color {
+    // 画像は左上が原点なので v を反転する
+    let v = 1.0 - v;
+    let x = u * (canvas.width() - 1) as FLOAT;
+    let y = v * (canvas.height() - 1) as FLOAT;
+    let x0 = x.floor() as usize;
+    let y0 = y.floor() as usize;
+    let x1 = (x0 + 1).min(canvas.width() - 1);
+    let y1 = (y0 + 1).min(canvas.height() - 1);
+    let fx = x - x0 as FLOAT;
+    let fy = y - y0 as FLOAT;
+
+    let top = &(canvas.color_at(x0, y0) * (1.0 - fx))
+        + &(canvas.color_at(x1, y0) * fx);
+    let bottom = &(canvas.color_at(x0, y1) * (1.0 - fx))
+        + &(canvas.color_at(x1, y1) * fx);
+    &(&top * (1.0 - fy)) + &(&bottom * fy)
+}
+
+impl Pattern for TexturePattern {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn transform_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+
+    fn pattern_at(&self, p: &Point3D) -> Color {
+        let (u, v) = self.mapping.uv_at(p);
+        self.uv_pattern_at(u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_canvas() -> Canvas {
+        // 左上から時計回りに 赤, 緑, 青, 白
+        let mut c = Canvas::new(2, 2);
+        *c.color_at_mut(0, 0) = Color::new(1.0, 0.0, 0.0);
+        *c.color_at_mut(1, 0) = Color::new(0.0, 1.0, 0.0);
+        *c.color_at_mut(1, 1) = Color::new(0.0, 0.0, 1.0);
+        *c.color_at_mut(0, 1) = Color::WHITE;
+        c
+    }
+
+    #[test]
+    fn looking_up_a_texture_by_uv() {
+        let pattern = TexturePattern::new(test_canvas(), UvMapping::Planar);
+
+        assert_eq!(Color::WHITE, pattern.uv_pattern_at(0.0, 0.0));
+        assert_eq!(Color::new(1.0, 0.0, 0.0), pattern.uv_pattern_at(0.0, 1.0));
+        assert_eq!(Color::new(0.0, 1.0, 0.0), pattern.uv_pattern_at(1.0, 1.0));
+        assert_eq!(Color::new(0.0, 0.0, 1.0), pattern.uv_pattern_at(0.9, 0.1));
+    }
+
+    #[test]
+    fn a_texture_pattern_maps_a_point_before_the_lookup() {
+        let pattern = TexturePattern::new(test_canvas(), UvMapping::Spherical);
+
+        // 北極付近は上端、-z 方向は u = 0.25 なので左端
+        assert_eq!(
+            Color::new(1.0, 0.0, 0.0),
+            pattern.pattern_at(&Point3D::new(0.0, 0.9, -0.1))
+        );
+        // 南極付近は下端、-x 方向は u = 0 なので左端
+        assert_eq!(
+            Color::WHITE,
+            pattern.pattern_at(&Point3D::new(-0.1, -0.9, 0.0))
+        );
+        // +x 方向は u = 0.5 なので右端
+        assert_eq!(
+            Color::new(0.0, 0.0, 1.0),
+            pattern.pattern_at(&Point3D::new(0.1, -0.9, 0.0))
+        );
+    }
+
+    #[test]
+    fn wrapping_uv_outside_of_the_texture() {
+        // 左から 赤, 緑, 青, 白
+        let mut c = Canvas::new(4, 1);
+        *c.color_at_mut(0, 0) = Color::new(1.0, 0.0, 0.0);
+        *c.color_at_mut(1, 0) = Color::new(0.0, 1.0, 0.0);
+        *c.color_at_mut(2, 0) = Color::new(0.0, 0.0, 1.0);
+        *c.color_at_mut(3, 0) = Color::WHITE;
+        let pattern = TexturePattern::new(c, UvMapping::Planar);
+        assert_eq!(Color::WHITE, pattern.uv_pattern_at(1.0, 0.5));
+
+        let repeat = pattern.with_wrap(WrapMode::Repeat);
+        assert_eq!(Color::new(0.0, 1.0, 0.0), repeat.uv_pattern_at(1.4, 0.5));
+        assert_eq!(Color::WHITE, repeat.uv_pattern_at(-0.1, 0.5));
+
+        let clamp = repeat.with_wrap(WrapMode::Clamp);
+        assert_eq!(Color::WHITE, clamp.uv_pattern_at(1.4, 0.5));
+        assert_eq!(Color::new(1.0, 0.0, 0.0), clamp.uv_pattern_at(-0.1, 0.5));
+
+        let mirror = clamp.with_wrap(WrapMode::Mirror);
+        assert_eq!(Color::new(0.0, 0.0, 1.0), mirror.uv_pattern_at(1.4, 0.5));
+        assert_eq!(Color::new(1.0, 0.0, 0.0), mirror.uv_pattern_at(-0.1, 0.5));
+    }
+
+    #[test]
+    fn bilinear_filtering_blends_neighboring_pixels() {
+        // 2x2 の市松模様
+        let mut c = Canvas::new(2, 2);
+        *c.color_at_mut(0, 0) = Color::WHITE;
+        *c.color_at_mut(1, 1) = Color::WHITE;
+        let nearest = TexturePattern::new(c, UvMapping::Planar);
+
+        // 上端の 2 pixel の中間
+        assert_eq!(Color::BLACK, nearest.uv_pattern_at(0.5, 1.0));
+
+        let bilinear = nearest.with_filter(Filter::Bilinear);
+        assert_eq!(Color::new(0.5, 0.5, 0.5), bilinear.uv_pattern_at(0.5, 1.0));
+        assert_eq!(Color::WHITE, bilinear.uv_pattern_at(0.0, 1.0));
+        assert_eq!(Color::new(0.5, 0.5, 0.5), bilinear.uv_pattern_at(0.5, 0.5));
+    }
+}
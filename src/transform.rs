@@ -4,7 +4,7 @@ use super::{
 use std::{cmp::PartialEq, ops::Mul};
 
 /// 座標変換を表す。
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Transform {
     mat: Matrix4x4,
     inv: Matrix4x4,
@@ -154,6 +154,41 @@ impl Transform {
         Transform { mat, inv }
     }
 
+    /// 任意の軸まわりの回転を作成する
+    ///
+    /// # Argumets
+    /// * `axis` - 回転軸。正規化されていなくてもよい。
+    /// * `a` - 回転角(rad)
+    pub fn rotation_axis(axis: &Vector3D, a: FLOAT) -> Self {
+        let mut k = axis.clone();
+        k.normalize();
+        let c = a.cos();
+        let s = a.sin();
+        let t = 1.0 - c;
+
+        // Rodrigues の回転公式
+        let mat = Matrix4x4::new([
+            c + k.x * k.x * t,
+            k.x * k.y * t - k.z * s,
+            k.x * k.z * t + k.y * s,
+            0.0,
+            k.y * k.x * t + k.z * s,
+            c + k.y * k.y * t,
+            k.y * k.z * t - k.x * s,
+            0.0,
+            k.z * k.x * t - k.y * s,
+            k.z * k.y * t + k.x * s,
+            c + k.z * k.z * t,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+        ]);
+        let inv = mat.transpose();
+        Transform { mat, inv }
+    }
+
     /// 剪断用の変換を作成する
     ///
     /// # Argumets
@@ -201,6 +236,95 @@ impl Transform {
         Transform { mat, inv }
     }
 
+    /// オブジェクトを origin に配置し、local 座標系の +y 軸を target へ
+    /// 向ける変換を作成する。up は target - origin と平行であってはならない。
+    ///
+    /// # Argumets
+    /// * `origin` - 配置する位置
+    /// * `target` - +y 軸を向ける点
+    /// * `up` - 向きを決めるための補助ベクトル
+    pub fn point_at(origin: &Point3D, target: &Point3D, up: &Vector3D) -> Self {
+        let mut y_axis = target - origin;
+        y_axis.normalize();
+        let mut x_axis = y_axis.cross(up);
+        x_axis.normalize();
+        let z_axis = x_axis.cross(&y_axis);
+
+        let mat = Matrix4x4::new([
+            x_axis.x, y_axis.x, z_axis.x, origin.x, x_axis.y, y_axis.y,
+            z_axis.y, origin.y, x_axis.z, y_axis.z, z_axis.z, origin.z, 0.0,
+            0.0, 0.0, 1.0,
+        ]);
+        let inv = mat.inverse();
+
+        Transform { mat, inv }
+    }
+
+    /// self と other の変換行列を要素ごとに線形補間した変換を作成する。
+    /// 平行移動や拡大/縮小は正しく補間されるが、回転は剛体変換にならない。
+    ///
+    /// # Argumets
+    /// * `other` - t = 1 における変換
+    /// * `t` - 補間パラメータ
+    pub fn lerp(&self, other: &Transform, t: FLOAT) -> Self {
+        let mat = self.mat.lerp(&other.mat, t);
+        let inv = mat.inverse();
+
+        Transform { mat, inv }
+    }
+
+    /// self を適用した後に t を適用する変換を作成する
+    ///
+    /// # Argumets
+    /// * `t` - 後から適用する変換
+    pub fn then(self, t: &Transform) -> Self {
+        t * &self
+    }
+
+    /// self を適用した後に平行移動する変換を作成する
+    ///
+    /// # Argumets
+    /// * `x` - x 方向の移動量
+    /// * `y` - y 方向の移動量
+    /// * `z` - z 方向の移動量
+    pub fn then_translate(self, x: FLOAT, y: FLOAT, z: FLOAT) -> Self {
+        self.then(&Transform::translation(x, y, z))
+    }
+
+    /// self を適用した後に拡大/縮小する変換を作成する
+    ///
+    /// # Argumets
+    /// * `x` - x 方向のスケール
+    /// * `y` - y 方向のスケール
+    /// * `z` - z 方向のスケール
+    pub fn then_scale(self, x: FLOAT, y: FLOAT, z: FLOAT) -> Self {
+        self.then(&Transform::scaling(x, y, z))
+    }
+
+    /// self を適用した後に x 軸まわりに回転する変換を作成する
+    ///
+    /// # Argumets
+    /// * `a` - 回転角(rad)
+    pub fn then_rotate_x(self, a: FLOAT) -> Self {
+        self.then(&Transform::rotation_x(a))
+    }
+
+    /// self を適用した後に y 軸まわりに回転する変換を作成する
+    ///
+    /// # Argumets
+    /// * `a` - 回転角(rad)
+    pub fn then_rotate_y(self, a: FLOAT) -> Self {
+        self.then(&Transform::rotation_y(a))
+    }
+
+    /// self を適用した後に z 軸まわりに回転する変換を作成する
+    ///
+    /// # Argumets
+    /// * `a` - 回転角(rad)
+    pub fn then_rotate_z(self, a: FLOAT) -> Self {
+        self.then(&Transform::rotation_z(a))
+    }
+
     /// 逆変換の行列を取得する
     pub fn inv(&self) -> &Matrix4x4 {
         &self.inv
@@ -409,6 +533,47 @@ mod tests {
         assert_eq!(Point3D::new(-1.0, 0.0, 0.0), &full_quarter * &p);
     }
 
+    #[test]
+    fn rotating_a_point_around_an_arbitrary_axis() {
+        let p = Point3D::new(1.0, 0.0, 0.0);
+        let t = Transform::rotation_axis(
+            &Vector3D::new(0.0, 0.0, 2.0),
+            std::f32::consts::FRAC_PI_2 as FLOAT,
+        );
+
+        assert_eq!(Point3D::new(0.0, 1.0, 0.0), &t * &p);
+        assert_eq!(p, t.inv() * &Point3D::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn rotation_about_a_coordinate_axis_matches_the_dedicated_rotation() {
+        let a = 0.7;
+        let x = Vector3D::new(1.0, 0.0, 0.0);
+        let y = Vector3D::new(0.0, 1.0, 0.0);
+        let z = Vector3D::new(0.0, 0.0, 1.0);
+
+        assert_eq!(Transform::rotation_x(a), Transform::rotation_axis(&x, a));
+        assert_eq!(Transform::rotation_y(a), Transform::rotation_axis(&y, a));
+        assert_eq!(Transform::rotation_z(a), Transform::rotation_axis(&z, a));
+    }
+
+    #[test]
+    fn chained_transformations_are_applied_in_call_order() {
+        let p = Point3D::new(1.0, 0.0, 1.0);
+        let a = std::f32::consts::FRAC_PI_2 as FLOAT;
+        let manual = &Transform::translation(10.0, 5.0, 7.0)
+            * &(&Transform::scaling(5.0, 5.0, 5.0) * &Transform::rotation_y(a));
+
+        let chained = Transform::identity()
+            .then_rotate_y(a)
+            .then_scale(5.0, 5.0, 5.0)
+            .then_translate(10.0, 5.0, 7.0);
+
+        assert_eq!(manual, chained);
+        assert_eq!(&manual * &p, &chained * &p);
+        assert_eq!(Point3D::new(15.0, 5.0, 2.0), &chained * &p);
+    }
+
     #[test]
     fn a_shearing_information_moves_x_in_propotion_to_y() {
         let t = Transform::shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
@@ -566,4 +731,24 @@ mod tests {
         let inv = mat.inverse();
         assert_eq!(Transform { mat, inv }, t);
     }
+
+    #[test]
+    fn point_at_aims_the_local_y_axis_at_the_target() {
+        let origin = Point3D::new(1.0, 2.0, 3.0);
+        let target = Point3D::new(4.0, 6.0, 3.0);
+        let up = Vector3D::new(0.0, 0.0, 1.0);
+
+        let t = Transform::point_at(&origin, &target, &up);
+
+        assert_eq!(origin, &t * &Point3D::new(0.0, 0.0, 0.0));
+        // 単位長さだけ target 方向に進んだ点
+        assert_eq!(
+            Point3D::new(1.6, 2.8, 3.0),
+            &t * &Point3D::new(0.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            Point3D::new(4.0, 6.0, 3.0),
+            &t * &Point3D::new(0.0, 5.0, 0.0)
+        );
+    }
 }
@@ -1,6 +1,7 @@
 use crate::{
-    intersection::Intersection, material::Material, node::Node,
-    point3d::Point3D, ray::Ray, shape::Shape, vector3d::Vector3D, EPSILON,
+    bounding_box::BoundingBox, intersection::Intersection, material::Material,
+    node::Node, point3d::Point3D, ray::Ray, shape::Shape, vector3d::Vector3D,
+    EPSILON,
 };
 
 #[derive(Debug)]
@@ -45,15 +46,25 @@ impl Triangle {
     pub fn p3(&self) -> &Point3D {
         &self.p3
     }
+
+    /// 法線の向きを反転する。
+    /// 頂点の並び順が逆になっているモデルを読み込んだ場合に用いる。
+    pub fn flip_normal(&mut self) {
+        self.normal = -&self.normal;
+    }
 }
 
 impl Shape for Triangle {
-    fn material(&self) -> &Material {
-        &self.material
+    fn is_triangle(&self) -> bool {
+        true
+    }
+
+    fn material(&self) -> Option<&Material> {
+        Some(&self.material)
     }
 
-    fn material_mut(&mut self) -> &mut Material {
-        &mut self.material
+    fn material_mut(&mut self) -> Option<&mut Material> {
+        Some(&mut self.material)
     }
 
     fn local_intersect<'a>(
@@ -84,20 +95,30 @@ impl Shape for Triangle {
         vec![Intersection {
             t: t,
             object: n,
-            u: 0.0,
-            v: 0.0,
+            u,
+            v,
+            // flip_normal() で反転した場合にも追従するよう法線で判定する
+            front_face: r.direction().dot(&self.normal) < 0.0,
         }]
     }
 
     fn local_normal_at(&self, _p: &Point3D, _i: &Intersection) -> Vector3D {
         self.normal.clone()
     }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut b = BoundingBox::empty();
+        b.add_point(&self.p1);
+        b.add_point(&self.p2);
+        b.add_point(&self.p3);
+        b
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::vector3d::Vector3D;
+    use crate::{approx_eq, vector3d::Vector3D};
 
     #[test]
     fn constructing_a_triangle() {
@@ -130,6 +151,7 @@ mod tests {
             ))),
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
 
         let n1 = t.local_normal_at(&Point3D::new(0.0, 0.5, 0.0), &i);
@@ -250,5 +272,70 @@ mod tests {
 
         assert_eq!(1, xs.len());
         assert_eq!(2.0, xs[0].t);
+        assert!(approx_eq(0.25, xs[0].u));
+        assert!(approx_eq(0.25, xs[0].v));
+    }
+
+    #[test]
+    fn a_triangle_reports_which_face_was_hit() {
+        let t = Triangle::new(
+            Point3D::new(0.0, 1.0, 0.0),
+            Point3D::new(-1.0, 0.0, 0.0),
+            Point3D::new(1.0, 0.0, 0.0),
+        );
+        let dummy_node = Node::new(Box::new(Triangle::new(
+            Point3D::new(0.0, 1.0, 0.0),
+            Point3D::new(-1.0, 0.0, 0.0),
+            Point3D::new(1.0, 0.0, 0.0),
+        )));
+
+        // 法線 (0, 0, 1) の側から
+        let r = Ray::new(
+            Point3D::new(0.0, 0.5, 2.0),
+            Vector3D::new(0.0, 0.0, -1.0),
+        );
+        let xs = t.local_intersect(&r, &dummy_node);
+        assert_eq!(1, xs.len());
+        assert!(xs[0].front_face);
+
+        // 裏側から
+        let r = Ray::new(
+            Point3D::new(0.0, 0.5, -2.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+        let xs = t.local_intersect(&r, &dummy_node);
+        assert_eq!(1, xs.len());
+        assert!(!xs[0].front_face);
+    }
+
+    #[test]
+    fn flipping_the_normal_of_a_triangle() {
+        let mut t = Triangle::new(
+            Point3D::new(0.0, 1.0, 0.0),
+            Point3D::new(-1.0, 0.0, 0.0),
+            Point3D::new(1.0, 0.0, 0.0),
+        );
+        let dummy_node = Node::new(Box::new(Triangle::new(
+            Point3D::new(0.0, 1.0, 0.0),
+            Point3D::new(-1.0, 0.0, 0.0),
+            Point3D::new(1.0, 0.0, 0.0),
+        )));
+        let r = Ray::new(
+            Point3D::new(0.0, 0.5, -2.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+        let before = t.local_intersect(&r, &dummy_node)[0].t;
+
+        t.flip_normal();
+
+        let xs = t.local_intersect(&r, &dummy_node);
+        assert_eq!(1, xs.len());
+        assert!(approx_eq(before, xs[0].t));
+        // 反転後は -z 側が表になる
+        assert!(xs[0].front_face);
+        assert_eq!(
+            Vector3D::new(0.0, 0.0, -1.0),
+            t.local_normal_at(&Point3D::new(0.0, 0.5, 0.0), &xs[0])
+        );
     }
 }
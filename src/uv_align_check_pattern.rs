@@ -0,0 +1,122 @@
+use super::{
+    color::Color, pattern::Pattern, point3d::Point3D, transform::Transform,
+    uv_mapping::UvMapping, FLOAT,
+};
+
+/// (u, v) の向きを確認するためのパターン。
+/// 四隅の 20% の範囲をそれぞれ別の色で、それ以外を main で塗る。
+#[derive(Debug)]
+pub struct UvAlignCheckPattern {
+    main: Color,
+    /// 左上 (u = 0, v = 1) の色
+    ul: Color,
+    /// 右上 (u = 1, v = 1) の色
+    ur: Color,
+    /// 左下 (u = 0, v = 0) の色
+    bl: Color,
+    /// 右下 (u = 1, v = 0) の色
+    br: Color,
+    /// 点を (u, v) に対応付ける方法
+    mapping: UvMapping,
+    /// Pattern -> Shape Transform
+    transform: Transform,
+}
+
+impl UvAlignCheckPattern {
+    /// 新規に UvAlignCheckPattern を作成する
+    ///
+    /// # Argumets
+    /// * `main` - 四隅以外の色
+    /// * `ul` - 左上の色
+    /// * `ur` - 右上の色
+    /// * `bl` - 左下の色
+    /// * `br` - 右下の色
+    /// * `mapping` - 点を (u, v) に対応付ける方法
+    pub fn new(
+        main: Color,
+        ul: Color,
+        ur: Color,
+        bl: Color,
+        br: Color,
+        mapping: UvMapping,
+    ) -> Self {
+        UvAlignCheckPattern {
+            main,
+            ul,
+            ur,
+            bl,
+            br,
+            mapping,
+            transform: Transform::identity(),
+        }
+    }
+
+    /// (u, v) における色を返す
+    ///
+    /// # Argumets
+    /// * `u` - 水平方向の位置
+    /// * `v` - 垂直方向の位置
+    pub fn uv_pattern_at(&self, u: FLOAT, v: FLOAT) -> Color {
+        if v > 0.8 {
+            if u < 0.2 {
+                return self.ul;
+            }
+            if u > 0.8 {
+                return self.ur;
+            }
+        } else if v < 0.2 {
+            if u < 0.2 {
+                return self.bl;
+            }
+            if u > 0.8 {
+                return self.br;
+            }
+        }
+
+        self.main
+    }
+}
+
+impl Pattern for UvAlignCheckPattern {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn transform_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+
+    fn pattern_at(&self, p: &Point3D) -> Color {
+        let (u, v) = self.mapping.uv_at(p);
+        self.uv_pattern_at(u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_of_the_align_check_pattern() {
+        let main = Color::WHITE;
+        let ul = Color::new(1.0, 0.0, 0.0);
+        let ur = Color::new(1.0, 1.0, 0.0);
+        let bl = Color::new(0.0, 1.0, 0.0);
+        let br = Color::new(0.0, 1.0, 1.0);
+        let pattern =
+            UvAlignCheckPattern::new(main, ul, ur, bl, br, UvMapping::Planar);
+        let cases = [
+            (0.5, 0.5, main),
+            (0.1, 0.9, ul),
+            (0.9, 0.9, ur),
+            (0.1, 0.1, bl),
+            (0.9, 0.1, br),
+            (0.5, 0.9, main),
+            (0.1, 0.5, main),
+        ];
+
+        for (u, v, c) in cases.iter() {
+            assert_eq!(*c, pattern.uv_pattern_at(*u, *v));
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use super::{
+    color::Color, pattern::Pattern, point3d::Point3D, transform::Transform,
+    uv_mapping::UvMapping, FLOAT,
+};
+
+/// (u, v) 座標上の市松模様。
+/// 3 次元の市松模様と異なり、球面などに貼り付けても継ぎ目ができない。
+#[derive(Debug)]
+pub struct UvCheckersPattern {
+    /// u 方向のマスの数
+    width: FLOAT,
+    /// v 方向のマスの数
+    height: FLOAT,
+    a: Color,
+    b: Color,
+    /// 点を (u, v) に対応付ける方法
+    mapping: UvMapping,
+    /// Pattern -> Shape Transform
+    transform: Transform,
+}
+
+impl UvCheckersPattern {
+    /// 新規に UvCheckersPattern を作成する
+    ///
+    /// # Argumets
+    /// * `width` - u 方向のマスの数
+    /// * `height` - v 方向のマスの数
+    /// * `a` - (0, 0) を含むマスの色
+    /// * `b` - a に隣接するマスの色
+    /// * `mapping` - 点を (u, v) に対応付ける方法
+    pub fn new(
+        width: FLOAT,
+        height: FLOAT,
+        a: Color,
+        b: Color,
+        mapping: UvMapping,
+    ) -> Self {
+        UvCheckersPattern {
+            width,
+            height,
+            a,
+            b,
+            mapping,
+            transform: Transform::identity(),
+        }
+    }
+
+    /// (u, v) における色を返す
+    ///
+    /// # Argumets
+    /// * `u` - 水平方向の位置
+    /// * `v` - 垂直方向の位置
+    pub fn uv_pattern_at(&self, u: FLOAT, v: FLOAT) -> Color {
+        let u2 = (u * self.width).floor();
+        let v2 = (v * self.height).floor();
+
+        if (u2 + v2) as i32 % 2 == 0 {
+            self.a
+        } else {
+            self.b
+        }
+    }
+}
+
+impl Pattern for UvCheckersPattern {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn transform_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+
+    fn pattern_at(&self, p: &Point3D) -> Color {
+        let (u, v) = self.mapping.uv_at(p);
+        self.uv_pattern_at(u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checker_pattern_in_2d() {
+        let pattern = UvCheckersPattern::new(
+            2.0,
+            2.0,
+            Color::BLACK,
+            Color::WHITE,
+            UvMapping::Planar,
+        );
+
+        assert_eq!(Color::BLACK, pattern.uv_pattern_at(0.0, 0.0));
+        assert_eq!(Color::WHITE, pattern.uv_pattern_at(0.5, 0.0));
+        assert_eq!(Color::WHITE, pattern.uv_pattern_at(0.0, 0.5));
+        assert_eq!(Color::BLACK, pattern.uv_pattern_at(0.5, 0.5));
+        assert_eq!(Color::BLACK, pattern.uv_pattern_at(1.0, 1.0));
+    }
+
+    #[test]
+    fn using_a_texture_map_pattern_with_a_spherical_map() {
+        let pattern = UvCheckersPattern::new(
+            16.0,
+            8.0,
+            Color::BLACK,
+            Color::WHITE,
+            UvMapping::Spherical,
+        );
+        let cases = [
+            (Point3D::new(0.4315, 0.4670, 0.7719), Color::WHITE),
+            (Point3D::new(-0.9654, 0.2552, -0.0534), Color::BLACK),
+            (Point3D::new(0.1039, 0.7090, 0.6975), Color::WHITE),
+            (Point3D::new(-0.4986, -0.7856, -0.3663), Color::BLACK),
+            (Point3D::new(-0.0317, -0.9395, 0.3411), Color::BLACK),
+            (Point3D::new(0.4809, -0.7721, 0.4154), Color::BLACK),
+            (Point3D::new(0.0285, -0.9612, -0.2745), Color::BLACK),
+            (Point3D::new(-0.5734, -0.2162, -0.7903), Color::WHITE),
+            (Point3D::new(0.7688, -0.1470, 0.6223), Color::BLACK),
+            (Point3D::new(-0.7652, 0.2175, 0.6060), Color::BLACK),
+        ];
+
+        for (p, c) in cases.iter() {
+            assert_eq!(*c, pattern.pattern_at(p));
+        }
+    }
+
+    #[test]
+    fn using_a_texture_map_pattern_with_a_planar_map() {
+        let pattern = UvCheckersPattern::new(
+            2.0,
+            2.0,
+            Color::BLACK,
+            Color::WHITE,
+            UvMapping::Planar,
+        );
+
+        assert_eq!(
+            Color::BLACK,
+            pattern.pattern_at(&Point3D::new(0.25, 0.0, 0.25))
+        );
+        assert_eq!(
+            Color::WHITE,
+            pattern.pattern_at(&Point3D::new(0.75, 1.0, 0.25))
+        );
+        assert_eq!(
+            Color::BLACK,
+            pattern.pattern_at(&Point3D::new(1.25, -3.0, 1.25))
+        );
+    }
+}
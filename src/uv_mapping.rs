@@ -0,0 +1,294 @@
+use super::{point3d::Point3D, FLOAT};
+
+/// 3 次元の点を 2 次元の (u, v) 座標に対応付ける方法
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UvMapping {
+    /// 原点を中心とする球面への対応付け
+    Spherical,
+    /// xz 平面への対応付け
+    Planar,
+    /// y 軸を中心とする円柱面への対応付け
+    Cylindrical,
+}
+
+impl UvMapping {
+    /// 点 p に対応する (u, v) を返す。u, v は [0, 1) の範囲となる。
+    ///
+    /// # Argumets
+    /// * `p` - pattern 座標系における点
+    pub fn uv_at(&self, p: &Point3D) -> (FLOAT, FLOAT) {
+        match self {
+            UvMapping::Spherical => uv_sphere(p),
+            UvMapping::Planar => uv_plane(p),
+            UvMapping::Cylindrical => uv_cylinder(p),
+        }
+    }
+}
+
+/// 原点を中心とする球面上の点 p に対応する (u, v) を返す。
+/// u は -z 方向を 0.25 として y 軸回りに、v は南極を 0 として北極に向かって
+/// 増加する。-x 方向を継ぎ目として [0, 1) の範囲に折り返す。
+///
+/// # Argumets
+/// * `p` - 球面上の点
+pub fn uv_sphere(p: &Point3D) -> (FLOAT, FLOAT) {
+    let two_pi = 2.0 * std::f64::consts::PI as FLOAT;
+
+    // 方位角 (-π, π]
+    let theta = p.x.atan2(p.z);
+    let radius = (p.x * p.x + p.y * p.y + p.z * p.z).sqrt();
+    // 極角 [0, π]
+    let phi = (p.y / radius).acos();
+
+    let raw_u = theta / two_pi;
+    // 反時計回りに増加するよう反転し、-z 方向が 0.25 となるようずらす
+    let u = (0.75 - raw_u).rem_euclid(1.0);
+    let v = 1.0 - phi / std::f64::consts::PI as FLOAT;
+
+    (u, v)
+}
+
+/// xz 平面上の点 p に対応する (u, v) を返す。1 単位ごとに繰り返す。
+///
+/// # Argumets
+/// * `p` - xz 平面上の点
+pub fn uv_plane(p: &Point3D) -> (FLOAT, FLOAT) {
+    (p.x.rem_euclid(1.0), p.z.rem_euclid(1.0))
+}
+
+/// y 軸を中心とする円柱面上の点 p に対応する (u, v) を返す。
+/// u は -z 方向を 0 として y 軸回りに増加し、v は高さ 1 単位ごとに繰り返す。
+///
+/// # Argumets
+/// * `p` - 円柱面上の点
+pub fn uv_cylinder(p: &Point3D) -> (FLOAT, FLOAT) {
+    let two_pi = 2.0 * std::f64::consts::PI as FLOAT;
+
+    let theta = p.x.atan2(p.z);
+    let u = (theta / two_pi + 0.5).rem_euclid(1.0);
+    let v = p.y.rem_euclid(1.0);
+
+    (u, v)
+}
+
+/// 原点を中心とする立方体の面
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CubeFace {
+    /// -x
+    Left,
+    /// +x
+    Right,
+    /// +z
+    Front,
+    /// -z
+    Back,
+    /// +y
+    Up,
+    /// -y
+    Down,
+}
+
+/// 点 p が立方体のどの面にあるかを返す。
+/// 絶対値が最大となる座標軸の面を選ぶ。
+///
+/// # Argumets
+/// * `p` - 立方体上の点
+pub fn cube_face(p: &Point3D) -> CubeFace {
+    let coord = p.x.abs().max(p.y.abs()).max(p.z.abs());
+
+    if coord == p.x {
+        CubeFace::Right
+    } else if coord == -p.x {
+        CubeFace::Left
+    } else if coord == p.y {
+        CubeFace::Up
+    } else if coord == -p.y {
+        CubeFace::Down
+    } else if coord == p.z {
+        CubeFace::Front
+    } else {
+        CubeFace::Back
+    }
+}
+
+/// 立方体の面 face 上の点 p に対応する面内の (u, v) を返す。
+/// 各面を外側から見たとき、左下が (0, 0)、右上が (1, 1) となる。
+///
+/// # Argumets
+/// * `face` - p がある面
+/// * `p` - 立方体上の点
+pub fn uv_cube_face(face: CubeFace, p: &Point3D) -> (FLOAT, FLOAT) {
+    match face {
+        CubeFace::Front => cube_uv_front(p),
+        CubeFace::Back => cube_uv_back(p),
+        CubeFace::Left => cube_uv_left(p),
+        CubeFace::Right => cube_uv_right(p),
+        CubeFace::Up => cube_uv_up(p),
+        CubeFace::Down => cube_uv_down(p),
+    }
+}
+
+/// 面の左下を (0, 0)、右上を (2, 2) とする座標を [0, 1) の範囲に変換する。
+///
+/// # Argumets
+/// * `u` - 面の横方向の座標
+/// * `v` - 面の縦方向の座標
+fn cube_face_uv(u: FLOAT, v: FLOAT) -> (FLOAT, FLOAT) {
+    (u.rem_euclid(2.0) / 2.0, v.rem_euclid(2.0) / 2.0)
+}
+
+/// 立方体の+z 方向の面 (Front)上の点 p に対応する (u, v) を返す。
+///
+/// # Argumets
+/// * `p` - 面上の点
+pub fn cube_uv_front(p: &Point3D) -> (FLOAT, FLOAT) {
+    cube_face_uv(p.x + 1.0, p.y + 1.0)
+}
+
+/// 立方体の-z 方向の面 (Back)上の点 p に対応する (u, v) を返す。
+///
+/// # Argumets
+/// * `p` - 面上の点
+pub fn cube_uv_back(p: &Point3D) -> (FLOAT, FLOAT) {
+    cube_face_uv(1.0 - p.x, p.y + 1.0)
+}
+
+/// 立方体の-x 方向の面 (Left)上の点 p に対応する (u, v) を返す。
+///
+/// # Argumets
+/// * `p` - 面上の点
+pub fn cube_uv_left(p: &Point3D) -> (FLOAT, FLOAT) {
+    cube_face_uv(p.z + 1.0, p.y + 1.0)
+}
+
+/// 立方体の+x 方向の面 (Right)上の点 p に対応する (u, v) を返す。
+///
+/// # Argumets
+/// * `p` - 面上の点
+pub fn cube_uv_right(p: &Point3D) -> (FLOAT, FLOAT) {
+    cube_face_uv(1.0 - p.z, p.y + 1.0)
+}
+
+/// 立方体の+y 方向の面 (Up)上の点 p に対応する (u, v) を返す。
+///
+/// # Argumets
+/// * `p` - 面上の点
+pub fn cube_uv_up(p: &Point3D) -> (FLOAT, FLOAT) {
+    cube_face_uv(p.x + 1.0, 1.0 - p.z)
+}
+
+/// 立方体の-y 方向の面 (Down)上の点 p に対応する (u, v) を返す。
+///
+/// # Argumets
+/// * `p` - 面上の点
+pub fn cube_uv_down(p: &Point3D) -> (FLOAT, FLOAT) {
+    cube_face_uv(p.x + 1.0, p.z + 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::approx_eq, *};
+
+    #[test]
+    fn using_a_spherical_mapping_on_a_3d_point() {
+        let sqrt2_2 = (2.0 as FLOAT).sqrt() / 2.0;
+        let cases = [
+            (Point3D::new(0.0, 0.0, -1.0), 0.25, 0.5),
+            (Point3D::new(1.0, 0.0, 0.0), 0.5, 0.5),
+            (Point3D::new(0.0, 0.0, 1.0), 0.75, 0.5),
+            (Point3D::new(-1.0, 0.0, 0.0), 0.0, 0.5),
+            (Point3D::new(0.0, 1.0, 0.0), 0.75, 1.0),
+            (Point3D::new(0.0, -1.0, 0.0), 0.75, 0.0),
+            (Point3D::new(sqrt2_2, sqrt2_2, 0.0), 0.5, 0.75),
+        ];
+
+        for (p, u, v) in cases.iter() {
+            let (actual_u, actual_v) = UvMapping::Spherical.uv_at(p);
+            assert!(approx_eq(*u, actual_u));
+            assert!(approx_eq(*v, actual_v));
+        }
+    }
+
+    #[test]
+    fn using_a_planar_mapping_on_a_3d_point() {
+        let cases = [
+            (Point3D::new(0.25, 0.0, 0.5), 0.25, 0.5),
+            (Point3D::new(0.25, 0.0, -0.25), 0.25, 0.75),
+            (Point3D::new(0.25, 0.5, -0.25), 0.25, 0.75),
+            (Point3D::new(1.25, 0.0, 0.5), 0.25, 0.5),
+            (Point3D::new(0.25, 0.0, -1.75), 0.25, 0.25),
+            (Point3D::new(1.0, 0.0, -1.0), 0.0, 0.0),
+            (Point3D::new(0.0, 0.0, 0.0), 0.0, 0.0),
+        ];
+
+        for (p, u, v) in cases.iter() {
+            let (actual_u, actual_v) = UvMapping::Planar.uv_at(p);
+            assert!(approx_eq(*u, actual_u));
+            assert!(approx_eq(*v, actual_v));
+        }
+    }
+
+    #[test]
+    fn using_a_cylindrical_mapping_on_a_3d_point() {
+        let sqrt2_2 = (2.0 as FLOAT).sqrt() / 2.0;
+        let cases = [
+            // -z の継ぎ目では 1 ではなく 0 に折り返す
+            (Point3D::new(0.0, 0.0, -1.0), 0.0, 0.0),
+            (Point3D::new(-1e-6, 0.0, -1.0), 0.0, 0.0),
+            (Point3D::new(-1.0, 0.0, 0.0), 0.25, 0.0),
+            (Point3D::new(0.0, 0.5, 1.0), 0.5, 0.5),
+            (Point3D::new(1.0, 0.0, 0.0), 0.75, 0.0),
+            (Point3D::new(sqrt2_2, 1.25, sqrt2_2), 0.625, 0.25),
+            (Point3D::new(-1.0, -0.25, 0.0), 0.25, 0.75),
+            (Point3D::new(0.0, 3.5, 1.0), 0.5, 0.5),
+        ];
+
+        for (p, u, v) in cases.iter() {
+            let (actual_u, actual_v) = UvMapping::Cylindrical.uv_at(p);
+            assert!(0.0 <= actual_u && actual_u < 1.0);
+            assert!(approx_eq(*u, actual_u));
+            assert!(approx_eq(*v, actual_v));
+        }
+    }
+
+    #[test]
+    fn identifying_the_face_of_a_cube_from_a_point() {
+        let cases = [
+            (Point3D::new(-1.0, 0.5, -0.25), CubeFace::Left),
+            (Point3D::new(1.1, -0.75, 0.8), CubeFace::Right),
+            (Point3D::new(0.1, 0.6, 0.9), CubeFace::Front),
+            (Point3D::new(-0.7, 0.0, -2.0), CubeFace::Back),
+            (Point3D::new(0.5, 1.0, 0.9), CubeFace::Up),
+            (Point3D::new(-0.2, -1.3, 1.1), CubeFace::Down),
+        ];
+
+        for (p, face) in cases.iter() {
+            assert_eq!(*face, cube_face(p));
+        }
+    }
+
+    #[test]
+    fn uv_mapping_the_faces_of_a_cube() {
+        let cases = [
+            (CubeFace::Front, Point3D::new(-0.5, 0.5, 1.0), 0.25, 0.75),
+            (CubeFace::Front, Point3D::new(0.5, -0.5, 1.0), 0.75, 0.25),
+            (CubeFace::Back, Point3D::new(0.5, 0.5, -1.0), 0.25, 0.75),
+            (CubeFace::Back, Point3D::new(-0.5, -0.5, -1.0), 0.75, 0.25),
+            (CubeFace::Left, Point3D::new(-1.0, 0.5, -0.5), 0.25, 0.75),
+            (CubeFace::Left, Point3D::new(-1.0, -0.5, 0.5), 0.75, 0.25),
+            (CubeFace::Right, Point3D::new(1.0, 0.5, 0.5), 0.25, 0.75),
+            (CubeFace::Right, Point3D::new(1.0, -0.5, -0.5), 0.75, 0.25),
+            (CubeFace::Up, Point3D::new(-0.5, 1.0, -0.5), 0.25, 0.75),
+            (CubeFace::Up, Point3D::new(0.5, 1.0, 0.5), 0.75, 0.25),
+            (CubeFace::Down, Point3D::new(-0.5, -1.0, 0.5), 0.25, 0.75),
+            (CubeFace::Down, Point3D::new(0.5, -1.0, -0.5), 0.75, 0.25),
+            (CubeFace::Left, Point3D::new(-1.0, 0.5, -0.25), 0.375, 0.75),
+        ];
+
+        for (face, p, u, v) in cases.iter() {
+            let (actual_u, actual_v) = uv_cube_face(*face, p);
+            assert!(approx_eq(*u, actual_u));
+            assert!(approx_eq(*v, actual_v));
+        }
+    }
+}
@@ -68,6 +68,16 @@ impl Vector3D {
     pub fn reflect(&self, n: &Vector3D) -> Vector3D {
         self - &(2.0 * self.dot(n) * n)
     }
+
+    /// self と other を線形補間する。
+    /// t = 0.0 で self、t = 1.0 で other となる。
+    ///
+    /// # Argumets
+    /// * `other` - 補間先の Vector3D
+    /// * `t` - 補間の割合
+    pub fn lerp(&self, other: &Vector3D, t: FLOAT) -> Vector3D {
+        self + &(&(other - self) * t)
+    }
 }
 
 impl PartialEq for Vector3D {
@@ -333,4 +343,14 @@ mod tests {
 
         assert_eq!(Vector3D::new(1.0, 0.0, 0.0), r);
     }
+
+    #[test]
+    fn linearly_interpolating_two_vectors() {
+        let v1 = Vector3D::new(1.0, 2.0, 3.0);
+        let v2 = Vector3D::new(3.0, -2.0, 4.0);
+
+        assert_eq!(v1, v1.lerp(&v2, 0.0));
+        assert_eq!(v2, v1.lerp(&v2, 1.0));
+        assert_eq!(Vector3D::new(2.0, 0.0, 3.5), v1.lerp(&v2, 0.5));
+    }
 }
@@ -1,13 +1,53 @@
 use super::{
+    checkers_pattern::CheckersPattern,
     color::Color,
+    cube_map_pattern::CubeMapPattern,
     intersection::{hit, Intersection},
     intersection_state::IntersectionState,
     light::Light,
-    node::Node,
+    node::{intersection_tests, Node},
+    pattern::Pattern,
+    plane::Plane,
     point3d::Point3D,
+    random::Random,
     ray::Ray,
+    sphere::Sphere,
+    transform::Transform,
+    vector3d::Vector3D,
+    FLOAT, INFINITY,
 };
 
+/// World に含まれるオブジェクトの統計情報
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SceneStats {
+    /// Group なども含めた全 Node の数
+    pub nodes: usize,
+    /// Material を持つ末端の Shape の数
+    pub leaves: usize,
+    /// 三角形の数
+    pub triangles: usize,
+}
+
+impl SceneStats {
+    /// node とその子孫を数えて加算する
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - 数える対象の Node
+    fn add_node(&mut self, node: &Node) {
+        self.nodes += 1;
+        if node.material().is_some() {
+            self.leaves += 1;
+        }
+        if node.is_triangle() {
+            self.triangles += 1;
+        }
+        for child in node.children() {
+            self.add_node(child);
+        }
+    }
+}
+
 /// レンダリングに用いるライトとオブジェクトを集約する
 #[derive(Debug)]
 pub struct World {
@@ -15,6 +55,23 @@ pub struct World {
     lights: Vec<Light>,
     /// オブジェクト
     nodes: Vec<Box<Node>>,
+    /// Ray がどのオブジェクトにもヒットしなかったときの色
+    background: Color,
+    /// Ray の方向から背景色を決めるパターン。None の場合は background を使う。
+    background_pattern: Option<Box<dyn Pattern>>,
+    /// Ray の方向から背景色を決める環境マップ。
+    /// 設定されている場合は background_pattern より優先する。
+    environment: Option<CubeMapPattern>,
+    /// 透明なオブジェクトで Schlick 近似により反射と屈折の比率を決めるか。
+    /// false の場合は反射成分と屈折成分を単純に加える。
+    use_schlick: bool,
+    /// 各ライトによる色の和をライトの数で割るか。
+    /// true の場合、ライトを増やしても表面の明るさが変わりにくくなる。
+    normalize_lights: bool,
+    /// 霧の色
+    fog_color: Color,
+    /// 霧の濃さ。0.0 の場合は霧を適用しない。
+    fog_density: FLOAT,
 }
 
 impl World {
@@ -23,9 +80,120 @@ impl World {
         World {
             lights: vec![],
             nodes: vec![],
+            background: Color::BLACK,
+            background_pattern: None,
+            environment: None,
+            use_schlick: true,
+            normalize_lights: false,
+            fog_color: Color::WHITE,
+            fog_density: 0.0,
         }
     }
 
+    /// 2 つの同心球と 1 つの点光源からなる、動作確認用の World を作成する。
+    /// 外側の球は半径 1 の緑がかった球、内側の球は半径 0.5 の白い球で、
+    /// 点光源は (-10, 10, -10) にある。
+    pub fn default_scene() -> Self {
+        let mut w = World::new();
+        w.add_light(Light::new(
+            Point3D::new(-10.0, 10.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+
+        let mut outer = Node::new(Box::new(Sphere::new()));
+        let material = outer.material_mut().unwrap();
+        material.color = Color::new(0.8, 1.0, 0.6);
+        material.diffuse = 0.7;
+        material.specular = 0.2;
+        w.add_node(outer);
+
+        let mut inner = Node::new(Box::new(Sphere::new()));
+        inner.set_transform(Transform::scaling(0.5, 0.5, 0.5));
+        w.add_node(inner);
+
+        w
+    }
+
+    /// 背景色を取得する
+    pub fn background(&self) -> &Color {
+        &self.background
+    }
+
+    /// 背景色を取得する
+    pub fn background_mut(&mut self) -> &mut Color {
+        &mut self.background
+    }
+
+    /// 背景のパターンを取得する
+    pub fn background_pattern(&self) -> &Option<Box<dyn Pattern>> {
+        &self.background_pattern
+    }
+
+    /// 背景のパターンを取得する
+    pub fn background_pattern_mut(&mut self) -> &mut Option<Box<dyn Pattern>> {
+        &mut self.background_pattern
+    }
+
+    /// 各ライトによる色の和をライトの数で割るかを取得する
+    pub fn normalize_lights(&self) -> bool {
+        self.normalize_lights
+    }
+
+    /// 各ライトによる色の和をライトの数で割るかを取得する
+    pub fn normalize_lights_mut(&mut self) -> &mut bool {
+        &mut self.normalize_lights
+    }
+
+    /// 環境マップを取得する
+    pub fn environment(&self) -> &Option<CubeMapPattern> {
+        &self.environment
+    }
+
+    /// 環境マップを取得する
+    pub fn environment_mut(&mut self) -> &mut Option<CubeMapPattern> {
+        &mut self.environment
+    }
+
+    /// Schlick 近似を用いるかを取得する
+    pub fn use_schlick(&self) -> bool {
+        self.use_schlick
+    }
+
+    /// Schlick 近似を用いるかを取得する
+    pub fn use_schlick_mut(&mut self) -> &mut bool {
+        &mut self.use_schlick
+    }
+
+    /// 霧の色を取得する
+    pub fn fog_color(&self) -> &Color {
+        &self.fog_color
+    }
+
+    /// 霧の色を取得する
+    pub fn fog_color_mut(&mut self) -> &mut Color {
+        &mut self.fog_color
+    }
+
+    /// 霧の濃さを取得する
+    pub fn fog_density(&self) -> FLOAT {
+        self.fog_density
+    }
+
+    /// 霧の濃さを取得する
+    pub fn fog_density_mut(&mut self) -> &mut FLOAT {
+        &mut self.fog_density
+    }
+
+    /// ライトを取得する
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    /// オブジェクトを取得する
+    pub fn nodes(&self) -> &[Box<Node>] {
+        &self.nodes
+    }
+
     /// ライトを追加する
     ///
     /// # Arguments
@@ -35,6 +203,34 @@ impl World {
         self.lights.push(light);
     }
 
+    /// center を中心とする半径 radius の球面上に count 個の点光源を追加する。
+    /// 各点光源の色は intensity を count で割ったものとなり、全体として
+    /// intensity の光源 1 つと同じ明るさで柔らかい影を落とす。
+    ///
+    /// # Arguments
+    ///
+    /// * `center` - 点光源を配置する球の中心
+    /// * `intensity` - 点光源全体の色
+    /// * `radius` - 点光源を配置する球の半径
+    /// * `count` - 追加する点光源の数
+    pub fn add_light_cluster(
+        &mut self,
+        center: Point3D,
+        intensity: Color,
+        radius: FLOAT,
+        count: usize,
+    ) {
+        if count == 0 {
+            return;
+        }
+
+        let intensity = &intensity * (1.0 / count as FLOAT);
+        for i in 0..count {
+            let offset = &fibonacci_sphere(i, count) * radius;
+            self.lights.push(Light::new(&offset + &center, intensity));
+        }
+    }
+
     /// オブジェクトを追加する
     ///
     /// # Arguments
@@ -44,6 +240,29 @@ impl World {
         self.nodes.push(node);
     }
 
+    /// y = 0 に a と b の市松模様の床 (Plane) を追加する
+    ///
+    /// # Arguments
+    ///
+    /// * `a` - 原点を含むマスの色
+    /// * `b` - a に隣接するマスの色
+    pub fn add_checker_floor(&mut self, a: Color, b: Color) {
+        let mut floor = Node::new(Box::new(Plane::new()));
+        *floor.material_mut().unwrap().pattern_mut() =
+            Some(Box::new(CheckersPattern::new(a, b)));
+        self.add_node(floor);
+    }
+
+    /// オブジェクトの階層をたどり、Node や三角形の数を集計する。
+    pub fn stats(&self) -> SceneStats {
+        let mut stats = SceneStats::default();
+        for node in &self.nodes {
+            stats.add_node(node);
+        }
+
+        stats
+    }
+
     /// Ray とオブジェクトの交差判定を行い、交差情報のリストを返す。
     /// 返された交差情報は Ray の起点を基準にソートされている。
     ///
@@ -52,96 +271,435 @@ impl World {
     /// * `ray` - 判定対象となる Ray
     pub fn intersect(&self, ray: &Ray) -> Vec<Intersection> {
         let mut intersections = vec![];
-        for shape in &self.nodes {
-            let mut xs = shape.intersect(ray);
-            intersections.append(&mut xs);
-        }
-
-        intersections.sort_unstable_by(|i1, i2| {
-            if i1.t < i2.t {
-                std::cmp::Ordering::Less
-            } else {
-                std::cmp::Ordering::Greater
-            }
-        });
+        self.intersect_into(ray, &mut intersections);
 
         intersections
     }
 
+    /// Ray とオブジェクトの交差判定を行い、交差情報を out に格納する。
+    /// out は最初にクリアされるため、確保済みの領域を再利用できる。
+    /// 格納された交差情報は Ray の起点を基準にソートされている。
+    ///
+    /// # Arguments
+    ///
+    /// * `ray` - 判定対象となる Ray
+    /// * `out` - 交差情報を格納するバッファ
+    pub fn intersect_into<'a>(
+        &'a self,
+        ray: &Ray,
+        out: &mut Vec<Intersection<'a>>,
+    ) {
+        out.clear();
+        for node in &self.nodes {
+            node.intersect_into(ray, out);
+        }
+
+        out.sort_unstable_by(Intersection::cmp_t);
+    }
+
     /// Ray がヒットした点における色を返す。
     ///
     /// # Arguments
     ///
     /// * `intersection_state` - 計算に必要な交点情報
     /// * `remaining` - 再帰の最大深さまでの残り回数
-    fn shade_hit(
-        &self,
+    /// * `xs` - 反射、屈折した Ray の交差情報の作業領域
+    fn shade_hit<'a>(
+        &'a self,
         intersection_state: &IntersectionState,
         remaining: usize,
+        xs: &mut Vec<Intersection<'a>>,
     ) -> Color {
-        let mut surface = Color::new(0.0, 0.0, 0.0);
+        let material = match intersection_state.object.material() {
+            Some(material) => material,
+            // 交点は常に Material を持つ Shape だが、念のため黒とする
+            None => return Color::BLACK,
+        };
+        let color = material.color_at_shape(
+            intersection_state.object,
+            &intersection_state.over_point,
+            intersection_state.time,
+        );
+        let mut surface = Color::BLACK;
         for light in &self.lights {
-            let is_shadowed =
-                self.is_shadowed(&intersection_state.over_point, light);
+            // 点光源の場合は遮られているかどうかのみなので 0.0 か 1.0 となる
+            let light_intensity = self.soft_point_shadow_at(
+                &intersection_state.over_point,
+                light,
+                light.radius(),
+                light.samples(),
+                intersection_state.time,
+            );
             surface = &surface
-                + &intersection_state.object.material().lighting(
-                    intersection_state.object,
+                + &material.lighting_with_color(
+                    &color,
                     light,
                     &intersection_state.over_point,
                     &intersection_state.eyev,
                     &intersection_state.normalv,
-                    is_shadowed,
+                    light_intensity,
                 );
         }
-        let reflected = self.reflected_color(&intersection_state, remaining);
-        let refracted = self.refracted_color(&intersection_state, remaining);
-
-        if intersection_state.object.material().reflective > 0.0
-            && intersection_state.object.material().transparency > 0.0
-        {
+        if self.normalize_lights && !self.lights.is_empty() {
+            surface = &surface * (1.0 / self.lights.len() as FLOAT);
+        }
+        // 自己発光はライトによらないので、正規化の対象としない
+        let surface = &surface + &material.emission;
+        let reflected =
+            self.reflected_color(&intersection_state, remaining, xs);
+        let refracted =
+            self.refracted_color(&intersection_state, remaining, xs);
+
+        let shaded = if self.use_schlick && material.transparency > 0.0 {
             let reflectance = intersection_state.schlick();
             &(&surface + &(&reflected * reflectance))
                 + &(&refracted * (1.0 - reflectance))
         } else {
             &(&surface + &reflected) + &refracted
+        };
+
+        if material.opacity < 1.0 {
+            let behind =
+                self.see_through_color(intersection_state, remaining, xs);
+            &(&shaded * material.opacity)
+                + &(&behind * (1.0 - material.opacity))
+        } else {
+            shaded
         }
     }
 
-    /// Ray に対応する色を返す。ヒットしなかった場合、黒を返す
+    /// Ray に対応する色を返す。ヒットしなかった場合、背景色を返す
     ///
     /// # Arguments
     ///
     /// * `r` - Ray
     /// * `remaining` - 再帰の最大深さまでの残り回数
     pub fn color_at(&self, r: &Ray, remaining: usize) -> Color {
-        let xs = self.intersect(r);
-        if let Some(ref nearest) = hit(&xs) {
-            let is = IntersectionState::new(nearest, r, &xs);
-            self.shade_hit(&is, remaining)
-        } else {
-            Color::BLACK
+        let mut xs = vec![];
+        self.color_at_with(r, remaining, &mut xs)
+    }
+
+    /// Ray に対応する色を返す。交差判定には xs を作業領域として用いる。
+    /// ヒットした点までの距離に応じて霧を適用する。
+    /// 多数の Ray を処理する場合に、同じバッファを使い回して確保を減らす。
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - Ray
+    /// * `remaining` - 再帰の最大深さまでの残り回数
+    /// * `xs` - 交差情報の作業領域
+    pub fn color_at_with<'a>(
+        &'a self,
+        r: &Ray,
+        remaining: usize,
+        xs: &mut Vec<Intersection<'a>>,
+    ) -> Color {
+        let (color, distance) = self.shade_ray(r, remaining, xs);
+        self.apply_fog(color, distance)
+    }
+
+    /// Ray に対応する色と、その計算のために行った Ray と形状の交差判定の
+    /// 回数を返す。回数には反射、屈折、影の Ray によるものも含まれる。
+    /// 交差判定の高速化の効果を確認する用途を想定している。
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - Ray
+    /// * `remaining` - 再帰の最大深さまでの残り回数
+    pub fn color_at_counted(
+        &self,
+        r: &Ray,
+        remaining: usize,
+    ) -> (Color, usize) {
+        let before = intersection_tests();
+        let color = self.color_at(r, remaining);
+        (color, intersection_tests() - before)
+    }
+
+    /// Ray に対応する色と、Ray がヒットした点までの t を返す。
+    /// ヒットしなかった場合は背景色と無限大を返す。
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - Ray
+    /// * `remaining` - 再帰の最大深さまでの残り回数
+    /// * `xs` - 交差情報の作業領域
+    fn shade_ray<'a>(
+        &'a self,
+        r: &Ray,
+        remaining: usize,
+        xs: &mut Vec<Intersection<'a>>,
+    ) -> (Color, FLOAT) {
+        self.intersect_into(r, xs);
+        let is = hit(xs).map(|nearest| IntersectionState::new(nearest, r, xs));
+        self.shade_state(r, is, remaining, xs)
+    }
+
+    /// Ray がヒットした点の交点情報 is から、Ray に対応する色と、
+    /// Ray がヒットした点までの t を返す。
+    /// is が None の場合は背景色と無限大を返す。
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - Ray
+    /// * `is` - Ray が最初にヒットした点の交点情報
+    /// * `remaining` - 再帰の最大深さまでの残り回数
+    /// * `xs` - 反射、屈折した Ray の交差情報の作業領域
+    fn shade_state<'a>(
+        &'a self,
+        r: &Ray,
+        is: Option<IntersectionState>,
+        remaining: usize,
+        xs: &mut Vec<Intersection<'a>>,
+    ) -> (Color, FLOAT) {
+        match is {
+            Some(is) => (self.shade_hit(&is, remaining, xs), is.t),
+            None => (self.background_at(r), INFINITY),
         }
     }
 
-    /// p と light の間に遮蔽物があるか
+    /// Ray に対応する色を返す。
+    /// ただし、Ray が直接ヒットするオブジェクトは Node::tag が filter を
+    /// 満たすものに限る。反射、屈折、影では全てのオブジェクトを考慮する。
+    /// 複雑なシーンの一部のみを確認する用途を想定している。
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - Ray
+    /// * `remaining` - 再帰の最大深さまでの残り回数
+    /// * `filter` - 描画するオブジェクトの tag に対して true を返す関数
+    pub fn color_at_filtered(
+        &self,
+        r: &Ray,
+        remaining: usize,
+        filter: &dyn Fn(Option<&str>) -> bool,
+    ) -> Color {
+        let mut xs = self.intersect(r);
+        xs.retain(|i| filter(i.object.tag()));
+        self.color_at_intersections(r, remaining, &xs, &mut vec![])
+    }
+
+    /// 求め済みの r の交点 xs を用いて、Ray に対応する色を返す。
+    /// 交差判定をやり直さない点を除き color_at と同じ。
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - Ray
+    /// * `remaining` - 再帰の最大深さまでの残り回数
+    /// * `xs` - r に関する全ての交点
+    /// * `buffer` - 反射、屈折した Ray の交差情報の作業領域
+    pub(crate) fn color_at_intersections<'a>(
+        &'a self,
+        r: &Ray,
+        remaining: usize,
+        xs: &Vec<Intersection<'a>>,
+        buffer: &mut Vec<Intersection<'a>>,
+    ) -> Color {
+        let is = hit(xs).map(|nearest| IntersectionState::new(nearest, r, xs));
+        let (color, distance) = self.shade_state(r, is, remaining, buffer);
+        self.apply_fog(color, distance)
+    }
+
+    /// distance だけ離れた位置の color に霧を適用する。
+    /// 1 - exp(-fog_density * distance) の割合で fog_color に近づける。
+    /// fog_density が 0.0 の場合は color をそのまま返す。
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - 霧を適用する前の色
+    /// * `distance` - 色を求めた位置までの距離
+    pub fn apply_fog(&self, color: Color, distance: FLOAT) -> Color {
+        if self.fog_density <= 0.0 {
+            return color;
+        }
+
+        let f = 1.0 - (-self.fog_density * distance).exp();
+        &(&color * (1.0 - f)) + &(&self.fog_color * f)
+    }
+
+    /// Ray がどのオブジェクトにもヒットしなかったときの色を返す。
+    /// 環境マップと背景のパターンは Ray の方向を単位ベクトルにした点で
+    /// 評価する。反射や屈折した Ray がシーンの外へ出た場合も同様。
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - Ray
+    fn background_at(&self, r: &Ray) -> Color {
+        let pattern: &dyn Pattern =
+            match (&self.environment, &self.background_pattern) {
+                (Some(environment), _) => environment,
+                (None, Some(pattern)) => pattern.as_ref(),
+                (None, None) => return self.background,
+            };
+
+        let mut d = r.direction().clone();
+        d.normalize();
+        let p = Point3D::new(d.x, d.y, d.z);
+        pattern.pattern_at(&(pattern.transform().inv() * &p))
+    }
+
+    /// Ray が最初にヒットした点の交点情報を返す。シェーディングは行わない。
+    /// ヒットしなかった場合は None を返す。
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - Ray
+    pub fn trace(&self, r: &Ray) -> Option<IntersectionState<'_>> {
+        let xs = self.intersect(r);
+        hit(&xs).map(|nearest| IntersectionState::new(nearest, r, &xs))
+    }
+
+    /// 時刻 0 において p と light の間に遮蔽物があるか。
+    /// 影を落とさない Material のオブジェクトは遮蔽物とみなさない。
     ///
     /// # Arguments
     ///
     /// * `p` - 位置
     /// * `light` - ライト
-    fn is_shadowed(&self, p: &Point3D, light: &Light) -> bool {
+    pub fn is_shadowed(&self, p: &Point3D, light: &Light) -> bool {
+        self.is_shadowed_at(p, light, 0.0)
+    }
+
+    /// 各ライトについて、p が遮蔽物なしに照らされているかを返す。
+    /// 影の不具合を調べる際のデバッグ用。
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - 位置
+    pub fn point_is_lit_by(&self, p: &Point3D) -> Vec<bool> {
+        self.lights
+            .iter()
+            .map(|light| !self.is_shadowed(p, light))
+            .collect()
+    }
+
+    /// 時刻 time において p と light の間に遮蔽物があるか。
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - 位置
+    /// * `light` - ライト
+    /// * `time` - 時刻
+    pub fn is_shadowed_at(
+        &self,
+        p: &Point3D,
+        light: &Light,
+        time: FLOAT,
+    ) -> bool {
         let mut direction = light.position() - p;
         let distance = direction.magnitude();
         direction.normalize();
 
-        let r = Ray::new(p.clone(), direction);
-        let intersections = self.intersect(&r);
-        if let Some(nearest) = hit(&intersections) {
-            if nearest.t < distance {
-                return true;
+        self.is_occluded_at(p, distance, &direction, time)
+    }
+
+    /// 点光源を半径 radius の球とみなし、時刻 0 において p から見て
+    /// 遮られていない割合を返す。
+    /// 球面を y 方向に samples 個の帯に分け、各帯からランダムに選んだ点で
+    /// 判定する。乱数の種は p と light の位置から決めるので、同じ点に対しては
+    /// 常に同じ値を返す。
+    /// radius が 0 の場合は is_shadowed と同じく 0.0 か 1.0 を返す。
+    /// shade_hit では、拡散反射光と鏡面反射光をこの割合で減衰させる。
+    /// 半径と点の数は Light::with_soft_shadow で設定したものが用いられる。
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - 位置
+    /// * `light` - ライト
+    /// * `radius` - ライトとみなす球の半径
+    /// * `samples` - 判定に用いる球面上の点の数
+    pub fn soft_point_shadow(
+        &self,
+        p: &Point3D,
+        light: &Light,
+        radius: FLOAT,
+        samples: usize,
+    ) -> FLOAT {
+        self.soft_point_shadow_at(p, light, radius, samples, 0.0)
+    }
+
+    /// 点光源を半径 radius の球とみなし、時刻 time において p から見て
+    /// 遮られていない割合を返す。
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - 位置
+    /// * `light` - ライト
+    /// * `radius` - ライトとみなす球の半径
+    /// * `samples` - 判定に用いる球面上の点の数
+    /// * `time` - 時刻
+    pub fn soft_point_shadow_at(
+        &self,
+        p: &Point3D,
+        light: &Light,
+        radius: FLOAT,
+        samples: usize,
+        time: FLOAT,
+    ) -> FLOAT {
+        if radius <= 0.0 || samples == 0 {
+            return if self.is_shadowed_at(p, light, time) {
+                0.0
+            } else {
+                1.0
+            };
+        }
+
+        // 全ての点で同じ配置を用いると縞模様になるため、点ごとに乱数でずらす
+        let mut rng = Random::new(shadow_seed(p, light.position()));
+        let mut lit = 0;
+        for i in 0..samples {
+            let offset = &jittered_sphere(i, samples, &mut rng) * radius;
+            let sample = &offset + light.position();
+
+            let mut direction = &sample - p;
+            let distance = direction.magnitude();
+            direction.normalize();
+            if !self.is_occluded_at(p, distance, &direction, time) {
+                lit += 1;
             }
         }
-        false
+
+        lit as FLOAT / samples as FLOAT
+    }
+
+    /// from から dir 方向に distance 未満の位置に遮蔽物があるか。
+    /// 遮蔽物が見つかった時点で判定を打ち切り、交差情報のソートも行わない。
+    /// 影を落とさない Material のオブジェクトは遮蔽物とみなさない。
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - 位置
+    /// * `to_light_distance` - ライトまでの距離
+    /// * `dir` - ライトへの方向を表す単位ベクトル
+    pub fn is_occluded(
+        &self,
+        from: &Point3D,
+        to_light_distance: FLOAT,
+        dir: &Vector3D,
+    ) -> bool {
+        self.is_occluded_at(from, to_light_distance, dir, 0.0)
+    }
+
+    /// 時刻 time において from から dir 方向に distance 未満の位置に
+    /// 遮蔽物があるか。
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - 位置
+    /// * `to_light_distance` - ライトまでの距離
+    /// * `dir` - ライトへの方向を表す単位ベクトル
+    /// * `time` - 時刻
+    pub fn is_occluded_at(
+        &self,
+        from: &Point3D,
+        to_light_distance: FLOAT,
+        dir: &Vector3D,
+        time: FLOAT,
+    ) -> bool {
+        let r = Ray::new(from.clone(), dir.clone()).with_time(time);
+        self.nodes
+            .iter()
+            .any(|node| node.any_hit_before(&r, to_light_distance))
     }
 
     /// 反射成分の色を計算する。
@@ -150,23 +708,58 @@ impl World {
     ///
     /// * `is` - 反射する点の情報
     /// * `remaining` - 再帰の最大深さまでの残り回数
-    fn reflected_color(
-        &self,
+    /// * `xs` - 反射した Ray の交差情報の作業領域
+    fn reflected_color<'a>(
+        &'a self,
         is: &IntersectionState,
         remaining: usize,
+        xs: &mut Vec<Intersection<'a>>,
     ) -> Color {
-        if is.object.material().reflective == 0.0 {
+        let material = match is.object.material() {
+            Some(material) if material.reflective != 0.0 => material,
             // 光を全く反射しない場合
-            return Color::BLACK;
-        }
+            _ => return Color::BLACK,
+        };
         if remaining <= 0 {
             return Color::BLACK;
         }
 
-        let reflect_ray = Ray::new(is.over_point.clone(), is.reflectv.clone());
-        let color = self.color_at(&reflect_ray, remaining - 1);
+        let reflect_ray = Ray::new(is.over_point.clone(), is.reflectv.clone())
+            .with_time(is.time);
+        // 反射した点までの距離に応じた霧は color_at_with の中で適用される
+        let color = self.color_at_with(&reflect_ray, remaining - 1, xs);
 
-        &color * is.object.material().reflective
+        let reflected = &color * material.reflective;
+        match material.reflect_color {
+            Some(ref tint) => &reflected * tint,
+            None => reflected,
+        }
+    }
+
+    /// 交点を屈折せずにそのまま通り抜けた先の色を計算する。
+    ///
+    /// # Arguments
+    ///
+    /// * `is` - 通り抜ける点の情報
+    /// * `remaining` - 再帰の最大深さまでの残り回数
+    /// * `xs` - 通り抜けた Ray の交差情報の作業領域
+    fn see_through_color<'a>(
+        &'a self,
+        is: &IntersectionState,
+        remaining: usize,
+        xs: &mut Vec<Intersection<'a>>,
+    ) -> Color {
+        // 反射や屈折ではないが、半透明な面が多数重なっている場合や、形状が
+        // 起点のすぐ近くで交点を返し続ける場合に再帰が止まらないよう、
+        // 再帰の深さを減らす。
+        if remaining == 0 {
+            return Color::BLACK;
+        }
+
+        let direction = -&is.eyev;
+        let origin = &is.point + &(&direction * is.offset());
+        let r = Ray::new(origin, direction).with_time(is.time);
+        self.color_at_with(&r, remaining - 1, xs)
     }
 
     /// 屈折成分の色を計算する。
@@ -175,15 +768,20 @@ impl World {
     ///
     /// * `is` - 屈折する点の情報
     /// * `remaining` - 再帰の最大深さまでの残り回数
-    fn refracted_color(
-        &self,
+    /// * `xs` - 屈折した Ray の交差情報の作業領域
+    fn refracted_color<'a>(
+        &'a self,
         is: &IntersectionState,
         remaining: usize,
+        xs: &mut Vec<Intersection<'a>>,
     ) -> Color {
-        if is.object.material().transparency == 0.0 {
+        let transparency = match is.object.material() {
+            Some(material) if material.transparency != 0.0 => {
+                material.transparency
+            }
             // 不透明な場合
-            return Color::BLACK;
-        }
+            _ => return Color::BLACK,
+        };
         if remaining <= 0 {
             return Color::BLACK;
         }
@@ -199,18 +797,81 @@ impl World {
         let cos_t = (1.0 - sin2_t).sqrt();
         let direction =
             &(&is.normalv * (n_ratio * cos_i - cos_t)) - &(&is.eyev * n_ratio);
-        let r = Ray::new(is.under_point.clone(), direction);
-        &self.color_at(&r, remaining - 1) * is.object.material().transparency
+        // 透過する場合は under_point ではなく、屈折方向にずらした点を起点とする。
+        // 法線が補間されている場合などに、法線方向にずらしただけでは
+        // 屈折した Ray が同じ面と再び交差することがあるため。
+        let origin = &is.point + &(&direction * is.offset());
+        let r = Ray::new(origin, direction).with_time(is.time);
+        &self.color_at_with(&r, remaining - 1, xs) * transparency
     }
 }
 
+/// 単位球面上に n 個の点をフィボナッチ格子状に配置したときの i 番目の点を、
+/// 原点からのベクトルとして返す。
+///
+/// # Arguments
+///
+/// * `i` - 点の番号
+/// * `n` - 点の総数
+fn fibonacci_sphere(i: usize, n: usize) -> Vector3D {
+    let golden_angle =
+        std::f64::consts::PI as FLOAT * (3.0 - (5.0 as FLOAT).sqrt());
+    let y = 1.0 - 2.0 * (i as FLOAT + 0.5) / n as FLOAT;
+    let r = (1.0 - y * y).sqrt();
+    let theta = golden_angle * i as FLOAT;
+
+    Vector3D::new(r * theta.cos(), y, r * theta.sin())
+}
+
+/// 単位球面を y 方向に n 個の帯に分けたときの i 番目の帯から、
+/// ランダムに選んだ点を原点からのベクトルとして返す。
+///
+/// # Arguments
+///
+/// * `i` - 帯の番号
+/// * `n` - 帯の総数
+/// * `rng` - 点を選ぶための乱数生成器
+fn jittered_sphere(i: usize, n: usize, rng: &mut Random) -> Vector3D {
+    let y = 1.0 - 2.0 * (i as FLOAT + rng.next_float()) / n as FLOAT;
+    let r = (1.0 - y * y).sqrt();
+    let theta = 2.0 * std::f64::consts::PI as FLOAT * rng.next_float();
+
+    Vector3D::new(r * theta.cos(), y, r * theta.sin())
+}
+
+/// soft_point_shadow で用いる乱数の種を、影を判定する点 p とライトの位置から
+/// 求める。
+///
+/// # Arguments
+///
+/// * `p` - 影を判定する点
+/// * `light_position` - ライトの位置
+fn shadow_seed(p: &Point3D, light_position: &Point3D) -> u64 {
+    [
+        p.x,
+        p.y,
+        p.z,
+        light_position.x,
+        light_position.y,
+        light_position.z,
+    ]
+    .iter()
+    .fold(0, |seed: u64, v| {
+        // splitmix64 の定数で混ぜ、座標の順序も区別する
+        (seed ^ v.to_bits()).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
         super::{
-            approx_eq, camera::Camera, color::Color, material::Material,
-            pattern::Pattern, plane::Plane, shape::Shape, sphere::Sphere,
-            transform::Transform, vector3d::Vector3D, FLOAT,
+            approx_eq, camera::Camera, canvas::Canvas, color::Color,
+            gradient_pattern::GradientPattern, group::Group,
+            material::Material, pattern::Pattern, plane::Plane,
+            smooth_triangle::SmoothTriangle, sphere::Sphere,
+            stripe_pattern::StripePattern, transform::Transform,
+            triangle::Triangle, vector3d::Vector3D, EPSILON, FLOAT,
         },
         *,
     };
@@ -242,48 +903,204 @@ mod tests {
         }
     }
 
-    fn default_world() -> World {
-        let mut w = World::new();
+    #[test]
+    fn the_default_scene() {
+        let w = World::default_scene();
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
 
-        let light = Light::new(
-            Point3D::new(-10.0, 10.0, -10.0),
-            Color::new(1.0, 1.0, 1.0),
+        assert_eq!(2, w.nodes().len());
+        assert_eq!(1, w.lights().len());
+        assert_eq!(Color::new(0.38066, 0.47583, 0.2855), w.color_at(&r, 5));
+    }
+
+    #[test]
+    fn intersect_a_world_with_a_ray() {
+        let w = World::default_scene();
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
         );
-        w.add_light(light);
 
-        let mut sphere = Box::new(Sphere::new());
-        let mut material = Material::new();
-        material.color = Color::new(0.8, 1.0, 0.6);
-        material.diffuse = 0.7;
-        material.specular = 0.2;
-        *sphere.material_mut() = material;
-        w.add_node(Node::new(sphere));
+        let xs = w.intersect(&r);
+        assert_eq!(4, xs.len());
+        assert!(approx_eq(4.0, xs[0].t));
+        assert!(approx_eq(4.5, xs[1].t));
+        assert!(approx_eq(5.5, xs[2].t));
+        assert!(approx_eq(6.0, xs[3].t));
+    }
 
+    #[test]
+    fn intersect_a_world_with_a_translated_node() {
+        let mut w = World::new();
         let mut sphere = Node::new(Box::new(Sphere::new()));
-        sphere.set_transform(Transform::scaling(0.5, 0.5, 0.5));
+        sphere.set_transform(Transform::translation(0.0, 0.0, 5.0));
         w.add_node(sphere);
-        return w;
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+
+        let xs = w.intersect(&r);
+        assert_eq!(2, xs.len());
+        assert!(approx_eq(9.0, xs[0].t));
+        assert!(approx_eq(11.0, xs[1].t));
+
+        let comps = w.trace(&r).unwrap();
+        assert_eq!(Vector3D::new(0.0, 0.0, -1.0), *comps.normalv());
+    }
+
+    #[test]
+    fn adding_a_checker_floor() {
+        let mut w = World::new();
+        w.add_checker_floor(Color::WHITE, Color::BLACK);
+
+        assert_eq!(1, w.nodes().len());
+        let floor = &w.nodes()[0];
+        assert!(floor.material().unwrap().pattern().is_some());
+
+        let r = Ray::new(
+            Point3D::new(0.5, 1.0, 0.5),
+            Vector3D::new(0.0, -1.0, 0.0),
+        );
+        assert!(approx_eq(1.0, w.intersect(&r)[0].t));
+    }
+
+    #[test]
+    fn counting_the_shapes_in_a_world() {
+        let mut w = World::new();
+        let mut g = Node::new(Box::new(Group::new()));
+        g.add_children(vec![
+            Node::new(Box::new(Sphere::new())),
+            Node::new(Box::new(Sphere::new())),
+            Node::new(Box::new(Triangle::new(
+                Point3D::new(0.0, 1.0, 0.0),
+                Point3D::new(-1.0, 0.0, 0.0),
+                Point3D::new(1.0, 0.0, 0.0),
+            ))),
+        ]);
+        w.add_node(g);
+
+        let stats = w.stats();
+        assert_eq!(4, stats.nodes);
+        assert_eq!(3, stats.leaves);
+        assert_eq!(1, stats.triangles);
     }
 
-    #[test]
-    fn intersect_a_world_with_a_ray() {
-        let w = default_world();
+    #[test]
+    fn intersect_into_reuses_the_buffer() {
+        let w = World::default_scene();
+        let hit_ray = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+        let miss_ray = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 1.0, 0.0),
+        );
+        let expected = w.intersect(&hit_ray);
+
+        let mut xs = vec![];
+        w.intersect_into(&hit_ray, &mut xs);
+        assert_eq!(expected.len(), xs.len());
+        for (e, x) in expected.iter().zip(xs.iter()) {
+            assert!(approx_eq(e.t, x.t));
+            assert!(std::ptr::eq(e.object, x.object));
+        }
+
+        let capacity = xs.capacity();
+        w.intersect_into(&miss_ray, &mut xs);
+        assert_eq!(0, xs.len());
+        w.intersect_into(&hit_ray, &mut xs);
+        assert_eq!(4, xs.len());
+        assert_eq!(capacity, xs.capacity());
+    }
+
+    #[test]
+    fn tracing_a_ray_returns_the_nearest_intersection_state() {
+        let w = World::default_scene();
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+
+        let comps = w.trace(&r).unwrap();
+        assert!(std::ptr::eq(&*w.nodes[0], comps.object()));
+        assert!(approx_eq(4.0, comps.t()));
+        assert_eq!(Point3D::new(0.0, 0.0, -1.0), *comps.point());
+        assert_eq!(Vector3D::new(0.0, 0.0, -1.0), *comps.normalv());
+
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 1.0, 0.0),
+        );
+        assert!(w.trace(&r).is_none());
+    }
+
+    #[test]
+    fn an_invisible_node_is_not_rendered() {
+        let mut w = World::default_scene();
+        let mut s = Node::new(Box::new(Sphere::new()));
+        s.set_transform(Transform::translation(5.0, 0.0, 0.0));
+        w.add_node(s);
+        let r1 = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+        let r2 = Ray::new(
+            Point3D::new(5.0, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+        let c1 = w.color_at(&r1, 5);
+        assert_ne!(Color::BLACK, w.color_at(&r2, 5));
+
+        *w.nodes[2].visible_mut() = false;
+        assert_eq!(c1, w.color_at(&r1, 5));
+        assert_eq!(Color::BLACK, w.color_at(&r2, 5));
+
+        *w.nodes[2].visible_mut() = true;
+        assert_ne!(Color::BLACK, w.color_at(&r2, 5));
+    }
+
+    #[test]
+    fn filtering_out_a_front_sphere_reveals_the_one_behind() {
+        let mut w = World::new();
+        w.add_light(Light::new(
+            Point3D::new(-10.0, 10.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let mut front = Node::new(Box::new(Sphere::new()));
+        *front.material_mut().unwrap() = Material::matte(Color::RED);
+        *front.tag_mut() = Some("front".to_string());
+        w.add_node(front);
+        let mut back = Node::new(Box::new(Sphere::new()));
+        back.set_transform(Transform::translation(0.0, 0.0, 5.0));
+        *back.material_mut().unwrap() =
+            Material::matte(Color::new(0.0, 0.0, 1.0));
+        w.add_node(back);
+
         let r = Ray::new(
             Point3D::new(0.0, 0.0, -5.0),
             Vector3D::new(0.0, 0.0, 1.0),
         );
+        let unfiltered = w.color_at(&r, 5);
+        assert_eq!(
+            unfiltered,
+            w.color_at_filtered(&r, 5, &|_: Option<&str>| true)
+        );
 
-        let xs = w.intersect(&r);
-        assert_eq!(4, xs.len());
-        assert!(approx_eq(4.0, xs[0].t));
-        assert!(approx_eq(4.5, xs[1].t));
-        assert!(approx_eq(5.5, xs[2].t));
-        assert!(approx_eq(6.0, xs[3].t));
+        let filtered = w.color_at_filtered(&r, 5, &|tag: Option<&str>| {
+            tag != Some("front")
+        });
+        assert!(0.0 < unfiltered.red && unfiltered.blue == 0.0);
+        assert!(filtered.red == 0.0 && 0.0 < filtered.blue);
     }
 
     #[test]
     fn shading_an_intersection() {
-        let w = default_world();
+        let w = World::default_scene();
         let r = Ray::new(
             Point3D::new(0.0, 0.0, -5.0),
             Vector3D::new(0.0, 0.0, 1.0),
@@ -294,16 +1111,51 @@ mod tests {
             object: shape,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let comps = IntersectionState::new(&i, &r, &vec![]);
 
-        let c = w.shade_hit(&comps, 1);
+        let c = w.shade_hit(&comps, 1, &mut vec![]);
         assert_eq!(Color::new(0.38066, 0.47583, 0.2855), c);
     }
 
+    #[test]
+    fn shade_hit_with_a_soft_light_in_the_penumbra() {
+        let mut w = World::new();
+        w.add_node(Node::new(Box::new(Sphere::new())));
+        let mut wall = Node::new(Box::new(Plane::new()));
+        wall.set_transform(
+            &Transform::translation(0.0, 0.0, 10.0)
+                * &Transform::rotation_x(std::f64::consts::FRAC_PI_2 as FLOAT),
+        );
+        {
+            let m = wall.material_mut().unwrap();
+            m.ambient = 0.1;
+            m.diffuse = 0.9;
+            m.specular = 0.0;
+        }
+        w.add_node(wall);
+        let r =
+            Ray::new(Point3D::new(1.5, 0.0, 5.0), Vector3D::new(0.0, 0.0, 1.0));
+        let shade = |w: &World| {
+            let xs = w.intersect(&r);
+            let comps = IntersectionState::new(&xs[0], &r, &xs);
+            w.shade_hit(&comps, 1, &mut vec![])
+        };
+
+        // 点光源では完全に影となり、環境光のみとなる
+        w.add_light(Light::new(Point3D::new(0.0, 0.0, -10.0), Color::WHITE));
+        assert_eq!(Color::new(0.1, 0.1, 0.1), shade(&w));
+
+        w.lights[0] = Light::new(Point3D::new(0.0, 0.0, -10.0), Color::WHITE)
+            .with_soft_shadow(1.0, 16);
+        let c = shade(&w);
+        assert!(0.1 + EPSILON < c.red && c.red < 0.9);
+    }
+
     #[test]
     fn shading_an_intersection_from_the_inside() {
-        let mut w = default_world();
+        let mut w = World::default_scene();
         w.lights[0] = Light::new(Point3D::new(0.0, 0.25, 0.0), Color::WHITE);
         let r =
             Ray::new(Point3D::new(0.0, 0.0, 0.0), Vector3D::new(0.0, 0.0, 1.0));
@@ -313,15 +1165,16 @@ mod tests {
             object: shape,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let comps = IntersectionState::new(&i, &r, &vec![]);
-        let c = w.shade_hit(&comps, 1);
+        let c = w.shade_hit(&comps, 1, &mut vec![]);
         assert_eq!(Color::new(0.90498, 0.90498, 0.90498), c);
     }
 
     #[test]
     fn shading_an_intersection_with_two_lights() {
-        let mut w = default_world();
+        let mut w = World::default_scene();
         let r = Ray::new(
             Point3D::new(0.0, 0.0, -5.0),
             Vector3D::new(0.0, 0.0, 1.0),
@@ -338,16 +1191,37 @@ mod tests {
             object: shape,
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let comps = IntersectionState::new(&i, &r, &vec![]);
 
-        let c = w.shade_hit(&comps, 1);
+        let c = w.shade_hit(&comps, 1, &mut vec![]);
         assert_eq!(Color::new(0.76132, 0.95166, 0.5710), c);
     }
 
+    #[test]
+    fn normalized_lights_keep_the_brightness_of_a_single_light() {
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+        let single = World::default_scene();
+        let expected = single.color_at(&r, 5);
+
+        let mut w = World::default_scene();
+        w.add_light(Light::new(
+            Point3D::new(-10.0, 10.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        assert!(expected.red < w.color_at(&r, 5).red);
+
+        *w.normalize_lights_mut() = true;
+        assert_eq!(expected, w.color_at(&r, 5));
+    }
+
     #[test]
     fn the_color_when_a_ray_misses() {
-        let w = default_world();
+        let w = World::default_scene();
         let r = Ray::new(
             Point3D::new(0.0, 0.0, -5.0),
             Vector3D::new(0.0, 1.0, 0.0),
@@ -358,7 +1232,7 @@ mod tests {
 
     #[test]
     fn the_color_when_a_ray_hits() {
-        let w = default_world();
+        let w = World::default_scene();
         let r = Ray::new(
             Point3D::new(0.0, 0.0, -5.0),
             Vector3D::new(0.0, 0.0, 1.0),
@@ -369,20 +1243,20 @@ mod tests {
 
     #[test]
     fn the_color_with_an_intersection_behinde_a_ray() {
-        let mut w = default_world();
-        w.nodes[0].material_mut().ambient = 1.0;
-        w.nodes[1].material_mut().ambient = 1.0;
+        let mut w = World::default_scene();
+        w.nodes[0].material_mut().unwrap().ambient = 1.0;
+        w.nodes[1].material_mut().unwrap().ambient = 1.0;
         let r = Ray::new(
             Point3D::new(0.0, 0.0, 0.75),
             Vector3D::new(0.0, 0.0, -1.0),
         );
         let c = w.color_at(&r, 1);
-        assert_eq!(w.nodes[1].material().color, c);
+        assert_eq!(w.nodes[1].material().unwrap().color, c);
     }
 
     #[test]
     fn rendering_a_world_with_a_camera() {
-        let w = default_world();
+        let w = World::default_scene();
         let mut c = Camera::new(11, 11, std::f32::consts::FRAC_PI_2 as FLOAT);
         let from = Point3D::new(0.0, 0.0, -5.0);
         let to = Point3D::new(0.0, 0.0, 0.0);
@@ -393,9 +1267,127 @@ mod tests {
         assert_eq!(Color::new(0.38066, 0.47583, 0.2855), *image.color_at(5, 5));
     }
 
+    #[test]
+    fn an_antialiased_render_is_close_to_the_plain_render_inside_a_shape() {
+        let w = World::default_scene();
+        let mut c = Camera::new(101, 101, std::f32::consts::FRAC_PI_2 as FLOAT);
+        let from = Point3D::new(0.0, 0.0, -5.0);
+        let to = Point3D::new(0.0, 0.0, 0.0);
+        let up = Vector3D::new(0.0, 1.0, 0.0);
+        *c.transform_mut() = Transform::view_transform(&from, &to, &up);
+        let image = c.render_antialiased(&w, 4);
+        let reference = c.render(&w);
+
+        // 形状の内側では pixel 内の色の変化が小さい
+        let expected = reference.color_at(50, 50);
+        let actual = image.color_at(50, 50);
+        assert!((expected.red - actual.red).abs() < 0.01);
+        assert!((expected.green - actual.green).abs() < 0.01);
+        assert!((expected.blue - actual.blue).abs() < 0.01);
+    }
+
+    #[test]
+    fn antialiased_renders_are_reproducible_for_a_seed() {
+        let w = World::default_scene();
+        let mut c = Camera::new(11, 11, std::f32::consts::FRAC_PI_2 as FLOAT);
+        let from = Point3D::new(0.0, 0.0, -5.0);
+        let to = Point3D::new(0.0, 0.0, 0.0);
+        let up = Vector3D::new(0.0, 1.0, 0.0);
+        *c.transform_mut() = Transform::view_transform(&from, &to, &up);
+
+        let render = |c: &Camera| {
+            let mut ppm = vec![];
+            c.render_antialiased(&w, 4).to_ppm(&mut ppm).unwrap();
+            ppm
+        };
+
+        *c.seed_mut() = 1;
+        let image1 = render(&c);
+        let image2 = render(&c);
+        *c.seed_mut() = 2;
+        let image3 = render(&c);
+
+        assert_eq!(image1, image2);
+        // 球の輪郭の pixel はサンプル位置によって色が変わる
+        assert_ne!(image1, image3);
+    }
+
+    #[test]
+    fn a_moving_object_is_blurred_along_its_motion() {
+        let mut w = World::new();
+        let mut s = Node::new(Box::new(Sphere::new()));
+        s.material_mut().unwrap().emission = Color::WHITE;
+        *s.motion_mut() = Some((
+            Transform::translation(0.0, 0.0, -5.0),
+            Transform::translation(2.0, 0.0, -5.0),
+        ));
+        w.add_node(s);
+        let c = Camera::new(11, 11, std::f32::consts::FRAC_PI_2 as FLOAT);
+
+        // 中心の pixel は前半の時刻のみ球が重なるので、半分程度の明るさになる
+        let image = c.render_antialiased(&w, 16);
+        let center = image.color_at(5, 5).red;
+        assert!(0.25 < center && center < 0.75);
+    }
+
+    #[test]
+    fn the_pattern_of_a_moving_object_follows_its_motion() {
+        let mut w = World::new();
+        w.add_light(Light::new(Point3D::new(0.0, 0.0, -10.0), Color::WHITE));
+        let mut s = Node::new(Box::new(Sphere::new()));
+        {
+            let m = s.material_mut().unwrap();
+            *m.pattern_mut() =
+                Some(Box::new(StripePattern::new(Color::WHITE, Color::BLACK)));
+            m.ambient = 1.0;
+            m.diffuse = 0.0;
+            m.specular = 0.0;
+        }
+        *s.motion_mut() = Some((
+            Transform::identity(),
+            Transform::translation(1.0, 0.0, 0.0),
+        ));
+        w.add_node(s);
+
+        // 時刻 1 では球の中心が x = 1 にあるので、object 座標系で x = -0.5
+        let r = Ray::new(
+            Point3D::new(0.5, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        )
+        .with_time(1.0);
+        assert_eq!(Color::BLACK, w.color_at(&r, 0));
+
+        let r = Ray::new(
+            Point3D::new(0.5, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+        assert_eq!(Color::WHITE, w.color_at(&r, 0));
+    }
+
+    #[test]
+    fn a_moving_object_casts_its_shadow_at_the_given_time() {
+        let mut w = World::new();
+        w.add_light(Light::new(Point3D::new(3.0, 10.0, 0.0), Color::WHITE));
+        let mut s = Node::new(Box::new(Sphere::new()));
+        *s.motion_mut() = Some((
+            Transform::identity(),
+            Transform::translation(3.0, 5.0, 0.0),
+        ));
+        w.add_node(s);
+        let p = Point3D::new(3.0, 0.0, 0.0);
+        let direction = Vector3D::new(0.0, 1.0, 0.0);
+        let light = &w.lights[0];
+
+        assert_eq!(false, w.is_shadowed_at(&p, light, 0.0));
+        assert_eq!(true, w.is_shadowed_at(&p, light, 1.0));
+        assert_eq!(false, w.is_occluded_at(&p, 10.0, &direction, 0.0));
+        assert_eq!(true, w.is_occluded_at(&p, 10.0, &direction, 1.0));
+        assert_eq!(0.0, w.soft_point_shadow_at(&p, light, 0.5, 16, 1.0));
+    }
+
     #[test]
     fn there_is_no_shadow_when_nothing_is_collinear_with_point_and_light() {
-        let w = default_world();
+        let w = World::default_scene();
         let p = Point3D::new(0.0, 10.0, 0.0);
 
         assert_eq!(false, w.is_shadowed(&p, &w.lights[0]));
@@ -403,15 +1395,38 @@ mod tests {
 
     #[test]
     fn the_shadow_when_an_object_is_between_the_point_and_the_light() {
-        let w = default_world();
+        let w = World::default_scene();
+        let p = Point3D::new(10.0, -10.0, 10.0);
+
+        assert_eq!(true, w.is_shadowed(&p, &w.lights[0]));
+    }
+
+    #[test]
+    fn an_object_that_does_not_cast_shadows_leaves_the_point_lit() {
+        let mut w = World::default_scene();
         let p = Point3D::new(10.0, -10.0, 10.0);
 
+        w.nodes[0].material_mut().unwrap().casts_shadow = false;
         assert_eq!(true, w.is_shadowed(&p, &w.lights[0]));
+
+        w.nodes[1].material_mut().unwrap().casts_shadow = false;
+        assert_eq!(false, w.is_shadowed(&p, &w.lights[0]));
+    }
+
+    #[test]
+    fn querying_which_lights_illuminate_a_point() {
+        let w = World::default_scene();
+
+        let above = Point3D::new(0.0, 10.0, 0.0);
+        assert_eq!(vec![true], w.point_is_lit_by(&above));
+
+        let occluded = Point3D::new(10.0, -10.0, 10.0);
+        assert_eq!(vec![false], w.point_is_lit_by(&occluded));
     }
 
     #[test]
     fn there_is_no_shadow_when_an_object_is_behind_the_light() {
-        let w = default_world();
+        let w = World::default_scene();
         let p = Point3D::new(-20.0, 20.0, -20.0);
 
         assert_eq!(false, w.is_shadowed(&p, &w.lights[0]));
@@ -419,12 +1434,176 @@ mod tests {
 
     #[test]
     fn there_is_no_shadow_when_an_object_is_behind_the_point() {
-        let w = default_world();
+        let w = World::default_scene();
         let p = Point3D::new(-2.0, 2.0, -2.0);
 
         assert_eq!(false, w.is_shadowed(&p, &w.lights[0]));
     }
 
+    #[test]
+    fn the_over_point_offset_scales_with_the_hit_distance() {
+        let mut w = World::new();
+        w.add_light(Light::new(Point3D::new(0.0, 0.0, 0.0), Color::WHITE));
+        let mut s = Node::new(Box::new(Sphere::new()));
+        s.set_transform(Transform::translation(0.0, 0.0, 10000.0));
+        w.add_node(s);
+        let r =
+            Ray::new(Point3D::new(0.0, 0.0, 0.0), Vector3D::new(0.0, 0.0, 1.0));
+
+        let comps = w.trace(&r).unwrap();
+
+        assert!(approx_eq(9999.0, comps.t()));
+        let offset = (comps.point() - &comps.over_point).magnitude();
+        assert!(approx_eq(EPSILON * 9999.0, offset));
+        assert!(!w.is_shadowed(&comps.over_point, &w.lights[0]));
+    }
+
+    #[test]
+    fn a_far_away_hit_does_not_shadow_itself() {
+        let mut w = World::new();
+        w.add_node(Node::new(Box::new(Plane::new())));
+        let h = 8.832e10;
+        let mut direction = Vector3D::new(1.3, -1.0, 0.1);
+        direction.normalize();
+        let r = Ray::new(Point3D::new(-1.3 * h, h, 0.0), direction);
+
+        let comps = w.trace(&r).unwrap();
+        let p = comps.point();
+        let light = Light::new(Point3D::new(p.x, 100.0, p.z), Color::WHITE);
+
+        // 丸め誤差で交点が平面の下にあり、固定の EPSILON では平面の上に出ない
+        let fixed = Point3D::new(p.x, p.y + EPSILON, p.z);
+        assert!(fixed.y < 0.0);
+        assert!(w.is_shadowed(&fixed, &light));
+
+        assert!(0.0 < comps.over_point.y);
+        assert!(!w.is_shadowed(&comps.over_point, &light));
+    }
+
+    #[test]
+    fn a_light_cluster_splits_the_intensity_among_its_lights() {
+        let mut w = World::new();
+        let intensity = Color::new(1.0, 0.5, 0.25);
+        w.add_light_cluster(Point3D::new(0.0, 0.0, -10.0), intensity, 1.0, 16);
+
+        assert_eq!(16, w.lights().len());
+        let total = w
+            .lights()
+            .iter()
+            .fold(Color::BLACK, |sum, l| &sum + l.intensity());
+        assert_eq!(intensity, total);
+        for l in w.lights() {
+            let d = l.position() - &Point3D::new(0.0, 0.0, -10.0);
+            assert!(approx_eq(1.0, d.magnitude()));
+        }
+    }
+
+    #[test]
+    fn a_light_cluster_casts_a_partial_shadow_near_an_edge() {
+        let mut w = World::new();
+        w.add_light_cluster(
+            Point3D::new(0.0, 0.0, -10.0),
+            Color::WHITE,
+            1.0,
+            16,
+        );
+        w.add_node(Node::new(Box::new(Sphere::new())));
+
+        let p = Point3D::new(1.5, 0.0, 10.0);
+        let shadowed =
+            w.lights().iter().filter(|l| w.is_shadowed(&p, l)).count();
+
+        assert!(0 < shadowed && shadowed < 16);
+    }
+
+    #[test]
+    fn is_occluded_agrees_with_a_full_intersection() {
+        let mut w = World::default_scene();
+        w.nodes[0].material_mut().unwrap().casts_shadow = false;
+        let points = [
+            Point3D::new(0.0, 10.0, 0.0),
+            Point3D::new(10.0, -10.0, 10.0),
+            Point3D::new(-20.0, 20.0, -20.0),
+            Point3D::new(-2.0, 2.0, -2.0),
+            Point3D::new(0.0, 0.0, 0.0),
+        ];
+
+        for p in points.iter() {
+            let mut direction = w.lights[0].position() - p;
+            let distance = direction.magnitude();
+            direction.normalize();
+            let xs = w.intersect(&Ray::new(p.clone(), direction.clone()));
+            let expected = xs.iter().any(|i| {
+                0.0 <= i.t
+                    && i.t < distance
+                    && i.object.material().unwrap().casts_shadow
+            });
+
+            assert_eq!(expected, w.is_occluded(p, distance, &direction));
+            assert_eq!(expected, w.is_shadowed(p, &w.lights[0]));
+        }
+    }
+
+    #[test]
+    fn a_soft_point_shadow_with_zero_radius_is_a_hard_shadow() {
+        let w = World::default_scene();
+        let points = [
+            Point3D::new(0.0, 10.0, 0.0),
+            Point3D::new(10.0, -10.0, 10.0),
+            Point3D::new(-20.0, 20.0, -20.0),
+            Point3D::new(-2.0, 2.0, -2.0),
+        ];
+
+        for p in points.iter() {
+            let expected = if w.is_shadowed(p, &w.lights[0]) {
+                0.0
+            } else {
+                1.0
+            };
+            assert_eq!(expected, w.soft_point_shadow(p, &w.lights[0], 0.0, 16));
+        }
+    }
+
+    #[test]
+    fn a_soft_point_shadow_is_partially_lit_in_the_penumbra() {
+        let w = World::default_scene();
+        let p = Point3D::new(10.0, -10.0, 10.0);
+
+        let fraction = w.soft_point_shadow(&p, &w.lights[0], 5.0, 64);
+        assert!(0.0 < fraction && fraction < 1.0);
+    }
+
+    #[test]
+    fn a_soft_point_shadow_is_reproducible_for_the_same_point() {
+        let w = World::default_scene();
+        let p = Point3D::new(10.0, -10.0, 10.0);
+
+        assert_eq!(
+            w.soft_point_shadow(&p, &w.lights[0], 5.0, 16),
+            w.soft_point_shadow(&p, &w.lights[0], 5.0, 16)
+        );
+    }
+
+    #[test]
+    fn jittered_sphere_points_differ_between_shaded_points() {
+        let light = Point3D::new(-10.0, 10.0, -10.0);
+        let mut rng1 = Random::new(shadow_seed(&Point3D::ZERO, &light));
+        let mut rng2 =
+            Random::new(shadow_seed(&Point3D::new(0.0, 0.0, 0.1), &light));
+
+        for i in 0..16 {
+            let v1 = jittered_sphere(i, 16, &mut rng1);
+            let v2 = jittered_sphere(i, 16, &mut rng2);
+            // 単位球面上の i 番目の帯に含まれる
+            for v in [&v1, &v2].iter() {
+                assert!(approx_eq(1.0, v.magnitude()));
+                assert!(v.y <= 1.0 - 2.0 * i as FLOAT / 16.0);
+                assert!(1.0 - 2.0 * (i + 1) as FLOAT / 16.0 <= v.y);
+            }
+            assert_ne!(v1, v2);
+        }
+    }
+
     #[test]
     fn shade_hit_is_given_an_intersection_in_shadow() {
         let mut w = World::new();
@@ -443,36 +1622,211 @@ mod tests {
             object: &w.nodes[1],
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let comps = IntersectionState::new(&i, &r, &vec![]);
-        let c = w.shade_hit(&comps, 1);
+        let c = w.shade_hit(&comps, 1, &mut vec![]);
 
         assert_eq!(Color::new(0.1, 0.1, 0.1), c);
     }
 
+    #[test]
+    fn a_missed_ray_returns_the_background_color() {
+        let mut w = World::default_scene();
+        let background = Color::new(0.2, 0.4, 0.8);
+        *w.background_mut() = background;
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 1.0, 0.0),
+        );
+
+        assert_eq!(background, w.color_at(&r, 1));
+    }
+
+    #[test]
+    fn a_background_pattern_is_evaluated_with_the_ray_direction() {
+        let mut w = World::new();
+        *w.background_mut() = Color::RED;
+        *w.background_pattern_mut() =
+            Some(Box::new(GradientPattern::new(Color::BLACK, Color::WHITE)));
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(1.0, 0.0, 1.0),
+        );
+
+        // 方向は単位ベクトルとして評価される
+        let x = (2.0 as FLOAT).sqrt() / 2.0;
+        assert_eq!(Color::new(x, x, x), w.color_at(&r, 1));
+    }
+
+    #[test]
+    fn a_missing_ray_samples_the_environment_map() {
+        let solid = |c: Color| {
+            let mut canvas = Canvas::new(1, 1);
+            *canvas.color_at_mut(0, 0) = c;
+            canvas
+        };
+        let mut w = World::new();
+        *w.background_pattern_mut() =
+            Some(Box::new(GradientPattern::new(Color::BLACK, Color::WHITE)));
+        *w.environment_mut() = Some(CubeMapPattern::new(
+            solid(Color::new(1.0, 0.0, 0.0)),
+            solid(Color::new(0.0, 1.0, 0.0)),
+            solid(Color::new(0.0, 0.0, 1.0)),
+            solid(Color::new(1.0, 1.0, 0.0)),
+            solid(Color::new(0.0, 1.0, 1.0)),
+            solid(Color::new(1.0, 0.0, 1.0)),
+        ));
+
+        let origin = Point3D::new(0.0, 0.0, -5.0);
+        let r = Ray::new(origin.clone(), Vector3D::new(3.0, 1.0, -0.5));
+        assert_eq!(Color::new(0.0, 0.0, 1.0), w.color_at(&r, 1));
+        let r = Ray::new(origin.clone(), Vector3D::new(0.2, 0.5, 2.0));
+        assert_eq!(Color::new(0.0, 1.0, 0.0), w.color_at(&r, 1));
+        let r = Ray::new(origin, Vector3D::new(0.0, -1.0, 0.0));
+        assert_eq!(Color::new(1.0, 0.0, 1.0), w.color_at(&r, 1));
+    }
+
+    #[test]
+    fn schlick_can_be_turned_off() {
+        let mut w = World::new();
+        *w.background_pattern_mut() =
+            Some(Box::new(GradientPattern::new(Color::BLACK, Color::WHITE)));
+        let mut s = Node::new(Box::new(Sphere::new()));
+        let m = s.material_mut().unwrap();
+        m.ambient = 0.0;
+        m.diffuse = 0.0;
+        m.specular = 0.0;
+        m.reflective = 0.9;
+        m.transparency = 0.9;
+        m.refractive_index = 1.5;
+        w.add_node(s);
+        assert!(w.use_schlick());
+
+        // 球の縁付近をかすめる Ray
+        let r = Ray::new(
+            Point3D::new(0.95, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+        let with_schlick = w.color_at(&r, 5);
+        *w.use_schlick_mut() = false;
+        let without_schlick = w.color_at(&r, 5);
+
+        assert_ne!(with_schlick, without_schlick);
+    }
+
+    #[test]
+    fn fog_fades_distant_reflections() {
+        let reflection = |height: FLOAT| {
+            let mut w = World::new();
+            *w.fog_color_mut() = Color::RED;
+            *w.fog_density_mut() = 0.1;
+            let mut floor = Node::new(Box::new(Plane::new()));
+            let m = floor.material_mut().unwrap();
+            m.color = Color::BLACK;
+            m.ambient = 0.0;
+            m.diffuse = 0.0;
+            m.specular = 0.0;
+            m.reflective = 1.0;
+            w.add_node(floor);
+            let mut s = Node::new(Box::new(Sphere::new()));
+            s.set_transform(
+                &Transform::translation(0.0, height, 0.0)
+                    * &Transform::scaling(0.5, 0.5, 0.5),
+            );
+            s.material_mut().unwrap().emission = Color::WHITE;
+            w.add_node(s);
+
+            let r = Ray::new(
+                Point3D::new(0.0, 0.25, 0.0),
+                Vector3D::new(0.0, -1.0, 0.0),
+            );
+            w.color_at(&r, 5)
+        };
+
+        // 床から 0.5 の位置に映る球はほぼ白のまま。
+        // 床自体も 0.25 離れているため、その分の霧も加わる
+        let near = reflection(1.0);
+        let f = 1.0 - (-0.05 as FLOAT).exp();
+        let f_floor = 1.0 - (-0.025 as FLOAT).exp();
+        let gb = (1.0 - f) * (1.0 - f_floor);
+        assert_eq!(Color::new(1.0, gb, gb), near);
+
+        // 床から 99.5 の位置に映る球はほぼ霧の色になる
+        let far = reflection(100.0);
+        assert!(far.green < 0.001 && far.blue < 0.001);
+        assert!(approx_eq(1.0, far.red));
+    }
+
+    #[test]
+    fn fog_blends_primary_hits_by_distance() {
+        let mut w = World::new();
+        *w.fog_color_mut() = Color::new(0.5, 0.5, 0.5);
+        *w.fog_density_mut() = 0.05;
+        let mut s = Node::new(Box::new(Sphere::new()));
+        s.material_mut().unwrap().emission = Color::RED;
+        w.add_node(s);
+
+        let near = Ray::new(
+            Point3D::new(0.0, 0.0, -2.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+        let far = Ray::new(
+            Point3D::new(0.0, 0.0, -201.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+
+        let near_color = w.color_at(&near, 5);
+        assert!(0.9 < near_color.red && near_color.green < 0.05);
+        let far_color = w.color_at(&far, 5);
+        assert!((far_color.red - 0.5).abs() < 0.001);
+        assert!((far_color.green - 0.5).abs() < 0.001);
+
+        assert_eq!(Color::RED, w.apply_fog(Color::RED, 0.0));
+        *w.fog_density_mut() = 0.0;
+        assert_eq!(Color::RED, w.apply_fog(Color::RED, 1000.0));
+    }
+
+    #[test]
+    fn an_emissive_material_glows_without_lights() {
+        let mut w = World::new();
+        let mut s = Node::new(Box::new(Sphere::new()));
+        s.material_mut().unwrap().emission = Color::RED;
+        w.add_node(s);
+
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+        let c = w.color_at(&r, 1);
+
+        assert_eq!(Color::RED, c);
+    }
+
     #[test]
     fn the_reflected_color_for_a_non_reflective_material() {
-        let mut w = default_world();
+        let mut w = World::default_scene();
         let r =
             Ray::new(Point3D::new(0.0, 0.0, 0.0), Vector3D::new(0.0, 0.0, 1.0));
-        w.nodes[1].material_mut().ambient = 1.0;
+        w.nodes[1].material_mut().unwrap().ambient = 1.0;
         let i = Intersection {
             t: 1.0,
             object: &w.nodes[1],
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let comps = IntersectionState::new(&i, &r, &vec![]);
-        let color = w.reflected_color(&comps, 1);
+        let color = w.reflected_color(&comps, 1, &mut vec![]);
 
         assert_eq!(Color::BLACK, color);
     }
 
     #[test]
     fn the_reflected_color_for_a_reflective_material() {
-        let mut w = default_world();
+        let mut w = World::default_scene();
         let mut node = Node::new(Box::new(Plane::new()));
-        node.material_mut().reflective = 0.5;
+        node.material_mut().unwrap().reflective = 0.5;
         node.set_transform(Transform::translation(0.0, -1.0, 0.0));
         w.add_node(node);
         let r = Ray::new(
@@ -488,18 +1842,50 @@ mod tests {
             object: &w.nodes[2],
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let comps = IntersectionState::new(&i, &r, &vec![]);
-        let color = w.reflected_color(&comps, 1);
+        let color = w.reflected_color(&comps, 1, &mut vec![]);
 
         assert_eq!(Color::new(0.19033, 0.23791, 0.14274), color);
     }
 
+    #[test]
+    fn the_reflected_color_is_tinted_by_the_reflect_color() {
+        let mut w = World::default_scene();
+        let mut node = Node::new(Box::new(Plane::new()));
+        node.material_mut().unwrap().reflective = 0.5;
+        node.material_mut().unwrap().reflect_color =
+            Some(Color::new(1.0, 0.8, 0.0));
+        node.set_transform(Transform::translation(0.0, -1.0, 0.0));
+        w.add_node(node);
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -3.0),
+            Vector3D::new(
+                0.0,
+                -2f32.sqrt() as FLOAT / 2.0,
+                2f32.sqrt() as FLOAT / 2.0,
+            ),
+        );
+        let i = Intersection {
+            t: 2f32.sqrt() as FLOAT,
+            object: &w.nodes[2],
+            u: 0.0,
+            v: 0.0,
+            front_face: true,
+        };
+        let comps = IntersectionState::new(&i, &r, &vec![]);
+        let color = w.reflected_color(&comps, 1, &mut vec![]);
+
+        // 金色の反射では青の成分がなくなる
+        assert_eq!(Color::new(0.19033, 0.19033, 0.0), color);
+    }
+
     #[test]
     fn shade_hit_with_a_reflective_material() {
-        let mut w = default_world();
+        let mut w = World::default_scene();
         let mut node = Node::new(Box::new(Plane::new()));
-        node.material_mut().reflective = 0.5;
+        node.material_mut().unwrap().reflective = 0.5;
         node.set_transform(Transform::translation(0.0, -1.0, 0.0));
         w.add_node(node);
         let r = Ray::new(
@@ -515,9 +1901,10 @@ mod tests {
             object: &w.nodes[2],
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let comps = IntersectionState::new(&i, &r, &vec![]);
-        let color = w.shade_hit(&comps, 1);
+        let color = w.shade_hit(&comps, 1, &mut vec![]);
 
         assert_eq!(Color::new(0.87676, 0.92434, 0.82918), color);
     }
@@ -528,12 +1915,12 @@ mod tests {
         w.add_light(Light::new(Point3D::new(0.0, 0.0, 0.0), Color::WHITE));
 
         let mut lower = Node::new(Box::new(Plane::new()));
-        lower.material_mut().reflective = 1.0;
+        lower.material_mut().unwrap().reflective = 1.0;
         lower.set_transform(Transform::translation(0.0, -1.0, 0.0));
         w.add_node(lower);
 
         let mut upper = Node::new(Box::new(Plane::new()));
-        upper.material_mut().reflective = 1.0;
+        upper.material_mut().unwrap().reflective = 1.0;
         upper.set_transform(Transform::translation(0.0, 1.0, 0.0));
         w.add_node(upper);
 
@@ -545,9 +1932,9 @@ mod tests {
 
     #[test]
     fn the_reflected_color_at_the_maximum_recursive_depth() {
-        let mut w = default_world();
+        let mut w = World::default_scene();
         let mut node = Node::new(Box::new(Plane::new()));
-        node.material_mut().reflective = 0.5;
+        node.material_mut().unwrap().reflective = 0.5;
         node.set_transform(Transform::translation(0.0, -1.0, 0.0));
 
         w.add_node(node);
@@ -564,16 +1951,17 @@ mod tests {
             object: &w.nodes[2],
             u: 0.0,
             v: 0.0,
+            front_face: true,
         };
         let comps = IntersectionState::new(&i, &r, &vec![]);
-        let color = w.reflected_color(&comps, 0);
+        let color = w.reflected_color(&comps, 0, &mut vec![]);
 
         assert_eq!(Color::BLACK, color);
     }
 
     #[test]
     fn the_refracted_color_with_an_opeque_surface() {
-        let w = default_world();
+        let w = World::default_scene();
         let shape = &w.nodes[0];
         let r = Ray::new(
             Point3D::new(0.0, 0.0, -5.0),
@@ -585,24 +1973,26 @@ mod tests {
                 object: shape,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
             Intersection {
                 t: 6.0,
                 object: shape,
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
         ];
         let comps = IntersectionState::new(&xs[0], &r, &xs);
-        let c = w.refracted_color(&comps, 5);
+        let c = w.refracted_color(&comps, 5, &mut vec![]);
         assert_eq!(Color::BLACK, c);
     }
 
     #[test]
     fn the_refracted_color_at_the_maximum_recursive_depth() {
-        let mut w = default_world();
-        w.nodes[0].material_mut().transparency = 1.0;
-        w.nodes[0].material_mut().refractive_index = 1.5;
+        let mut w = World::default_scene();
+        w.nodes[0].material_mut().unwrap().transparency = 1.0;
+        w.nodes[0].material_mut().unwrap().refractive_index = 1.5;
         let r = Ray::new(
             Point3D::new(0.0, 0.0, -5.0),
             Vector3D::new(0.0, 0.0, 1.0),
@@ -613,24 +2003,26 @@ mod tests {
                 object: &w.nodes[0],
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
             Intersection {
                 t: 6.0,
                 object: &w.nodes[0],
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
         ];
         let comps = IntersectionState::new(&xs[0], &r, &xs);
-        let c = w.refracted_color(&comps, 0);
+        let c = w.refracted_color(&comps, 0, &mut vec![]);
         assert_eq!(Color::BLACK, c);
     }
 
     #[test]
     fn the_refracted_color_under_total_internal_reflection() {
-        let mut w = default_world();
-        w.nodes[0].material_mut().transparency = 1.0;
-        w.nodes[0].material_mut().refractive_index = 1.5;
+        let mut w = World::default_scene();
+        w.nodes[0].material_mut().unwrap().transparency = 1.0;
+        w.nodes[0].material_mut().unwrap().refractive_index = 1.5;
         let r = Ray::new(
             Point3D::new(0.0, 0.0, 2f32.sqrt() as FLOAT / 2.0),
             Vector3D::new(0.0, 1.0, 0.0),
@@ -641,30 +2033,32 @@ mod tests {
                 object: &w.nodes[0],
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
             Intersection {
                 t: 2f32.sqrt() as FLOAT / 2.0,
                 object: &w.nodes[0],
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
         ];
 
         let comps = IntersectionState::new(&xs[1], &r, &xs);
-        let c = w.refracted_color(&comps, 5);
+        let c = w.refracted_color(&comps, 5, &mut vec![]);
 
         assert_eq!(Color::BLACK, c);
     }
 
     #[test]
     fn the_refracted_color_with_a_refracted_ray() {
-        let mut w = default_world();
-        w.nodes[0].material_mut().ambient = 1.0;
-        *w.nodes[0].material_mut().pattern_mut() =
+        let mut w = World::default_scene();
+        w.nodes[0].material_mut().unwrap().ambient = 1.0;
+        *w.nodes[0].material_mut().unwrap().pattern_mut() =
             Some(Box::new(TestPattern::new()));
 
-        w.nodes[1].material_mut().transparency = 1.0;
-        w.nodes[1].material_mut().refractive_index = 1.5;
+        w.nodes[1].material_mut().unwrap().transparency = 1.0;
+        w.nodes[1].material_mut().unwrap().refractive_index = 1.5;
 
         let r =
             Ray::new(Point3D::new(0.0, 0.0, 0.1), Vector3D::new(0.0, 1.0, 0.0));
@@ -674,45 +2068,143 @@ mod tests {
                 object: &w.nodes[0],
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
             Intersection {
                 t: -0.4899,
                 object: &w.nodes[1],
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
             Intersection {
                 t: 0.4899,
                 object: &w.nodes[1],
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
             Intersection {
                 t: 0.9899,
                 object: &w.nodes[0],
                 u: 0.0,
                 v: 0.0,
+                front_face: true,
             },
         ];
 
         let comps = IntersectionState::new(&xs[2], &r, &xs);
-        let c = w.refracted_color(&comps, 5);
+        let c = w.refracted_color(&comps, 5, &mut vec![]);
         assert_eq!(Color::new(0.0, 0.99887, 0.047218), c);
     }
 
+    #[test]
+    fn the_refracted_ray_does_not_hit_the_same_surface_again() {
+        let mut w = World::new();
+        *w.background_mut() = Color::WHITE;
+        // 補間された法線が面に対して大きく傾いた薄いガラス
+        let mut n = Vector3D::new(0.95, 0.0, 0.312);
+        n.normalize();
+        let mut glass = Node::new(Box::new(SmoothTriangle::new(
+            Point3D::new(-10.0, -10.0, 0.0),
+            Point3D::new(10.0, -10.0, 0.0),
+            Point3D::new(0.0, 10.0, 0.0),
+            n.clone(),
+            n.clone(),
+            n,
+        )));
+        *glass.material_mut().unwrap() = Material::glass();
+        // 反射光で誤りが隠れないように、反射しないようにする
+        glass.material_mut().unwrap().reflective = 0.0;
+        w.add_node(glass);
+
+        // 面をかすめるように入射すると、屈折した Ray は under_point から
+        // 面の手前側へ向かう
+        let r = Ray::new(
+            Point3D::new(0.96, 0.0, -0.28),
+            Vector3D::new(-0.96, 0.0, 0.28),
+        );
+        let comps = w.trace(&r).unwrap();
+        assert!(comps.under_point.z < comps.point.z);
+
+        assert_eq!(Color::WHITE, w.refracted_color(&comps, 5, &mut vec![]));
+    }
+
+    #[test]
+    fn a_half_opaque_sheet_blends_its_color_with_the_background() {
+        let mut w = World::new();
+        *w.background_mut() = Color::WHITE;
+        w.add_light(Light::new(
+            Point3D::new(0.0, 0.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let mut sheet = Node::new(Box::new(Plane::new()));
+        sheet.set_transform(Transform::rotation_x(
+            std::f64::consts::FRAC_PI_2 as FLOAT,
+        ));
+        let m = sheet.material_mut().unwrap();
+        m.color = Color::RED;
+        m.ambient = 1.0;
+        m.diffuse = 0.0;
+        m.specular = 0.0;
+        m.opacity = 0.5;
+        m.refractive_index = 1.0;
+        w.add_node(sheet);
+
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+        assert_eq!(Color::new(1.0, 0.5, 0.5), w.color_at(&r, 5));
+    }
+
+    #[test]
+    fn seeing_through_many_stacked_sheets_is_bounded_by_the_depth() {
+        let mut w = World::new();
+        *w.background_mut() = Color::WHITE;
+        w.add_light(Light::new(
+            Point3D::new(0.0, 0.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        for i in 0..1000 {
+            let mut sheet = Node::new(Box::new(Plane::new()));
+            sheet.set_transform(
+                &Transform::translation(0.0, 0.0, i as FLOAT * 0.001)
+                    * &Transform::rotation_x(
+                        std::f64::consts::FRAC_PI_2 as FLOAT,
+                    ),
+            );
+            let m = sheet.material_mut().unwrap();
+            m.color = Color::RED;
+            m.ambient = 1.0;
+            m.diffuse = 0.0;
+            m.specular = 0.0;
+            m.opacity = 0.5;
+            m.refractive_index = 1.0;
+            w.add_node(sheet);
+        }
+
+        let r = Ray::new(
+            Point3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+        );
+        // 手前の 6 枚のみが混ざり、その先は黒となる
+        assert_eq!(Color::new(0.984375, 0.0, 0.0), w.color_at(&r, 5));
+    }
+
     #[test]
     fn shadow_hit_with_a_transparent_material() {
-        let mut w = default_world();
+        let mut w = World::default_scene();
 
         let mut floor = Node::new(Box::new(Plane::new()));
         floor.set_transform(Transform::translation(0.0, -1.0, 0.0));
-        floor.material_mut().transparency = 0.5;
-        floor.material_mut().refractive_index = 1.5;
+        floor.material_mut().unwrap().transparency = 0.5;
+        floor.material_mut().unwrap().refractive_index = 1.5;
         w.add_node(floor);
 
         let mut ball = Node::new(Box::new(Sphere::new()));
-        ball.material_mut().color = Color::new(1.0, 0.0, 0.0);
-        ball.material_mut().ambient = 0.5;
+        ball.material_mut().unwrap().color = Color::new(1.0, 0.0, 0.0);
+        ball.material_mut().unwrap().ambient = 0.5;
         ball.set_transform(Transform::translation(0.0, -3.5, -0.5));
         w.add_node(ball);
 
@@ -729,16 +2221,46 @@ mod tests {
             object: &w.nodes[2],
             u: 0.0,
             v: 0.0,
+            front_face: true,
         }];
         let comps = IntersectionState::new(&xs[0], &r, &xs);
-        let color = w.shade_hit(&comps, 5);
+        let color = w.shade_hit(&comps, 5, &mut vec![]);
+
+        // 透明な物体では反射率によらず Schlick の近似で屈折光が減衰する
+        assert_eq!(Color::new(0.92591, 0.68642, 0.68642), color);
+    }
+
+    #[test]
+    fn a_transparent_surface_refracts_less_light_at_glancing_angles() {
+        let mut w = World::new();
+        *w.background_mut() = Color::WHITE;
+        let mut water = Node::new(Box::new(Plane::new()));
+        water.material_mut().unwrap().ambient = 0.0;
+        water.material_mut().unwrap().diffuse = 0.0;
+        water.material_mut().unwrap().transparency = 1.0;
+        water.material_mut().unwrap().refractive_index = 1.333;
+        w.add_node(water);
+
+        let steep = Ray::new(
+            Point3D::new(0.0, 1.0, 0.0),
+            Vector3D::new(0.0, -1.0, 0.0),
+        );
+        let glancing = Ray::new(
+            Point3D::new(0.0, 1.0, 0.0),
+            Vector3D::new(0.0, -0.28, 0.96),
+        );
+        let steep_color = w.color_at(&steep, 5);
+        let glancing_color = w.color_at(&glancing, 5);
 
-        assert_eq!(Color::new(0.93642, 0.68642, 0.68642), color);
+        // 水面の背後は白い背景なので、色は 1 - 反射率 になる
+        let reflectance = w.trace(&glancing).unwrap().schlick();
+        assert!(approx_eq(1.0 - reflectance, glancing_color.red));
+        assert!(glancing_color.red < steep_color.red);
     }
 
     #[test]
     fn shade_hit_with_a_reflective_transparent_material() {
-        let mut w = default_world();
+        let mut w = World::default_scene();
         let r = Ray::new(
             Point3D::new(0.0, 0.0, -3.0),
             Vector3D::new(
@@ -750,14 +2272,14 @@ mod tests {
 
         let mut floor = Node::new(Box::new(Plane::new()));
         floor.set_transform(Transform::translation(0.0, -1.0, 0.0));
-        floor.material_mut().reflective = 0.5;
-        floor.material_mut().transparency = 0.5;
-        floor.material_mut().refractive_index = 1.5;
+        floor.material_mut().unwrap().reflective = 0.5;
+        floor.material_mut().unwrap().transparency = 0.5;
+        floor.material_mut().unwrap().refractive_index = 1.5;
         w.add_node(floor);
 
         let mut ball = Node::new(Box::new(Sphere::new()));
-        ball.material_mut().color = Color::new(1.0, 0.0, 0.0);
-        ball.material_mut().ambient = 0.5;
+        ball.material_mut().unwrap().color = Color::new(1.0, 0.0, 0.0);
+        ball.material_mut().unwrap().ambient = 0.5;
         ball.set_transform(Transform::translation(0.0, -3.5, -0.5));
         w.add_node(ball);
 
@@ -766,9 +2288,10 @@ mod tests {
             object: &w.nodes[2],
             u: 0.0,
             v: 0.0,
+            front_face: true,
         }];
         let comps = IntersectionState::new(&xs[0], &r, &xs);
-        let color = w.shade_hit(&comps, 5);
+        let color = w.shade_hit(&comps, 5, &mut vec![]);
 
         assert_eq!(Color::new(0.93391, 0.69643, 0.69243), color);
     }